@@ -2,4 +2,7 @@ mod data_channel;
 mod peer_connection;
 pub mod signaling;
 
-pub use self::{data_channel::DataChannel, peer_connection::PeerConnection};
+pub use self::{
+    data_channel::DataChannel,
+    peer_connection::{PeerConnection, PROTOCOL},
+};
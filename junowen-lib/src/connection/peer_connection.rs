@@ -70,7 +70,7 @@ impl Drop for PeerConnection {
     }
 }
 
-const PROTOCOL: &str = "JUNOWEN/1.1";
+pub const PROTOCOL: &str = "JUNOWEN/1.2";
 
 impl PeerConnection {
     pub async fn new(timeout: Duration) -> Result<Self> {
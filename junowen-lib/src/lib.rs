@@ -9,7 +9,10 @@ pub mod lang;
 mod macros;
 #[cfg(target_os = "windows")]
 mod memory_accessors;
+pub mod replay;
+pub mod rollback;
 pub mod signaling_server;
+pub mod structs;
 #[cfg(target_os = "windows")]
 mod th19;
 #[cfg(target_os = "windows")]
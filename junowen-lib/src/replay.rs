@@ -0,0 +1,251 @@
+pub mod analytics;
+mod legacy;
+#[cfg(test)]
+mod tests;
+
+use std::io::{Read, Write};
+
+use bytes::{Buf, BufMut, BytesMut};
+use flate2::{
+    write::{DeflateDecoder, DeflateEncoder},
+    Compression, Crc,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+pub use legacy::{LegacyInputList, LegacyReplayFile};
+
+pub const MAGIC: [u8; 4] = *b"JNRP";
+pub const VERSION: u16 = 2;
+/// ネット対戦の記録がない版
+const VERSION_WITHOUT_NETPLAY: u16 = 1;
+
+/// magic + version + checksum + 本体の長さ
+const HEADER_SIZE: usize = 4 + 2 + 4 + 4;
+
+#[derive(Debug, Error)]
+pub enum ReplayError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("not a replay file")]
+    InvalidMagic,
+    #[error("unsupported replay version: {0}")]
+    UnsupportedVersion(u16),
+    #[error("checksum mismatch")]
+    ChecksumMismatch,
+    #[error("invalid replay data: {0}")]
+    InvalidData(String),
+}
+
+fn invalid_data(msg: impl ToString) -> ReplayError {
+    ReplayError::InvalidData(msg.to_string())
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct ReplayGameSettings {
+    pub common: u32,
+    pub p1: u32,
+    pub p2: u32,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct ReplayMetadata {
+    pub p1_name: String,
+    pub p2_name: String,
+    /// UNIX 時間 (秒)。旧形式から変換したものは不明
+    pub started_at: Option<i64>,
+    pub difficulty: u8,
+    pub player_matchup: u8,
+    pub p1_character: u8,
+    pub p1_card: u8,
+    pub p2_character: u8,
+    pub p2_card: u8,
+    pub game_settings: ReplayGameSettings,
+}
+
+/// ラウンド開始時の乱数のシード
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RoundSeeds {
+    pub seed1: u32,
+    pub seed2: u32,
+    pub seed3: u32,
+    pub seed4: u32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ReplayRound {
+    pub seeds: RoundSeeds,
+    pub inputs: Vec<(u16, u16)>,
+    /// ネット対戦でなければ None
+    pub netplay: Option<RoundNetplay>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct RoundNetplay {
+    /// ディレイが変わったフレームと変わった後のディレイ。最初の要素はラウンド開始時のもの
+    pub delays: Vec<(u32, u8)>,
+    /// 相手の入力を待ったフレームと待った時間 (ミリ秒)
+    pub stalls: Vec<(u32, u32)>,
+}
+
+/// ファイルの構造 (数値はすべてリトルエンディアン)
+///
+/// - magic "JNRP", version: u16, 本体の CRC32: u32, 本体の長さ: u32
+/// - 本体
+///   - メタデータの長さ: u32, メタデータ (名前付きの MessagePack)
+///   - ラウンド数: u32
+///   - ラウンドごとに seed1..4: u32, フレーム数: u32, 入力の長さ: u32,
+///     入力 (p1 の列, p2 の列の順に並べた u16 を deflate したもの),
+///     ネット対戦の記録の長さ: u32 (無ければ 0), ネット対戦の記録 (名前付きの MessagePack)
+///
+/// version 1 にはネット対戦の記録がない
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Replay {
+    pub metadata: ReplayMetadata,
+    pub rounds: Vec<ReplayRound>,
+}
+
+impl Replay {
+    pub fn read_from(reader: &mut impl Read) -> Result<Self, ReplayError> {
+        let mut header = [0u8; HEADER_SIZE];
+        reader.read_exact(&mut header)?;
+        let mut header = &header[..];
+        if header[..4] != MAGIC {
+            return Err(ReplayError::InvalidMagic);
+        }
+        header.advance(4);
+        let version = header.get_u16_le();
+        if version != VERSION && version != VERSION_WITHOUT_NETPLAY {
+            return Err(ReplayError::UnsupportedVersion(version));
+        }
+        let checksum = header.get_u32_le();
+        let body_len = header.get_u32_le() as usize;
+
+        let mut body = Vec::new();
+        reader.take(body_len as u64).read_to_end(&mut body)?;
+        if body.len() != body_len {
+            return Err(invalid_data("unexpected end of file"));
+        }
+        let mut crc = Crc::new();
+        crc.update(&body);
+        if crc.sum() != checksum {
+            return Err(ReplayError::ChecksumMismatch);
+        }
+        Self::decode_body(&body, version)
+    }
+
+    pub fn write_to(&self, writer: &mut impl Write) -> Result<(), ReplayError> {
+        let body = self.encode_body()?;
+        let mut crc = Crc::new();
+        crc.update(&body);
+        let mut buf = BytesMut::with_capacity(HEADER_SIZE + body.len());
+        buf.put_slice(&MAGIC);
+        buf.put_u16_le(VERSION);
+        buf.put_u32_le(crc.sum());
+        buf.put_u32_le(body.len() as u32);
+        buf.put_slice(&body);
+        writer.write_all(&buf)?;
+        Ok(())
+    }
+
+    fn encode_body(&self) -> Result<Vec<u8>, ReplayError> {
+        let mut buf = BytesMut::new();
+        let metadata = rmp_serde::to_vec_named(&self.metadata).map_err(invalid_data)?;
+        buf.put_u32_le(metadata.len() as u32);
+        buf.put_slice(&metadata);
+        buf.put_u32_le(self.rounds.len() as u32);
+        for round in &self.rounds {
+            buf.put_u32_le(round.seeds.seed1);
+            buf.put_u32_le(round.seeds.seed2);
+            buf.put_u32_le(round.seeds.seed3);
+            buf.put_u32_le(round.seeds.seed4);
+            buf.put_u32_le(round.inputs.len() as u32);
+            let inputs = compress_inputs(&round.inputs)?;
+            buf.put_u32_le(inputs.len() as u32);
+            buf.put_slice(&inputs);
+            let netplay = round
+                .netplay
+                .as_ref()
+                .map(rmp_serde::to_vec_named)
+                .transpose()
+                .map_err(invalid_data)?
+                .unwrap_or_default();
+            buf.put_u32_le(netplay.len() as u32);
+            buf.put_slice(&netplay);
+        }
+        Ok(buf.to_vec())
+    }
+
+    fn decode_body(mut body: &[u8], version: u16) -> Result<Self, ReplayError> {
+        let metadata_len = get_u32(&mut body)? as usize;
+        let metadata = take(&mut body, metadata_len)?;
+        let metadata = rmp_serde::from_slice(metadata).map_err(invalid_data)?;
+        let round_count = get_u32(&mut body)?;
+        let mut rounds = Vec::new();
+        for _ in 0..round_count {
+            let seeds = RoundSeeds {
+                seed1: get_u32(&mut body)?,
+                seed2: get_u32(&mut body)?,
+                seed3: get_u32(&mut body)?,
+                seed4: get_u32(&mut body)?,
+            };
+            let frames = get_u32(&mut body)? as usize;
+            let inputs_len = get_u32(&mut body)? as usize;
+            let inputs = decompress_inputs(take(&mut body, inputs_len)?, frames)?;
+            let netplay = if version == VERSION_WITHOUT_NETPLAY {
+                None
+            } else {
+                let netplay_len = get_u32(&mut body)? as usize;
+                let netplay = take(&mut body, netplay_len)?;
+                (!netplay.is_empty())
+                    .then(|| rmp_serde::from_slice(netplay))
+                    .transpose()
+                    .map_err(invalid_data)?
+            };
+            rounds.push(ReplayRound {
+                seeds,
+                inputs,
+                netplay,
+            });
+        }
+        if !body.is_empty() {
+            return Err(invalid_data("trailing bytes"));
+        }
+        Ok(Self { metadata, rounds })
+    }
+}
+
+fn take<'a>(buf: &mut &'a [u8], len: usize) -> Result<&'a [u8], ReplayError> {
+    if buf.len() < len {
+        return Err(invalid_data("unexpected end of data"));
+    }
+    let (head, tail) = buf.split_at(len);
+    *buf = tail;
+    Ok(head)
+}
+
+fn get_u32(buf: &mut &[u8]) -> Result<u32, ReplayError> {
+    Ok(take(buf, 4)?.get_u32_le())
+}
+
+fn compress_inputs(inputs: &[(u16, u16)]) -> Result<Vec<u8>, ReplayError> {
+    let mut raw = BytesMut::with_capacity(inputs.len() * 4);
+    inputs.iter().for_each(|&(p1, _)| raw.put_u16_le(p1));
+    inputs.iter().for_each(|&(_, p2)| raw.put_u16_le(p2));
+    let mut e = DeflateEncoder::new(Vec::new(), Compression::best());
+    e.write_all(&raw)?;
+    Ok(e.finish()?)
+}
+
+fn decompress_inputs(compressed: &[u8], frames: usize) -> Result<Vec<(u16, u16)>, ReplayError> {
+    let mut d = DeflateDecoder::new(Vec::new());
+    d.write_all(compressed)?;
+    let raw = d.finish()?;
+    if raw.len() != frames * 4 {
+        return Err(invalid_data("input length mismatch"));
+    }
+    let (mut p1, mut p2) = raw.split_at(frames * 2);
+    Ok((0..frames)
+        .map(|_| (p1.get_u16_le(), p2.get_u16_le()))
+        .collect())
+}
@@ -0,0 +1,182 @@
+use std::collections::BTreeMap;
+
+use flagset::FlagSet;
+
+use crate::structs::input_devices::InputFlags;
+
+use super::{Replay, RoundNetplay};
+
+const FRAMES_PER_SECOND: f64 = 60.0;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ButtonUsage {
+    /// 押していたフレーム数
+    pub frames: u32,
+    /// 押した回数
+    pub presses: u32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PlayerAnalytics {
+    pub frames: u32,
+    /// 1 秒あたりにボタンや方向キーを押した回数
+    pub inputs_per_second: f64,
+    pub shot: ButtonUsage,
+    pub charge: ButtonUsage,
+    pub bomb: ButtonUsage,
+    pub slow: ButtonUsage,
+    /// CHARGE を押し続けたフレーム数
+    pub charge_holds: Vec<u32>,
+    /// 方向ごとのフレーム数。movement[上, 無し, 下][左, 無し, 右]
+    pub movement: [[u32; 3]; 3],
+}
+
+impl PlayerAnalytics {
+    pub fn average_charge_hold(&self) -> Option<f64> {
+        if self.charge_holds.is_empty() {
+            return None;
+        }
+        Some(self.charge_holds.iter().sum::<u32>() as f64 / self.charge_holds.len() as f64)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NetplayAnalytics {
+    /// ディレイごとのフレーム数
+    pub delays: BTreeMap<u8, u32>,
+    /// 相手の入力を待ったフレーム数
+    pub stalled_frames: u32,
+    /// 相手の入力を待った時間の合計 (ミリ秒)
+    pub total_stall_ms: u32,
+    pub max_stall_ms: u32,
+    pub frames: u32,
+}
+
+impl NetplayAnalytics {
+    pub fn stall_rate(&self) -> f64 {
+        if self.frames == 0 {
+            return 0.0;
+        }
+        self.stalled_frames as f64 / self.frames as f64
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ReplayAnalytics {
+    pub p1: PlayerAnalytics,
+    pub p2: PlayerAnalytics,
+    /// ネット対戦の記録があるラウンドのみを集計する
+    pub netplay: Option<NetplayAnalytics>,
+}
+
+/// 1 プレイヤーの入力を 1 ラウンド分ずつ集計する
+#[derive(Default)]
+struct PlayerAccumulator {
+    analytics: PlayerAnalytics,
+    presses: u32,
+    prev: FlagSet<InputFlags>,
+    charge_hold: u32,
+}
+
+impl PlayerAccumulator {
+    fn add_frame(&mut self, input: u16) {
+        let current = FlagSet::<InputFlags>::new_truncated(input as u32);
+        let pressed = current - self.prev;
+        self.presses += pressed.into_iter().count() as u32;
+        let analytics = &mut self.analytics;
+        analytics.frames += 1;
+        for (flag, usage) in [
+            (InputFlags::SHOT, &mut analytics.shot),
+            (InputFlags::CHARGE, &mut analytics.charge),
+            (InputFlags::BOMB, &mut analytics.bomb),
+            (InputFlags::SLOW, &mut analytics.slow),
+        ] {
+            if current.contains(flag) {
+                usage.frames += 1;
+            }
+            if pressed.contains(flag) {
+                usage.presses += 1;
+            }
+        }
+        let axis =
+            |negative, positive| match (current.contains(negative), current.contains(positive)) {
+                (true, false) => 0,
+                (false, true) => 2,
+                _ => 1,
+            };
+        let row = axis(InputFlags::UP, InputFlags::DOWN);
+        let column = axis(InputFlags::LEFT, InputFlags::RIGHT);
+        analytics.movement[row][column] += 1;
+        if current.contains(InputFlags::CHARGE) {
+            self.charge_hold += 1;
+        } else {
+            self.end_charge_hold();
+        }
+        self.prev = current;
+    }
+
+    fn end_charge_hold(&mut self) {
+        if self.charge_hold > 0 {
+            self.analytics.charge_holds.push(self.charge_hold);
+            self.charge_hold = 0;
+        }
+    }
+
+    fn end_round(&mut self) {
+        self.end_charge_hold();
+        self.prev = FlagSet::default();
+    }
+
+    fn finish(mut self) -> PlayerAnalytics {
+        self.end_round();
+        if self.analytics.frames > 0 {
+            self.analytics.inputs_per_second =
+                self.presses as f64 / self.analytics.frames as f64 * FRAMES_PER_SECOND;
+        }
+        self.analytics
+    }
+}
+
+fn add_netplay(analytics: &mut NetplayAnalytics, netplay: &RoundNetplay, frames: u32) {
+    analytics.frames += frames;
+    for (i, &(start, delay)) in netplay.delays.iter().enumerate() {
+        let end = netplay
+            .delays
+            .get(i + 1)
+            .map(|&(frame, _)| frame)
+            .unwrap_or(frames)
+            .min(frames);
+        *analytics.delays.entry(delay).or_default() += end.saturating_sub(start);
+    }
+    for &(_, ms) in &netplay.stalls {
+        analytics.stalled_frames += 1;
+        analytics.total_stall_ms += ms;
+        analytics.max_stall_ms = analytics.max_stall_ms.max(ms);
+    }
+}
+
+pub fn analyze(replay: &Replay) -> ReplayAnalytics {
+    let mut p1 = PlayerAccumulator::default();
+    let mut p2 = PlayerAccumulator::default();
+    let mut netplay: Option<NetplayAnalytics> = None;
+    for round in &replay.rounds {
+        for &(p1_input, p2_input) in &round.inputs {
+            p1.add_frame(p1_input);
+            p2.add_frame(p2_input);
+        }
+        p1.end_round();
+        p2.end_round();
+        if let Some(round_netplay) = &round.netplay {
+            add_netplay(
+                netplay.get_or_insert_with(Default::default),
+                round_netplay,
+                round.inputs.len() as u32,
+            );
+        }
+    }
+    ReplayAnalytics {
+        p1: p1.finish(),
+        p2: p2.finish(),
+        netplay,
+    }
+}
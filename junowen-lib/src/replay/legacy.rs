@@ -0,0 +1,178 @@
+use std::io::{Read, Write};
+
+use bytes::{Buf, BufMut, BytesMut};
+
+use super::{
+    invalid_data, Replay, ReplayError, ReplayGameSettings, ReplayMetadata, ReplayRound, RoundSeeds,
+};
+
+/// PlayerMatchup::HumanVsCpu
+const HUMAN_VS_CPU: u8 = 1;
+/// 旧実装の読み込みは 13 バイトとしていたが、書き込まれるのは 17 バイト
+const LEGACY_HEADER_SIZE: usize = 17;
+
+pub enum LegacyInputList {
+    HumanVsHuman(Vec<(u16, u16)>),
+    HumanVsCpu(Vec<u16>),
+}
+
+/// th19replayplayer の ReplayFile (ヘッダーと入力の列)
+pub struct LegacyReplayFile {
+    pub rand_seed1: u32,
+    pub rand_seed2: u32,
+    pub difficulty: u8,
+    pub player_matchup: u8,
+    pub battle_settings: ReplayGameSettings,
+    pub p1_character: u8,
+    pub p1_card: u8,
+    pub p2_character: u8,
+    pub p2_card: u8,
+    pub inputs: LegacyInputList,
+}
+
+impl LegacyReplayFile {
+    pub fn read_from(reader: &mut impl Read) -> Result<Self, ReplayError> {
+        let mut header = [0u8; LEGACY_HEADER_SIZE];
+        reader.read_exact(&mut header)?;
+        let mut header = &header[..];
+        let rand_seed1 = header.get_u32_le();
+        let rand_seed2 = header.get_u32_le();
+        let difficulty = header.get_u8();
+        let player_matchup = header.get_u8();
+        if player_matchup > 3 {
+            return Err(invalid_data(format!(
+                "unknown player matchup: {}",
+                player_matchup
+            )));
+        }
+        let battle_settings = ReplayGameSettings {
+            common: header.get_u8() as u32,
+            p1: header.get_u8() as u32,
+            p2: header.get_u8() as u32,
+        };
+        let p1_character = header.get_u8();
+        let p1_card = header.get_u8();
+        let p2_character = header.get_u8();
+        let p2_card = header.get_u8();
+
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest)?;
+        let inputs = if player_matchup == HUMAN_VS_CPU {
+            if rest.len() % 2 != 0 {
+                return Err(invalid_data("truncated input"));
+            }
+            LegacyInputList::HumanVsCpu(rest.chunks(2).map(|mut x| x.get_u16_le()).collect())
+        } else {
+            if rest.len() % 4 != 0 {
+                return Err(invalid_data("truncated input"));
+            }
+            LegacyInputList::HumanVsHuman(
+                rest.chunks(4)
+                    .map(|mut x| (x.get_u16_le(), x.get_u16_le()))
+                    .collect(),
+            )
+        };
+        Ok(Self {
+            rand_seed1,
+            rand_seed2,
+            difficulty,
+            player_matchup,
+            battle_settings,
+            p1_character,
+            p1_card,
+            p2_character,
+            p2_card,
+            inputs,
+        })
+    }
+
+    pub fn write_to(&self, writer: &mut impl Write) -> Result<(), ReplayError> {
+        let mut buf = BytesMut::new();
+        buf.put_u32_le(self.rand_seed1);
+        buf.put_u32_le(self.rand_seed2);
+        buf.put_u8(self.difficulty);
+        buf.put_u8(self.player_matchup);
+        buf.put_u8(self.battle_settings.common as u8);
+        buf.put_u8(self.battle_settings.p1 as u8);
+        buf.put_u8(self.battle_settings.p2 as u8);
+        buf.put_u8(self.p1_character);
+        buf.put_u8(self.p1_card);
+        buf.put_u8(self.p2_character);
+        buf.put_u8(self.p2_card);
+        match &self.inputs {
+            LegacyInputList::HumanVsHuman(vec) => vec.iter().for_each(|&(p1, p2)| {
+                buf.put_u16_le(p1);
+                buf.put_u16_le(p2);
+            }),
+            LegacyInputList::HumanVsCpu(vec) => vec.iter().for_each(|&p1| buf.put_u16_le(p1)),
+        }
+        writer.write_all(&buf)?;
+        Ok(())
+    }
+}
+
+/// 旧形式は 1 ラウンドのみで、シードも 2 つしか記録していない
+impl From<LegacyReplayFile> for Replay {
+    fn from(legacy: LegacyReplayFile) -> Self {
+        let inputs = match legacy.inputs {
+            LegacyInputList::HumanVsHuman(vec) => vec,
+            LegacyInputList::HumanVsCpu(vec) => vec.into_iter().map(|p1| (p1, 0)).collect(),
+        };
+        Self {
+            metadata: ReplayMetadata {
+                p1_name: String::new(),
+                p2_name: String::new(),
+                started_at: None,
+                difficulty: legacy.difficulty,
+                player_matchup: legacy.player_matchup,
+                p1_character: legacy.p1_character,
+                p1_card: legacy.p1_card,
+                p2_character: legacy.p2_character,
+                p2_card: legacy.p2_card,
+                game_settings: legacy.battle_settings,
+            },
+            rounds: vec![ReplayRound {
+                seeds: RoundSeeds {
+                    seed1: legacy.rand_seed1,
+                    seed2: legacy.rand_seed2,
+                    seed3: 0,
+                    seed4: 0,
+                },
+                inputs,
+                netplay: None,
+            }],
+        }
+    }
+}
+
+/// 旧形式に書き戻せるのは 1 ラウンドだけのもの。名前やシードの 3, 4 つ目は失われる
+impl TryFrom<&Replay> for LegacyReplayFile {
+    type Error = ReplayError;
+
+    fn try_from(replay: &Replay) -> Result<Self, Self::Error> {
+        let [round] = &replay.rounds[..] else {
+            return Err(invalid_data(format!(
+                "legacy format supports only 1 round, but got {}",
+                replay.rounds.len()
+            )));
+        };
+        let metadata = &replay.metadata;
+        let inputs = if metadata.player_matchup == HUMAN_VS_CPU {
+            LegacyInputList::HumanVsCpu(round.inputs.iter().map(|&(p1, _)| p1).collect())
+        } else {
+            LegacyInputList::HumanVsHuman(round.inputs.clone())
+        };
+        Ok(Self {
+            rand_seed1: round.seeds.seed1,
+            rand_seed2: round.seeds.seed2,
+            difficulty: metadata.difficulty,
+            player_matchup: metadata.player_matchup,
+            battle_settings: metadata.game_settings,
+            p1_character: metadata.p1_character,
+            p1_card: metadata.p1_card,
+            p2_character: metadata.p2_character,
+            p2_card: metadata.p2_card,
+            inputs,
+        })
+    }
+}
@@ -0,0 +1,161 @@
+use flagset::FlagSet;
+
+use crate::structs::input_devices::InputFlags;
+
+use super::{
+    analytics::analyze, LegacyReplayFile, Replay, ReplayError, ReplayGameSettings, ReplayMetadata,
+    ReplayRound, RoundNetplay, RoundSeeds, MAGIC,
+};
+
+fn replay() -> Replay {
+    Replay {
+        metadata: ReplayMetadata {
+            p1_name: "p1".into(),
+            p2_name: "プレイヤー2".into(),
+            started_at: Some(1_700_000_000),
+            difficulty: 2,
+            player_matchup: 0,
+            p1_character: 3,
+            p1_card: 4,
+            p2_character: 5,
+            p2_card: 6,
+            game_settings: ReplayGameSettings {
+                common: 0x12,
+                p1: 0x34,
+                p2: 0x56,
+            },
+        },
+        rounds: (0..3)
+            .map(|round| ReplayRound {
+                seeds: RoundSeeds {
+                    seed1: round,
+                    seed2: round + 1,
+                    seed3: round + 2,
+                    seed4: round + 3,
+                },
+                inputs: (0..1000u16)
+                    .map(|i| (i / 7, i / 13 + round as u16))
+                    .collect(),
+                netplay: (round == 0).then(|| RoundNetplay {
+                    delays: vec![(0, 2), (500, 3)],
+                    stalls: vec![(10, 20), (11, 5)],
+                }),
+            })
+            .collect(),
+    }
+}
+
+#[test]
+fn test_round_trip() {
+    let replay = replay();
+    let mut buf = Vec::new();
+    replay.write_to(&mut buf).unwrap();
+    assert_eq!(buf[..4], MAGIC);
+    assert!(buf.len() < 3 * 1000 * 4);
+    assert_eq!(Replay::read_from(&mut &buf[..]).unwrap(), replay);
+}
+
+#[test]
+fn test_corrupted() {
+    let mut buf = Vec::new();
+    replay().write_to(&mut buf).unwrap();
+
+    let mut corrupted = buf.clone();
+    *corrupted.last_mut().unwrap() ^= 0xff;
+    let result = Replay::read_from(&mut &corrupted[..]);
+    assert!(matches!(result, Err(ReplayError::ChecksumMismatch)));
+
+    let mut corrupted = buf.clone();
+    corrupted[4] = 0xff;
+    let result = Replay::read_from(&mut &corrupted[..]);
+    assert!(matches!(result, Err(ReplayError::UnsupportedVersion(_))));
+
+    let result = Replay::read_from(&mut &buf[1..]);
+    assert!(matches!(result, Err(ReplayError::InvalidMagic)));
+
+    let result = Replay::read_from(&mut &buf[..buf.len() - 1]);
+    assert!(matches!(result, Err(ReplayError::InvalidData(_))));
+}
+
+#[test]
+fn test_legacy() {
+    let mut file = vec![
+        1, 0, 0, 0, // rand_seed1
+        2, 0, 0, 0, // rand_seed2
+        3, // difficulty
+        1, // player_matchup (HumanVsCpu)
+        4, 5, 6, // battle_settings
+        7, 8, 9, 10, // p1_character, p1_card, p2_character, p2_card
+    ];
+    file.extend([0x01, 0x00, 0x02, 0x01]);
+    let legacy = LegacyReplayFile::read_from(&mut &file[..]).unwrap();
+    let replay = Replay::from(legacy);
+    assert_eq!(replay.metadata.difficulty, 3);
+    assert_eq!(replay.metadata.player_matchup, 1);
+    assert_eq!(replay.metadata.game_settings.p2, 6);
+    assert_eq!(replay.metadata.p2_card, 10);
+    assert_eq!(replay.rounds.len(), 1);
+    assert_eq!(replay.rounds[0].seeds.seed2, 2);
+    assert_eq!(replay.rounds[0].inputs, vec![(1, 0), (0x102, 0)]);
+}
+
+#[test]
+fn test_legacy_round_trip() {
+    let mut replay = replay();
+    assert!(LegacyReplayFile::try_from(&replay).is_err());
+
+    replay.rounds.truncate(1);
+    replay.rounds[0].seeds.seed3 = 0;
+    replay.rounds[0].seeds.seed4 = 0;
+    replay.rounds[0].netplay = None;
+    let mut buf = Vec::new();
+    LegacyReplayFile::try_from(&replay)
+        .unwrap()
+        .write_to(&mut buf)
+        .unwrap();
+    let converted = Replay::from(LegacyReplayFile::read_from(&mut &buf[..]).unwrap());
+    assert_eq!(converted.rounds, replay.rounds);
+    assert_eq!(converted.metadata.p2_card, replay.metadata.p2_card);
+}
+
+#[test]
+fn test_analyze() {
+    let bits = |flags: FlagSet<InputFlags>| flags.bits() as u16;
+    let shot = bits(InputFlags::SHOT.into());
+    let charge = bits(InputFlags::CHARGE.into());
+    let up_left = bits(InputFlags::UP | InputFlags::LEFT);
+    let mut replay = replay();
+    replay.rounds = vec![
+        ReplayRound {
+            inputs: vec![(shot, charge), (0, charge), (shot, charge), (shot, 0)],
+            netplay: Some(RoundNetplay {
+                delays: vec![(0, 2), (3, 1)],
+                stalls: vec![(1, 30)],
+            }),
+            ..Default::default()
+        },
+        ReplayRound {
+            inputs: vec![(up_left, charge), (up_left, charge)],
+            ..Default::default()
+        },
+    ];
+    let analytics = analyze(&replay);
+
+    assert_eq!(analytics.p1.frames, 6);
+    assert_eq!(analytics.p1.shot.frames, 3);
+    assert_eq!(analytics.p1.shot.presses, 2);
+    assert_eq!(analytics.p1.movement[0][0], 2);
+    assert_eq!(analytics.p1.movement[1][1], 4);
+    assert_eq!(analytics.p1.inputs_per_second, 4.0 / 6.0 * 60.0);
+    assert_eq!(analytics.p2.charge.presses, 2);
+    assert_eq!(analytics.p2.charge_holds, vec![3, 2]);
+    assert_eq!(analytics.p2.average_charge_hold(), Some(2.5));
+
+    let netplay = analytics.netplay.unwrap();
+    assert_eq!(netplay.frames, 4);
+    assert_eq!(netplay.delays.get(&2), Some(&3));
+    assert_eq!(netplay.delays.get(&1), Some(&1));
+    assert_eq!(netplay.stalled_frames, 1);
+    assert_eq!(netplay.max_stall_ms, 30);
+    assert_eq!(netplay.stall_rate(), 0.25);
+}
@@ -0,0 +1,96 @@
+mod inputs;
+#[cfg(test)]
+mod tests;
+
+use std::collections::VecDeque;
+
+use getset::{CopyGetters, Getters};
+
+pub use inputs::{FrameInputs, InputsMessage, RollbackInputs, MAX_PREDICTION_FRAMES};
+
+/// 巻き戻しの為に状態を保存、復元できるゲーム
+pub trait RollbackGame {
+    type Snapshot;
+
+    fn save(&self) -> Self::Snapshot;
+    fn load(&mut self, snapshot: &Self::Snapshot);
+    fn step(&mut self, p1: u16, p2: u16);
+}
+
+/// 相手の入力を予測してゲームを進め、予測が外れたら保存した状態から再計算する
+#[derive(CopyGetters, Getters)]
+pub struct Rollback<S> {
+    host: bool,
+    #[get = "pub"]
+    inputs: RollbackInputs,
+    /// snapshots[0] は snapshots_start フレーム目を進める前の状態
+    snapshots_start: u32,
+    snapshots: VecDeque<S>,
+    #[get_copy = "pub"]
+    rollback_count: u32,
+}
+
+impl<S> Rollback<S> {
+    pub fn new(host: bool) -> Self {
+        Self {
+            host,
+            inputs: RollbackInputs::new(),
+            snapshots_start: 0,
+            snapshots: VecDeque::new(),
+            rollback_count: 0,
+        }
+    }
+
+    fn to_p1_p2(&self, inputs: FrameInputs) -> (u16, u16) {
+        if self.host {
+            (inputs.local, inputs.remote)
+        } else {
+            (inputs.remote, inputs.local)
+        }
+    }
+
+    pub fn message(&self) -> InputsMessage {
+        self.inputs.message()
+    }
+
+    pub fn receive(&mut self, msg: &InputsMessage) {
+        self.inputs.receive(msg);
+    }
+
+    /// 予測が外れていれば、外れたフレームの状態に戻して現在のフレームまで再計算する
+    pub fn resimulate(&mut self, game: &mut impl RollbackGame<Snapshot = S>) {
+        let Some(frames) = self.inputs.take_rollback() else {
+            return;
+        };
+        let Some(first) = frames.first() else {
+            return;
+        };
+        let index = (first.frame - self.snapshots_start) as usize;
+        game.load(&self.snapshots[index]);
+        self.snapshots.truncate(index);
+        for inputs in frames {
+            self.snapshots.push_back(game.save());
+            let (p1, p2) = self.to_p1_p2(inputs);
+            game.step(p1, p2);
+        }
+        self.rollback_count += 1;
+    }
+
+    /// 必要なら再計算してから 1 フレーム進める。予測できる範囲を超えたら進めずに false を返す
+    pub fn update(&mut self, game: &mut impl RollbackGame<Snapshot = S>, local_input: u16) -> bool {
+        self.resimulate(game);
+        if !self.inputs.can_advance() {
+            return false;
+        }
+        self.snapshots.push_back(game.save());
+        let inputs = self.inputs.advance(local_input);
+        let (p1, p2) = self.to_p1_p2(inputs);
+        game.step(p1, p2);
+
+        while self.snapshots_start < self.inputs.confirmed_frame() {
+            self.snapshots.pop_front();
+            self.snapshots_start += 1;
+        }
+        true
+    }
+}
@@ -0,0 +1,192 @@
+use std::collections::VecDeque;
+
+use getset::CopyGetters;
+use serde::{Deserialize, Serialize};
+
+/// 相手の入力が届かないまま予測で進められるフレーム数
+pub const MAX_PREDICTION_FRAMES: u32 = 8;
+
+/// 相手が受け取っていない自分の入力をまとめて送るので、途中のメッセージが失われても良い
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct InputsMessage {
+    /// 受け取った相手の入力の次のフレーム
+    pub ack_frame: u32,
+    /// inputs[0] のフレーム
+    pub start_frame: u32,
+    pub inputs: Vec<u16>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FrameInputs {
+    pub frame: u32,
+    pub local: u16,
+    pub remote: u16,
+    /// remote が確定していない予測の入力か
+    pub predicted: bool,
+}
+
+/// 相手の入力の予測と確定を管理する
+#[derive(CopyGetters)]
+pub struct RollbackInputs {
+    /// 次に進めるフレーム
+    #[get_copy = "pub"]
+    frame: u32,
+    /// local_inputs[0] のフレーム
+    local_start: u32,
+    local_inputs: VecDeque<u16>,
+    /// 相手が受け取った自分の入力の次のフレーム
+    local_acked_frame: u32,
+    /// remote_inputs[0] のフレーム
+    remote_start: u32,
+    /// 確定した相手の入力
+    remote_inputs: VecDeque<u16>,
+    last_remote_input: u16,
+    /// 確定していない相手の入力の代わりに使った予測
+    predictions: VecDeque<u16>,
+    /// 予測が外れた最初のフレーム
+    rollback_frame: Option<u32>,
+}
+
+impl Default for RollbackInputs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RollbackInputs {
+    pub fn new() -> Self {
+        Self {
+            frame: 0,
+            local_start: 0,
+            local_inputs: VecDeque::new(),
+            local_acked_frame: 0,
+            remote_start: 0,
+            remote_inputs: VecDeque::new(),
+            last_remote_input: 0,
+            predictions: VecDeque::new(),
+            rollback_frame: None,
+        }
+    }
+
+    /// 相手の入力が確定していない最初のフレーム
+    fn remote_frame(&self) -> u32 {
+        self.remote_start + self.remote_inputs.len() as u32
+    }
+
+    /// これより前のフレームは両者の入力が確定していて、巻き戻ることがない
+    pub fn confirmed_frame(&self) -> u32 {
+        self.remote_frame().min(self.frame)
+    }
+
+    pub fn can_advance(&self) -> bool {
+        self.frame < self.remote_frame() + MAX_PREDICTION_FRAMES
+    }
+
+    fn local_input(&self, frame: u32) -> u16 {
+        self.local_inputs[(frame - self.local_start) as usize]
+    }
+
+    fn remote_input(&self, frame: u32) -> Option<u16> {
+        if frame < self.remote_start {
+            return None;
+        }
+        self.remote_inputs
+            .get((frame - self.remote_start) as usize)
+            .copied()
+    }
+
+    fn frame_inputs(&self, frame: u32) -> FrameInputs {
+        let local = self.local_input(frame);
+        if let Some(remote) = self.remote_input(frame) {
+            return FrameInputs {
+                frame,
+                local,
+                remote,
+                predicted: false,
+            };
+        }
+        let remote = self.predictions[(frame - self.remote_frame()) as usize];
+        FrameInputs {
+            frame,
+            local,
+            remote,
+            predicted: true,
+        }
+    }
+
+    /// 自分の入力を追加してフレームを進める。相手の入力が無ければ最後に確定した入力が続くと予測する
+    pub fn advance(&mut self, local_input: u16) -> FrameInputs {
+        debug_assert!(self.can_advance());
+        self.local_inputs.push_back(local_input);
+        if self.remote_input(self.frame).is_none() {
+            self.predictions.push_back(self.last_remote_input);
+        }
+        let inputs = self.frame_inputs(self.frame);
+        self.frame += 1;
+        inputs
+    }
+
+    pub fn message(&self) -> InputsMessage {
+        InputsMessage {
+            ack_frame: self.remote_frame(),
+            start_frame: self.local_acked_frame,
+            inputs: (self.local_acked_frame..self.frame)
+                .map(|frame| self.local_input(frame))
+                .collect(),
+        }
+    }
+
+    pub fn receive(&mut self, msg: &InputsMessage) {
+        self.local_acked_frame = self.local_acked_frame.max(msg.ack_frame.min(self.frame));
+        let remote_frame = self.remote_frame();
+        // 間のメッセージが失われた場合は、再送されるのを待つ
+        if msg.start_frame > remote_frame {
+            self.discard_unused();
+            return;
+        }
+        let skip = (remote_frame - msg.start_frame) as usize;
+        for (i, &input) in msg.inputs.iter().enumerate().skip(skip) {
+            let frame = msg.start_frame + i as u32;
+            if frame < self.frame {
+                let prediction = self.predictions.pop_front().unwrap();
+                if prediction != input && self.rollback_frame.is_none() {
+                    self.rollback_frame = Some(frame);
+                }
+            }
+            self.remote_inputs.push_back(input);
+            self.last_remote_input = input;
+        }
+        self.discard_unused();
+    }
+
+    /// 予測が外れていれば、そのフレームから現在までの入力を予測し直して返す
+    pub fn take_rollback(&mut self) -> Option<Vec<FrameInputs>> {
+        let rollback_frame = self.rollback_frame.take()?;
+        let last_remote_input = self.last_remote_input;
+        self.predictions
+            .iter_mut()
+            .for_each(|prediction| *prediction = last_remote_input);
+        let inputs = (rollback_frame..self.frame)
+            .map(|frame| self.frame_inputs(frame))
+            .collect();
+        self.discard_unused();
+        Some(inputs)
+    }
+
+    /// 送信にも巻き戻しにも使わなくなった入力を捨てる
+    fn discard_unused(&mut self) {
+        let oldest = self
+            .rollback_frame
+            .unwrap_or(u32::MAX)
+            .min(self.confirmed_frame());
+        let local_oldest = oldest.min(self.local_acked_frame);
+        while self.local_start < local_oldest {
+            self.local_inputs.pop_front();
+            self.local_start += 1;
+        }
+        while self.remote_start < oldest {
+            self.remote_inputs.pop_front();
+            self.remote_start += 1;
+        }
+    }
+}
@@ -0,0 +1,232 @@
+use std::collections::VecDeque;
+
+use super::{InputsMessage, Rollback, RollbackGame, RollbackInputs, MAX_PREDICTION_FRAMES};
+
+/// 入力の履歴で状態が決まるゲーム
+#[derive(Default)]
+struct HashGame {
+    state: u64,
+    frame: u32,
+}
+
+impl RollbackGame for HashGame {
+    type Snapshot = (u64, u32);
+
+    fn save(&self) -> Self::Snapshot {
+        (self.state, self.frame)
+    }
+
+    fn load(&mut self, snapshot: &Self::Snapshot) {
+        (self.state, self.frame) = *snapshot;
+    }
+
+    fn step(&mut self, p1: u16, p2: u16) {
+        self.state = self
+            .state
+            .wrapping_mul(0x100000001b3)
+            .wrapping_add(((p1 as u64) << 16) | p2 as u64);
+        self.frame += 1;
+    }
+}
+
+/// 同じ乱数列を再現する為の線形合同法
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self) -> u32 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.0 >> 33) as u32
+    }
+}
+
+/// latency フレーム遅れて届き、loss_percent % のメッセージが失われる回線
+struct Link {
+    latency: u32,
+    loss_percent: u32,
+    rng: Lcg,
+    queue: VecDeque<(u32, InputsMessage)>,
+}
+
+impl Link {
+    fn new(latency: u32, loss_percent: u32, seed: u64) -> Self {
+        Self {
+            latency,
+            loss_percent,
+            rng: Lcg(seed),
+            queue: VecDeque::new(),
+        }
+    }
+
+    fn send(&mut self, now: u32, msg: InputsMessage) {
+        if self.rng.next() % 100 < self.loss_percent {
+            return;
+        }
+        self.queue.push_back((now + self.latency, msg));
+    }
+
+    fn recv(&mut self, now: u32) -> Vec<InputsMessage> {
+        let mut msgs = vec![];
+        while self.queue.front().is_some_and(|(at, _)| *at <= now) {
+            msgs.push(self.queue.pop_front().unwrap().1);
+        }
+        msgs
+    }
+}
+
+struct Peer {
+    rollback: Rollback<(u64, u32)>,
+    game: HashGame,
+    inputs: Vec<u16>,
+    stalls: u32,
+}
+
+impl Peer {
+    fn new(host: bool, inputs: Vec<u16>) -> Self {
+        Self {
+            rollback: Rollback::new(host),
+            game: HashGame::default(),
+            inputs,
+            stalls: 0,
+        }
+    }
+
+    fn tick(&mut self, now: u32, rx: &mut Link, tx: &mut Link) {
+        for msg in rx.recv(now) {
+            self.rollback.receive(&msg);
+        }
+        let frame = self.rollback.inputs().frame() as usize;
+        if let Some(&input) = self.inputs.get(frame) {
+            if !self.rollback.update(&mut self.game, input) {
+                self.stalls += 1;
+            }
+        } else {
+            self.rollback.resimulate(&mut self.game);
+        }
+        tx.send(now, self.rollback.message());
+    }
+
+    fn confirmed_all(&self) -> bool {
+        self.rollback.inputs().confirmed_frame() == self.inputs.len() as u32
+    }
+}
+
+fn scripted_inputs(seed: u64, frames: usize) -> Vec<u16> {
+    let mut rng = Lcg(seed);
+    let mut input = 0;
+    (0..frames)
+        .map(|_| {
+            // 人の入力のように、しばらく同じ入力が続く
+            if rng.next().is_multiple_of(8) {
+                input = (rng.next() & 0x0fff) as u16;
+            }
+            input
+        })
+        .collect()
+}
+
+fn expected_state(p1: &[u16], p2: &[u16]) -> u64 {
+    let mut game = HashGame::default();
+    p1.iter().zip(p2).for_each(|(&p1, &p2)| game.step(p1, p2));
+    game.state
+}
+
+/// 両者が最後のフレームまで確定するまで回線越しに進める
+fn simulate(latency: u32, loss_percent: u32) -> (Peer, Peer) {
+    const FRAMES: usize = 600;
+    let mut host = Peer::new(true, scripted_inputs(1, FRAMES));
+    let mut guest = Peer::new(false, scripted_inputs(2, FRAMES));
+    let mut host_to_guest = Link::new(latency, loss_percent, 3);
+    let mut guest_to_host = Link::new(latency, loss_percent, 4);
+    let mut now = 0;
+    while !host.confirmed_all() || !guest.confirmed_all() {
+        host.tick(now, &mut guest_to_host, &mut host_to_guest);
+        guest.tick(now, &mut host_to_guest, &mut guest_to_host);
+        now += 1;
+        assert!(now < FRAMES as u32 * 10, "simulation did not converge");
+    }
+    (host, guest)
+}
+
+#[test]
+fn prediction_repeats_last_confirmed_input() {
+    let mut inputs = RollbackInputs::new();
+    let first = inputs.advance(1);
+    assert!(first.predicted);
+    assert_eq!(first.remote, 0);
+
+    inputs.receive(&InputsMessage {
+        ack_frame: 1,
+        start_frame: 0,
+        inputs: vec![0, 5],
+    });
+    assert!(inputs.take_rollback().is_none());
+    let confirmed = inputs.advance(1);
+    assert!(!confirmed.predicted);
+    assert_eq!(confirmed.remote, 5);
+    let predicted = inputs.advance(1);
+    assert!(predicted.predicted);
+    assert_eq!(predicted.remote, 5);
+}
+
+#[test]
+fn wrong_prediction_rolls_back_from_first_mismatch() {
+    let mut inputs = RollbackInputs::new();
+    for _ in 0..4 {
+        inputs.advance(1);
+    }
+    inputs.receive(&InputsMessage {
+        ack_frame: 4,
+        start_frame: 0,
+        inputs: vec![0, 0, 7],
+    });
+    let frames = inputs.take_rollback().unwrap();
+    assert_eq!(
+        frames
+            .iter()
+            .map(|x| (x.frame, x.remote, x.predicted))
+            .collect::<Vec<_>>(),
+        [(2, 7, false), (3, 7, true)]
+    );
+    assert!(inputs.take_rollback().is_none());
+    assert_eq!(inputs.confirmed_frame(), 3);
+}
+
+#[test]
+fn stalls_when_prediction_window_is_exceeded() {
+    let mut rollback = Rollback::new(true);
+    let mut game = HashGame::default();
+    for _ in 0..MAX_PREDICTION_FRAMES {
+        assert!(rollback.update(&mut game, 1));
+    }
+    assert!(!rollback.update(&mut game, 1));
+    assert_eq!(game.frame, MAX_PREDICTION_FRAMES);
+
+    rollback.receive(&InputsMessage {
+        ack_frame: 0,
+        start_frame: 0,
+        inputs: vec![0],
+    });
+    assert!(rollback.update(&mut game, 1));
+}
+
+#[test]
+fn converges_without_loss() {
+    let (host, guest) = simulate(3, 0);
+    let expected = expected_state(&host.inputs, &guest.inputs);
+    assert_eq!(host.game.state, expected);
+    assert_eq!(guest.game.state, expected);
+    assert!(host.rollback.rollback_count() > 0);
+    assert_eq!(host.stalls, 0);
+}
+
+#[test]
+fn converges_with_loss_and_high_latency() {
+    let (host, guest) = simulate(6, 30);
+    let expected = expected_state(&host.inputs, &guest.inputs);
+    assert_eq!(host.game.state, expected);
+    assert_eq!(guest.game.state, expected);
+    assert!(host.stalls > 0);
+}
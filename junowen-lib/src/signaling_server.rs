@@ -1,3 +1,6 @@
 pub mod custom;
+pub mod public_room;
+pub mod quick_match;
 pub mod reserved_room;
 pub mod room;
+pub mod version;
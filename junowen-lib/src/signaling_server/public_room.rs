@@ -0,0 +1,77 @@
+use anyhow::{bail, Result};
+use derive_new::new;
+use getset::{CopyGetters, Getters};
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use super::version::ClientVersion;
+
+// GET /public-rooms
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PublicRoomKind {
+    Shared,
+    Reserved,
+}
+
+#[derive(Clone, CopyGetters, Debug, Deserialize, Getters, Serialize, new)]
+pub struct PublicRoom {
+    #[get_copy = "pub"]
+    kind: PublicRoomKind,
+    #[get = "pub"]
+    name: String,
+    /// ルームが作られてからの秒数
+    #[get_copy = "pub"]
+    age_sec: u64,
+    /// 対戦相手として入れる
+    #[get_copy = "pub"]
+    opponent_slot_free: bool,
+    /// 観戦者として入れる
+    #[get_copy = "pub"]
+    spectator_slot_free: bool,
+    /// 空いている枠に入るのにパスワードが要る
+    #[serde(default)]
+    #[get_copy = "pub"]
+    password_required: bool,
+    #[get = "pub"]
+    client_version: Option<ClientVersion>,
+}
+
+#[derive(Debug, Deserialize, Serialize, new)]
+pub struct GetPublicRoomsResponseOkBody {
+    rooms: Vec<PublicRoom>,
+}
+
+impl GetPublicRoomsResponseOkBody {
+    pub fn into_rooms(self) -> Vec<PublicRoom> {
+        self.rooms
+    }
+}
+
+pub enum GetPublicRoomsResponse {
+    Ok(GetPublicRoomsResponseOkBody),
+}
+
+impl GetPublicRoomsResponse {
+    pub fn parse(status: StatusCode, text: Option<&str>) -> Result<Self> {
+        if let (StatusCode::OK, Some(text)) = (status, text) {
+            if let Ok(body) = serde_json::from_str::<GetPublicRoomsResponseOkBody>(text) {
+                return Ok(Self::Ok(body));
+            }
+        }
+        bail!("invalid response")
+    }
+
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            Self::Ok(_) => StatusCode::OK,
+        }
+    }
+
+    pub fn to_body(&self) -> String {
+        match self {
+            Self::Ok(body) => serde_json::to_string(&body).unwrap(),
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use anyhow::{anyhow, bail, Result};
+use derive_new::new;
+use getset::Getters;
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use crate::connection::signaling::CompressedSdp;
+
+// POST /quick-match
+
+/// None の項目は何でもよい
+#[derive(Clone, Debug, Default, Deserialize, Eq, Getters, PartialEq, Serialize, new)]
+pub struct QuickMatchFilter {
+    /// GameSettings の Round
+    #[get = "pub"]
+    round: Option<u8>,
+    /// GameSettings の TimeLimit
+    #[get = "pub"]
+    time_limit: Option<u8>,
+    #[get = "pub"]
+    region: Option<String>,
+}
+
+impl QuickMatchFilter {
+    pub fn matches(&self, other: &Self) -> bool {
+        fn eq<T: PartialEq>(a: &Option<T>, b: &Option<T>) -> bool {
+            a.is_none() || b.is_none() || a == b
+        }
+        eq(&self.round, &other.round)
+            && eq(&self.time_limit, &other.time_limit)
+            && eq(&self.region, &other.region)
+    }
+}
+
+#[derive(Deserialize, Serialize, new)]
+pub struct PostQuickMatchRequestBody {
+    offer: CompressedSdp,
+    #[serde(default)]
+    filter: QuickMatchFilter,
+}
+
+impl PostQuickMatchRequestBody {
+    pub fn into_inner(self) -> (CompressedSdp, QuickMatchFilter) {
+        (self.offer, self.filter)
+    }
+}
+
+/// 待ち行列に入った。以降は /custom/{room_name}/keep で応答を待つ
+#[derive(Debug, Deserialize, Serialize, new)]
+pub struct PostQuickMatchResponseWaitingBody {
+    room_name: String,
+    key: String,
+}
+
+impl PostQuickMatchResponseWaitingBody {
+    pub fn into_inner(self) -> (String, String) {
+        (self.room_name, self.key)
+    }
+}
+
+/// 待っている相手が見つかった。/custom/{room_name}/join で応答する
+#[derive(Debug, Deserialize, Serialize, new)]
+pub struct PostQuickMatchResponseMatchedBody {
+    room_name: String,
+    offer: CompressedSdp,
+}
+
+impl PostQuickMatchResponseMatchedBody {
+    pub fn into_inner(self) -> (String, CompressedSdp) {
+        (self.room_name, self.offer)
+    }
+}
+
+#[derive(Debug)]
+pub enum PostQuickMatchResponse {
+    Waiting {
+        retry_after: u32,
+        body: PostQuickMatchResponseWaitingBody,
+    },
+    Matched(PostQuickMatchResponseMatchedBody),
+}
+
+impl PostQuickMatchResponse {
+    pub fn parse(status: StatusCode, retry_after: Option<u32>, text: Option<&str>) -> Result<Self> {
+        match (status, text) {
+            (StatusCode::CREATED, Some(text)) => {
+                if let Ok(body) = serde_json::from_str(text) {
+                    return Ok(Self::Waiting {
+                        retry_after: retry_after.ok_or_else(|| anyhow!("invalid response"))?,
+                        body,
+                    });
+                }
+            }
+            (StatusCode::OK, Some(text)) => {
+                if let Ok(body) = serde_json::from_str(text) {
+                    return Ok(Self::Matched(body));
+                }
+            }
+            _ => {}
+        }
+        bail!("invalid response")
+    }
+
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            Self::Waiting { .. } => StatusCode::CREATED,
+            Self::Matched(_) => StatusCode::OK,
+        }
+    }
+
+    pub fn to_body(&self) -> String {
+        match self {
+            Self::Waiting { body, .. } => serde_json::to_string(body).unwrap(),
+            Self::Matched(body) => serde_json::to_string(body).unwrap(),
+        }
+    }
+}
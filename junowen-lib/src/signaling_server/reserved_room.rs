@@ -1,7 +1,7 @@
 use anyhow::bail;
 use anyhow::Result;
 use derive_new::new;
-use getset::Getters;
+use getset::{CopyGetters, Getters};
 use http::StatusCode;
 use serde::Deserialize;
 use serde::Serialize;
@@ -10,9 +10,34 @@ use crate::connection::signaling::CompressedSdp;
 
 use super::room::PostRoomKeepResponse;
 use super::room::PutRoomResponse;
+use super::version::IncompatibleResponseBody;
+
+/// GET, join, spectate でルームのパスワードを送るヘッダー
+pub const ROOM_PASSWORD_HEADER: &str = "x-junowen-room-password";
+
+/// ホストが同時に待ち受けられる観戦枠の数
+pub const MAX_SPECTATOR_SLOTS: usize = 4;
 
 // PUT /reserved-room/{name}
 
+/// パスワードは対戦者用と観戦者用で別々に設定でき、None ならパスワード無し
+#[derive(CopyGetters, Deserialize, Getters, Serialize, new)]
+pub struct PutReservedRoomRequestBody {
+    #[get = "pub"]
+    offer: CompressedSdp,
+    /// ルーム一覧に載せるか
+    #[serde(default)]
+    #[get_copy = "pub"]
+    public: bool,
+    /// ルームが既にあれば、入室の為のパスワードとして照合される
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    player_password: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    spectator_password: Option<String>,
+}
+
 #[derive(Debug, Deserialize, Serialize, new)]
 pub struct PutReservedRoomResponseConflictBody {
     opponent_offer: Option<CompressedSdp>,
@@ -28,10 +53,20 @@ pub type PutReservedRoomResponse = PutRoomResponse<PutReservedRoomResponseConfli
 
 // GET /reserved-room/{name}
 
+/// 観戦枠ごとのオファー
+#[derive(Debug, Deserialize, Serialize, new)]
+pub struct SpectatorOffer {
+    spectator_id: String,
+    offer: CompressedSdp,
+}
+
 #[derive(Debug, Deserialize, Serialize, new)]
 pub struct GetReservedRoomResponseOkBody {
     opponent_offer: Option<CompressedSdp>,
+    /// 観戦枠を使わない古いホストのオファー
     spectator_offer: Option<CompressedSdp>,
+    #[serde(default)]
+    spectator_offers: Vec<SpectatorOffer>,
 }
 
 impl GetReservedRoomResponseOkBody {
@@ -42,11 +77,23 @@ impl GetReservedRoomResponseOkBody {
     pub fn into_spectator_offer(self) -> Option<CompressedSdp> {
         self.spectator_offer
     }
+
+    /// 観戦枠のオファーの後に古いホストのオファーを spectator_id 無しで並べる
+    pub fn into_spectator_offers(self) -> Vec<(Option<String>, CompressedSdp)> {
+        self.spectator_offers
+            .into_iter()
+            .map(|offer| (Some(offer.spectator_id), offer.offer))
+            .chain(self.spectator_offer.map(|offer| (None, offer)))
+            .collect()
+    }
 }
 
 pub enum GetReservedRoomResponse {
     Ok(GetReservedRoomResponseOkBody),
     NotFound,
+    Incompatible(IncompatibleResponseBody),
+    /// 観戦者用のパスワードが一致しない
+    Forbidden,
 }
 
 impl GetReservedRoomResponse {
@@ -58,6 +105,12 @@ impl GetReservedRoomResponse {
                 }
             }
             (StatusCode::NOT_FOUND, _) => return Ok(Self::NotFound),
+            (StatusCode::FORBIDDEN, _) => return Ok(Self::Forbidden),
+            (StatusCode::PRECONDITION_FAILED, Some(text)) => {
+                if let Ok(body) = serde_json::from_str::<IncompatibleResponseBody>(text) {
+                    return Ok(Self::Incompatible(body));
+                }
+            }
             _ => {}
         }
         bail!("invalid response")
@@ -67,6 +120,8 @@ impl GetReservedRoomResponse {
         match self {
             Self::Ok(_) => StatusCode::OK,
             Self::NotFound => StatusCode::NOT_FOUND,
+            Self::Incompatible(_) => StatusCode::PRECONDITION_FAILED,
+            Self::Forbidden => StatusCode::FORBIDDEN,
         }
     }
 
@@ -74,6 +129,8 @@ impl GetReservedRoomResponse {
         match self {
             Self::Ok(body) => Some(serde_json::to_string(&body).unwrap()),
             Self::NotFound => None,
+            Self::Incompatible(body) => Some(serde_json::to_string(&body).unwrap()),
+            Self::Forbidden => None,
         }
     }
 }
@@ -84,11 +141,14 @@ impl GetReservedRoomResponse {
 pub struct PostReservedRoomKeepRequestBody {
     key: String,
     spectator_offer: Option<CompressedSdp>,
+    /// 観戦枠の ID。None なら観戦枠を使わない古いホスト
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    spectator_id: Option<String>,
 }
 
 impl PostReservedRoomKeepRequestBody {
-    pub fn into_inner(self) -> (String, Option<CompressedSdp>) {
-        (self.key, self.spectator_offer)
+    pub fn into_inner(self) -> (String, Option<CompressedSdp>, Option<String>) {
+        (self.key, self.spectator_offer, self.spectator_id)
     }
 }
 
@@ -142,7 +202,20 @@ impl From<PostReservedRoomKeepResponseOkBody> for PostReservedRoomKeepResponse {
     }
 }
 
-// POST /reserved-room/{name}/join
+// POST /reserved-room/{name}/spectate
+
+#[derive(Deserialize, Serialize, new)]
+pub struct PostReservedRoomSpectateRequestBody {
+    answer: CompressedSdp,
+    /// 応答する観戦枠の ID。None なら古いホストのオファーへの応答
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    spectator_id: Option<String>,
+}
+
+impl PostReservedRoomSpectateRequestBody {
+    pub fn into_inner(self) -> (CompressedSdp, Option<String>) {
+        (self.answer, self.spectator_id)
+    }
+}
 
-pub use super::room::PostRoomJoinRequestBody as PostReservedRoomSpectateRequestBody;
 pub use super::room::PostRoomJoinResponse as PostReservedRoomSpectateResponse;
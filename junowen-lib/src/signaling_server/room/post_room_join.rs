@@ -19,6 +19,8 @@ impl RequestBody {
 pub enum Response {
     Ok,
     Conflict,
+    /// パスワードが一致しない
+    Forbidden,
 }
 
 impl Response {
@@ -27,6 +29,7 @@ impl Response {
             StatusCode::OK => Ok(Self::Ok),
             StatusCode::CREATED => Ok(Self::Ok),
             StatusCode::CONFLICT => Ok(Self::Conflict),
+            StatusCode::FORBIDDEN => Ok(Self::Forbidden),
             _ => bail!("invalid response"),
         }
     }
@@ -35,6 +38,7 @@ impl Response {
         match self {
             Response::Ok => StatusCode::CREATED,
             Response::Conflict => StatusCode::CONFLICT,
+            Response::Forbidden => StatusCode::FORBIDDEN,
         }
     }
 
@@ -42,6 +46,7 @@ impl Response {
         match self {
             Response::Ok => StatusCode::OK,
             Response::Conflict => StatusCode::CONFLICT,
+            Response::Forbidden => StatusCode::FORBIDDEN,
         }
     }
 }
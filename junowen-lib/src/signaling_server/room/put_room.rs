@@ -1,15 +1,21 @@
 use anyhow::{anyhow, bail, Result};
 use derive_new::new;
-use getset::Getters;
+use getset::{CopyGetters, Getters};
 use http::StatusCode;
 use serde::{Deserialize, Serialize};
 
-use crate::connection::signaling::CompressedSdp;
+use crate::{
+    connection::signaling::CompressedSdp, signaling_server::version::IncompatibleResponseBody,
+};
 
-#[derive(Deserialize, Serialize, Getters, new)]
+#[derive(CopyGetters, Deserialize, Serialize, Getters, new)]
 pub struct RequestBody {
     #[get = "pub"]
     offer: CompressedSdp,
+    /// ルーム一覧に載せるか
+    #[serde(default)]
+    #[get_copy = "pub"]
+    public: bool,
 }
 
 #[derive(Debug, Deserialize, Serialize, new)]
@@ -48,6 +54,14 @@ pub enum Response<T> {
         retry_after: u32,
         body: T,
     },
+    Incompatible {
+        retry_after: u32,
+        body: IncompatibleResponseBody,
+    },
+    /// パスワードが一致しない
+    Forbidden {
+        retry_after: u32,
+    },
 }
 
 impl<'a, T> Response<T>
@@ -63,6 +77,12 @@ where
     pub fn conflict(retry_after: u32, body: T) -> Self {
         Self::Conflict { retry_after, body }
     }
+    pub fn incompatible(retry_after: u32, body: IncompatibleResponseBody) -> Self {
+        Self::Incompatible { retry_after, body }
+    }
+    pub fn forbidden(retry_after: u32) -> Self {
+        Self::Forbidden { retry_after }
+    }
 
     pub fn parse(status: StatusCode, retry_after: Option<u32>, text: &'a str) -> Result<Self> {
         match status {
@@ -88,6 +108,19 @@ where
                     });
                 }
             }
+            StatusCode::PRECONDITION_FAILED => {
+                if let Ok(res) = serde_json::from_str(text) {
+                    return Ok(Self::Incompatible {
+                        retry_after: retry_after.ok_or_else(|| anyhow!("invalid response"))?,
+                        body: res,
+                    });
+                }
+            }
+            StatusCode::FORBIDDEN => {
+                return Ok(Self::Forbidden {
+                    retry_after: retry_after.ok_or_else(|| anyhow!("invalid response"))?,
+                });
+            }
             _ => {}
         }
         bail!("invalid response")
@@ -98,6 +131,8 @@ where
             Response::CreatedWithKey { .. } => StatusCode::CREATED,
             Response::CreatedWithAnswer { .. } => StatusCode::CREATED,
             Response::Conflict { .. } => StatusCode::CONFLICT,
+            Response::Incompatible { .. } => StatusCode::PRECONDITION_FAILED,
+            Response::Forbidden { .. } => StatusCode::FORBIDDEN,
         }
     }
 
@@ -106,6 +141,8 @@ where
             Response::CreatedWithKey { retry_after, .. } => *retry_after,
             Response::CreatedWithAnswer { retry_after, .. } => *retry_after,
             Response::Conflict { retry_after, .. } => *retry_after,
+            Response::Incompatible { retry_after, .. } => *retry_after,
+            Response::Forbidden { retry_after } => *retry_after,
         }
     }
 }
@@ -0,0 +1,65 @@
+use derive_new::new;
+use getset::Getters;
+use http::{HeaderMap, HeaderValue};
+use serde::{Deserialize, Serialize};
+
+use crate::connection::PROTOCOL;
+
+pub const PROTOCOL_HEADER: &str = "x-junowen-protocol";
+pub const VERSION_HEADER: &str = "x-junowen-version";
+
+/// ヘッダーで申告されるクライアントのバージョン
+#[derive(Clone, Debug, Deserialize, Eq, Getters, PartialEq, Serialize, new)]
+pub struct ClientVersion {
+    /// データチャネルのプロトコル。一致しなければ接続できない
+    #[get = "pub"]
+    protocol: String,
+    #[get = "pub"]
+    version: String,
+}
+
+impl ClientVersion {
+    pub fn current() -> Self {
+        Self::new(PROTOCOL.to_owned(), env!("CARGO_PKG_VERSION").to_owned())
+    }
+
+    /// ヘッダーを送らない古いクライアントは None
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let get = |name| headers.get(name).and_then(|x| x.to_str().ok());
+        let protocol = get(PROTOCOL_HEADER)?;
+        let version = get(VERSION_HEADER).unwrap_or("unknown");
+        Some(Self::new(protocol.to_owned(), version.to_owned()))
+    }
+
+    pub fn to_headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        let value = |x: &str| HeaderValue::from_str(x).unwrap();
+        headers.insert(PROTOCOL_HEADER, value(&self.protocol));
+        headers.insert(VERSION_HEADER, value(&self.version));
+        headers
+    }
+
+    pub fn is_compatible_with(&self, other: &Self) -> bool {
+        self.protocol == other.protocol
+    }
+}
+
+/// 412 Precondition Failed のボディ
+#[derive(Debug, Deserialize, Serialize, new)]
+pub struct IncompatibleResponseBody {
+    opponent: ClientVersion,
+}
+
+impl IncompatibleResponseBody {
+    pub fn opponent(&self) -> &ClientVersion {
+        &self.opponent
+    }
+
+    pub fn into_error(self) -> IncompatibleOpponentError {
+        IncompatibleOpponentError(self.opponent)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("your opponent uses version {} ({})", .0.version(), .0.protocol())]
+pub struct IncompatibleOpponentError(pub ClientVersion);
@@ -15,21 +15,16 @@ pub struct Player {
     _unknown3: [u8; 0x34],
 }
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
 #[repr(u32)]
 pub enum Difficulty {
     Easy,
+    #[default]
     Normal,
     Hard,
     Lunatic,
 }
 
-impl Default for Difficulty {
-    fn default() -> Self {
-        Self::Normal
-    }
-}
-
 impl TryFrom<u32> for Difficulty {
     type Error = anyhow::Error;
     fn try_from(value: u32) -> Result<Self> {
@@ -58,21 +53,16 @@ impl TryFrom<u32> for GameMode {
     }
 }
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
 #[repr(u32)]
 pub enum PlayerMatchup {
+    #[default]
     HumanVsHuman,
     HumanVsCpu,
     CpuVsCpu,
     YoukaiVsYoukai,
 }
 
-impl Default for PlayerMatchup {
-    fn default() -> Self {
-        Self::HumanVsHuman
-    }
-}
-
 impl TryFrom<u32> for PlayerMatchup {
     type Error = anyhow::Error;
     fn try_from(value: u32) -> Result<Self> {
@@ -1,4 +1,3 @@
-pub mod structs;
 pub mod th19_helpers;
 
 use std::{arch::asm, ffi::c_void, mem::transmute};
@@ -16,7 +15,7 @@ use crate::{
     pointer, ptr_opt, u32_prop, u32_prop_todo, value_ref,
 };
 
-use self::structs::{
+use crate::structs::{
     app::App,
     input_devices::{Input, InputDevices},
     others::{RenderingText, RoundFrame, VSMode, WindowInner},
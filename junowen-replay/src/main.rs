@@ -0,0 +1,329 @@
+use std::{
+    env::args,
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use anyhow::{bail, Context, Result};
+use junowen_lib::{
+    replay::{
+        analytics::{analyze, NetplayAnalytics, PlayerAnalytics},
+        LegacyReplayFile, Replay, ReplayMetadata, MAGIC,
+    },
+    structs::{
+        input_devices::InputValue,
+        selection::{Difficulty, PlayerMatchup},
+        settings::GameSettings,
+    },
+};
+use time::OffsetDateTime;
+
+const USAGE: &str = "\
+usage: junowen-replay <command> [args...]
+
+commands:
+  info <file>                           print the match information
+  inputs <file> [round]                 dump the inputs of each frame
+  stats <file>                          print the input and netplay statistics
+  convert <input> <output>              convert the format (*.rep is the legacy format)
+  trim <input> <output> <first> [last]  keep only the rounds first..=last (1-based)
+  validate <file>                       check the integrity";
+
+/// 拡張子が rep なら th19replayrecorder の旧形式
+fn is_legacy(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .is_some_and(|x| x.eq_ignore_ascii_case("rep"))
+}
+
+fn read_replay(path: &str) -> Result<Replay> {
+    let bytes = fs::read(path).with_context(|| format!("failed to read {}", path))?;
+    if bytes.starts_with(&MAGIC) {
+        return Ok(Replay::read_from(&mut &bytes[..])?);
+    }
+    Ok(LegacyReplayFile::read_from(&mut &bytes[..])?.into())
+}
+
+fn write_replay(path: &str, replay: &Replay) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    if is_legacy(path) {
+        LegacyReplayFile::try_from(replay)?.write_to(&mut writer)?;
+    } else {
+        replay.write_to(&mut writer)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn difficulty_name(value: u8) -> String {
+    Difficulty::try_from(value as u32)
+        .map(|x| format!("{:?}", x))
+        .unwrap_or_else(|_| format!("Unknown ({})", value))
+}
+
+fn player_matchup_name(value: u8) -> String {
+    PlayerMatchup::try_from(value as u32)
+        .map(|x| format!("{:?}", x))
+        .unwrap_or_else(|_| format!("Unknown ({})", value))
+}
+
+fn input_name(input: u16) -> String {
+    let Ok(value) = InputValue::try_from(input as u32) else {
+        return format!("{:#06x}", input);
+    };
+    let names: Vec<_> = value.0.into_iter().map(|x| format!("{:?}", x)).collect();
+    if names.is_empty() {
+        return "-".to_owned();
+    }
+    names.join("+")
+}
+
+fn print_info(replay: &Replay) {
+    let metadata = &replay.metadata;
+    let name = |name: &str| {
+        if name.is_empty() {
+            "(unknown)".to_owned()
+        } else {
+            name.to_owned()
+        }
+    };
+    println!(
+        "P1: {} (character: {}, card: {})",
+        name(&metadata.p1_name),
+        metadata.p1_character,
+        metadata.p1_card
+    );
+    println!(
+        "P2: {} (character: {}, card: {})",
+        name(&metadata.p2_name),
+        metadata.p2_character,
+        metadata.p2_card
+    );
+    match metadata
+        .started_at
+        .and_then(|x| OffsetDateTime::from_unix_timestamp(x).ok())
+    {
+        Some(started_at) => println!("Started at: {}", started_at),
+        None => println!("Started at: (unknown)"),
+    }
+    println!("Difficulty: {}", difficulty_name(metadata.difficulty));
+    println!(
+        "Player matchup: {}",
+        player_matchup_name(metadata.player_matchup)
+    );
+    let settings = GameSettings::new(
+        metadata.game_settings.common,
+        metadata.game_settings.p1,
+        metadata.game_settings.p2,
+    );
+    println!("Time limit: {}", settings.time_limit());
+    println!("Round: {}", settings.round());
+    println!("Ability card: {:?}", settings.ability_card());
+    println!(
+        "P1 life: {}, barrier: {}",
+        settings.p1_life(),
+        settings.p1_barrier()
+    );
+    println!(
+        "P2 life: {}, barrier: {}",
+        settings.p2_life(),
+        settings.p2_barrier()
+    );
+    for (i, round) in replay.rounds.iter().enumerate() {
+        let seeds = &round.seeds;
+        println!(
+            "Round {}: {} frames (seeds: {} {} {} {})",
+            i + 1,
+            round.inputs.len(),
+            seeds.seed1,
+            seeds.seed2,
+            seeds.seed3,
+            seeds.seed4
+        );
+    }
+}
+
+fn print_inputs(replay: &Replay, round: Option<usize>) -> Result<()> {
+    if let Some(round) = round {
+        if round == 0 || round > replay.rounds.len() {
+            bail!("round {} does not exist", round);
+        }
+    }
+    for (i, r) in replay.rounds.iter().enumerate() {
+        if round.is_some_and(|x| x != i + 1) {
+            continue;
+        }
+        println!("Round {}", i + 1);
+        for (frame, &(p1, p2)) in r.inputs.iter().enumerate() {
+            println!("{:>7}  {:<32}  {}", frame, input_name(p1), input_name(p2));
+        }
+    }
+    Ok(())
+}
+
+fn percent(value: u32, total: u32) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    value as f64 / total as f64 * 100.0
+}
+
+fn print_player_stats(label: &str, name: &str, analytics: &PlayerAnalytics) {
+    println!(
+        "{}: {}",
+        label,
+        if name.is_empty() { "(unknown)" } else { name }
+    );
+    println!("  Inputs per second: {:.2}", analytics.inputs_per_second);
+    for (name, usage) in [
+        ("SHOT", &analytics.shot),
+        ("CHARGE", &analytics.charge),
+        ("BOMB", &analytics.bomb),
+        ("SLOW", &analytics.slow),
+    ] {
+        println!(
+            "  {:<6}  {:>5} presses, held {:>5.1}% of frames",
+            name,
+            usage.presses,
+            percent(usage.frames, analytics.frames)
+        );
+    }
+    match analytics.average_charge_hold() {
+        Some(average) => println!(
+            "  Charge holds: {} (average {:.1} frames, longest {} frames)",
+            analytics.charge_holds.len(),
+            average,
+            analytics.charge_holds.iter().max().unwrap()
+        ),
+        None => println!("  Charge holds: 0"),
+    }
+    println!("  Movement (% of frames):");
+    println!("          {:>6}  {:>6}  {:>6}", "LEFT", "-", "RIGHT");
+    for (name, row) in ["UP", "-", "DOWN"].iter().zip(&analytics.movement) {
+        println!(
+            "    {:<4}  {:>6.1}  {:>6.1}  {:>6.1}",
+            name,
+            percent(row[0], analytics.frames),
+            percent(row[1], analytics.frames),
+            percent(row[2], analytics.frames)
+        );
+    }
+}
+
+fn print_netplay_stats(analytics: &NetplayAnalytics) {
+    println!("Netplay:");
+    for (delay, frames) in &analytics.delays {
+        println!(
+            "  Delay {}: {} frames ({:.1}%)",
+            delay,
+            frames,
+            percent(*frames, analytics.frames)
+        );
+    }
+    println!(
+        "  Stalled frames: {} ({:.2}%), total {} ms, longest {} ms",
+        analytics.stalled_frames,
+        analytics.stall_rate() * 100.0,
+        analytics.total_stall_ms,
+        analytics.max_stall_ms
+    );
+}
+
+fn print_stats(replay: &Replay) {
+    let analytics = analyze(replay);
+    print_player_stats("P1", &replay.metadata.p1_name, &analytics.p1);
+    print_player_stats("P2", &replay.metadata.p2_name, &analytics.p2);
+    if let Some(netplay) = &analytics.netplay {
+        print_netplay_stats(netplay);
+    }
+}
+
+fn validate_metadata(metadata: &ReplayMetadata) -> Result<()> {
+    Difficulty::try_from(metadata.difficulty as u32)?;
+    PlayerMatchup::try_from(metadata.player_matchup as u32)?;
+    Ok(())
+}
+
+fn validate(replay: &Replay) -> Result<()> {
+    validate_metadata(&replay.metadata)?;
+    if replay.rounds.is_empty() {
+        bail!("no rounds");
+    }
+    for (i, round) in replay.rounds.iter().enumerate() {
+        if round.inputs.is_empty() {
+            bail!("round {} has no inputs", i + 1);
+        }
+        let Some(netplay) = &round.netplay else {
+            continue;
+        };
+        let frames = round.inputs.len() as u32;
+        let delay_frames = netplay.delays.iter().map(|&(frame, _)| frame);
+        let stall_frames = netplay.stalls.iter().map(|&(frame, _)| frame);
+        if delay_frames
+            .chain(stall_frames)
+            .any(|frame| frame >= frames)
+        {
+            bail!("round {} has netplay records out of range", i + 1);
+        }
+    }
+    Ok(())
+}
+
+fn trim(replay: &mut Replay, first: usize, last: Option<usize>) -> Result<()> {
+    let last = last.unwrap_or(replay.rounds.len());
+    if first == 0 || first > last || last > replay.rounds.len() {
+        bail!(
+            "invalid range {}..={} (the replay has {} rounds)",
+            first,
+            last,
+            replay.rounds.len()
+        );
+    }
+    replay.rounds = replay.rounds[first - 1..last].to_vec();
+    Ok(())
+}
+
+fn parse_round(arg: Option<String>) -> Result<Option<usize>> {
+    arg.map(|x| x.parse().with_context(|| format!("invalid round: {}", x)))
+        .transpose()
+}
+
+fn main() -> Result<()> {
+    let mut args = args().skip(1);
+    let command = args.next();
+    let mut next_arg = || args.next().with_context(|| USAGE.to_owned());
+    match command.as_deref() {
+        Some("info") => print_info(&read_replay(&next_arg()?)?),
+        Some("inputs") => {
+            let replay = read_replay(&next_arg()?)?;
+            print_inputs(&replay, parse_round(next_arg().ok())?)?;
+        }
+        Some("stats") => print_stats(&read_replay(&next_arg()?)?),
+        Some("convert") => {
+            let replay = read_replay(&next_arg()?)?;
+            write_replay(&next_arg()?, &replay)?;
+        }
+        Some("trim") => {
+            let mut replay = read_replay(&next_arg()?)?;
+            let output = next_arg()?;
+            let first = parse_round(Some(next_arg()?))?.unwrap();
+            trim(&mut replay, first, parse_round(next_arg().ok())?)?;
+            write_replay(&output, &replay)?;
+        }
+        Some("validate") => {
+            let path = next_arg()?;
+            let replay = read_replay(&path)?;
+            validate(&replay).with_context(|| format!("{} is invalid", path))?;
+            let frames: usize = replay.rounds.iter().map(|x| x.inputs.len()).sum();
+            println!(
+                "{}: OK ({} rounds, {} frames)",
+                path,
+                replay.rounds.len(),
+                frames
+            );
+        }
+        _ => bail!("{}", USAGE),
+    }
+    Ok(())
+}
@@ -1,17 +1,29 @@
 mod dynamodb;
 mod file;
+mod memory;
+mod sqlite;
+
+use std::{
+    collections::BTreeMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use async_trait::async_trait;
 use derive_new::new;
 pub use dynamodb::DynamoDB;
 pub use file::File;
+pub use memory::Memory;
+pub use sqlite::Sqlite;
 
 use anyhow::Result;
-use getset::{Getters, Setters};
-use junowen_lib::connection::signaling::CompressedSdp;
+use getset::{CopyGetters, Getters, Setters};
+use junowen_lib::{
+    connection::signaling::CompressedSdp,
+    signaling_server::{quick_match::QuickMatchFilter, version::ClientVersion},
+};
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, Deserialize, Getters, Serialize, new)]
+#[derive(Clone, CopyGetters, Debug, Deserialize, Getters, Serialize, Setters, new)]
 pub struct SharedRoom {
     /// primary
     #[get = "pub"]
@@ -21,6 +33,26 @@ pub struct SharedRoom {
     key: String,
     #[get = "pub"]
     sdp: CompressedSdp,
+    /// ヘッダーを送らない古いクライアントは None
+    #[get = "pub"]
+    client_version: Option<ClientVersion>,
+    /// ルーム一覧に載せるか
+    #[serde(default)]
+    #[get_copy = "pub"]
+    public: bool,
+    #[serde(default)]
+    #[new(value = "now_sec()")]
+    #[get_copy = "pub"]
+    created_at_sec: u64,
+    /// クイックマッチの待ち行列に入っているルーム
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[get = "pub"]
+    quick_match: Option<QuickMatchFilter>,
+    /// 作成したクライアントの IP アドレスのハッシュ
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[new(default)]
+    #[getset(get = "pub", set = "pub")]
+    owner: Option<String>,
     ttl_sec: u64,
 }
 
@@ -34,7 +66,7 @@ impl SharedRoom {
     }
 }
 
-#[derive(Serialize, Getters, Deserialize, new)]
+#[derive(Clone, Serialize, Getters, Deserialize, new)]
 pub struct Answer {
     /// primary
     #[get = "pub"]
@@ -62,6 +94,11 @@ pub enum PutError {
 pub trait SharedRoomTables: Send + Sync + 'static {
     async fn put_room(&self, offer: SharedRoom) -> Result<(), PutError>;
     async fn find_room(&self, name: String) -> Result<Option<SharedRoom>>;
+    async fn find_public_rooms(&self) -> Result<Vec<SharedRoom>>;
+    async fn find_quick_match_rooms(&self) -> Result<Vec<SharedRoom>>;
+    /// クイックマッチの待ち行列から外す。他のリクエストが先に外していれば false
+    async fn claim_quick_match_room(&self, name: String) -> Result<bool>;
+    async fn find_rooms_by_owner(&self, owner: String) -> Result<Vec<SharedRoom>>;
     async fn keep_room(&self, name: String, key: String, ttl_sec: u64) -> Result<bool>;
     async fn remove_room(&self, name: String, key: Option<String>) -> Result<bool>;
 
@@ -75,7 +112,7 @@ pub trait SharedRoomTables: Send + Sync + 'static {
     ) -> Result<Option<SharedRoomOpponentAnswer>>;
 }
 
-#[derive(Clone, Debug, Deserialize, Getters, Setters, Serialize, new)]
+#[derive(Clone, CopyGetters, Debug, Deserialize, Getters, Setters, Serialize, new)]
 pub struct ReservedRoom {
     /// primary
     #[get = "pub"]
@@ -85,8 +122,40 @@ pub struct ReservedRoom {
     key: String,
     #[getset(get = "pub", set = "pub")]
     opponent_offer_sdp: Option<CompressedSdp>,
+    /// 観戦枠を使わない古いホストのオファー
     #[get = "pub"]
     spectator_offer_sdp: Option<CompressedSdp>,
+    /// 観戦枠の ID ごとのオファー
+    #[serde(default)]
+    #[new(default)]
+    #[get = "pub"]
+    spectator_offer_sdps: BTreeMap<String, CompressedSdp>,
+    /// ヘッダーを送らない古いクライアントは None
+    #[get = "pub"]
+    client_version: Option<ClientVersion>,
+    /// ルーム一覧に載せるか
+    #[serde(default)]
+    #[get_copy = "pub"]
+    public: bool,
+    #[serde(default)]
+    #[new(value = "now_sec()")]
+    #[get_copy = "pub"]
+    created_at_sec: u64,
+    /// 作成したクライアントの IP アドレスのハッシュ
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[new(default)]
+    #[getset(get = "pub", set = "pub")]
+    owner: Option<String>,
+    /// 対戦者用のパスワードのハッシュ
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[new(default)]
+    #[getset(get = "pub", set = "pub")]
+    player_password_hash: Option<String>,
+    /// 観戦者用のパスワードのハッシュ
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[new(default)]
+    #[getset(get = "pub", set = "pub")]
+    spectator_password_hash: Option<String>,
     ttl_sec: u64,
 }
 
@@ -95,10 +164,18 @@ impl ReservedRoom {
         self.opponent_offer_sdp
     }
 
-    pub fn into_opponent_offer_sdp_spectator_offer_sdp(
+    pub fn into_offer_sdps(
         self,
-    ) -> (Option<CompressedSdp>, Option<CompressedSdp>) {
-        (self.opponent_offer_sdp, self.spectator_offer_sdp)
+    ) -> (
+        Option<CompressedSdp>,
+        Option<CompressedSdp>,
+        BTreeMap<String, CompressedSdp>,
+    ) {
+        (
+            self.opponent_offer_sdp,
+            self.spectator_offer_sdp,
+            self.spectator_offer_sdps,
+        )
     }
 
     pub fn is_expired(&self, now_sec: u64) -> bool {
@@ -106,24 +183,32 @@ impl ReservedRoom {
     }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct ReservedRoomOpponentAnswer(pub Answer);
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct ReservedRoomSpectatorAnswer(pub Answer);
 
 #[async_trait]
 pub trait ReservedRoomTables: Send + Sync + 'static {
     async fn put_room(&self, offer: ReservedRoom) -> Result<(), PutError>;
     async fn find_room(&self, name: String) -> Result<Option<ReservedRoom>>;
+    async fn find_public_rooms(&self) -> Result<Vec<ReservedRoom>>;
+    async fn find_rooms_by_owner(&self, owner: String) -> Result<Vec<ReservedRoom>>;
+    /// spectator_id が None なら古いホストのオファーとして保存する
     async fn keep_room(
         &self,
         name: String,
         key: String,
+        spectator_id: Option<String>,
         spectator_offer_sdp: Option<CompressedSdp>,
         ttl_sec: u64,
     ) -> Result<Option<ReservedRoom>>;
     async fn remove_opponent_offer_sdp_in_room(&self, name: String) -> Result<bool>;
-    async fn remove_spectator_offer_sdp_in_room(&self, name: String) -> Result<bool>;
+    async fn remove_spectator_offer_sdp_in_room(
+        &self,
+        name: String,
+        spectator_id: Option<String>,
+    ) -> Result<bool>;
     async fn remove_room(&self, name: String, key: Option<String>) -> Result<bool>;
 
     async fn put_room_opponent_answer(
@@ -145,4 +230,94 @@ pub trait ReservedRoomTables: Send + Sync + 'static {
     ) -> Result<Option<ReservedRoomSpectatorAnswer>>;
 }
 
-pub trait Database: SharedRoomTables + ReservedRoomTables {}
+/// 時間枠ごとのリクエスト数
+#[derive(Clone, Deserialize, Serialize, new)]
+pub struct RequestCounter {
+    /// primary
+    name: String,
+    count: u32,
+    ttl_sec: u64,
+}
+
+#[async_trait]
+pub trait RateLimitTables: Send + Sync + 'static {
+    /// カウンターを 1 増やして増やした後の値を返す。無ければ ttl_sec で失効するカウンターを作る
+    async fn increment_request_count(&self, name: String, ttl_sec: u64) -> Result<u32>;
+}
+
+pub trait Database: SharedRoomTables + ReservedRoomTables + RateLimitTables {}
+
+fn now_sec() -> u64 {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    now.as_secs()
+}
+
+/// name をプライマリキーとし、ttl_sec で失効するテーブルのアイテム
+trait Item: Clone {
+    fn name(&self) -> &str;
+    fn ttl_sec(&self) -> u64;
+
+    fn is_expired(&self, now_sec: u64) -> bool {
+        now_sec > self.ttl_sec()
+    }
+}
+
+impl Item for SharedRoom {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn ttl_sec(&self) -> u64 {
+        self.ttl_sec
+    }
+}
+
+impl Item for Answer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn ttl_sec(&self) -> u64 {
+        self.ttl_sec
+    }
+}
+
+impl Item for ReservedRoom {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn ttl_sec(&self) -> u64 {
+        self.ttl_sec
+    }
+}
+
+impl Item for RequestCounter {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn ttl_sec(&self) -> u64 {
+        self.ttl_sec
+    }
+}
+
+impl Item for ReservedRoomOpponentAnswer {
+    fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    fn ttl_sec(&self) -> u64 {
+        self.0.ttl_sec
+    }
+}
+
+impl Item for ReservedRoomSpectatorAnswer {
+    fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    fn ttl_sec(&self) -> u64 {
+        self.0.ttl_sec
+    }
+}
@@ -1,11 +1,13 @@
+mod rate_limit;
 mod reserved_room;
 mod shared_room;
 
-use std::env;
+use std::{collections::HashMap, env};
 
 use anyhow::Result;
 use aws_sdk_dynamodb::{
     error::SdkError,
+    operation::query::builders::QueryFluentBuilder,
     types::{AttributeValue, ReturnValue},
 };
 use serde::{Deserialize, Serialize};
@@ -13,6 +15,31 @@ use serde_dynamo::{from_item, to_item};
 
 use super::{Database, PutError};
 
+/// 一覧に載せるルームだけが持つ属性をパーティションキーとする疎なインデックス
+const PUBLIC_INDEX: &str = "public-index";
+const PUBLIC_INDEX_KEY: &str = "public_index_key";
+/// クイックマッチの待ち行列に入っているルームだけが持つ属性をパーティションキーとする疎なインデックス
+const QUICK_MATCH_INDEX: &str = "quick-match-index";
+const QUICK_MATCH_INDEX_KEY: &str = "quick_match_index_key";
+/// 所有者を記録したルームだけが載る、owner をパーティションキーとするインデックス
+const OWNER_INDEX: &str = "owner-index";
+
+/// インデックスのキーは真偽値にできないので、対象のルームにだけ文字列の属性を足す
+fn add_index_keys(item: &mut HashMap<String, AttributeValue>) {
+    if item.get("public") == Some(&AttributeValue::Bool(true)) {
+        item.insert(
+            PUBLIC_INDEX_KEY.to_owned(),
+            AttributeValue::S("public".to_owned()),
+        );
+    }
+    if item.contains_key("quick_match") {
+        item.insert(
+            QUICK_MATCH_INDEX_KEY.to_owned(),
+            AttributeValue::S("quick_match".to_owned()),
+        );
+    }
+}
+
 pub struct DynamoDB {
     client: aws_sdk_dynamodb::Client,
     table_name_shared_room: String,
@@ -20,6 +47,7 @@ pub struct DynamoDB {
     table_name_reserved_room: String,
     table_name_reserved_room_opponent_answer: String,
     table_name_reserved_room_spectator_answer: String,
+    table_name_request_counter: String,
 }
 
 impl DynamoDB {
@@ -38,11 +66,13 @@ impl DynamoDB {
                 "{}.ReservedRoomSpectatorAnswer",
                 env::var("ENV").unwrap()
             ),
+            table_name_request_counter: format!("{}.RequestCounter", env::var("ENV").unwrap()),
         }
     }
 
     pub async fn put_item(&self, table_name: &str, item: impl Serialize) -> Result<(), PutError> {
-        let item = to_item(item).map_err(|err| PutError::Unknown(err.into()))?;
+        let mut item: HashMap<_, _> = to_item(item).map_err(|err| PutError::Unknown(err.into()))?;
+        add_index_keys(&mut item);
         let result = self
             .client
             .put_item()
@@ -85,6 +115,58 @@ impl DynamoDB {
         Ok(Some(from_item(item.to_owned())?))
     }
 
+    async fn query_items<'a, T>(&self, query: QueryFluentBuilder) -> Result<Vec<T>>
+    where
+        T: Deserialize<'a>,
+    {
+        let mut items = vec![];
+        let mut exclusive_start_key = None;
+        loop {
+            let output = query
+                .clone()
+                .set_exclusive_start_key(exclusive_start_key)
+                .send()
+                .await?;
+            for item in output.items().unwrap_or_default() {
+                items.push(from_item(item.to_owned())?);
+            }
+            exclusive_start_key = output.last_evaluated_key().cloned();
+            if exclusive_start_key.is_none() {
+                return Ok(items);
+            }
+        }
+    }
+
+    async fn find_public_items<'a, T>(&self, table_name: &str) -> Result<Vec<T>>
+    where
+        T: Deserialize<'a>,
+    {
+        let query = self
+            .client
+            .query()
+            .table_name(table_name)
+            .index_name(PUBLIC_INDEX)
+            .key_condition_expression("#key = :key")
+            .expression_attribute_names("#key", PUBLIC_INDEX_KEY)
+            .expression_attribute_values(":key", AttributeValue::S("public".to_owned()));
+        self.query_items(query).await
+    }
+
+    async fn find_items_by_owner<'a, T>(&self, table_name: &str, owner: String) -> Result<Vec<T>>
+    where
+        T: Deserialize<'a>,
+    {
+        let query = self
+            .client
+            .query()
+            .table_name(table_name)
+            .index_name(OWNER_INDEX)
+            .key_condition_expression("#owner = :owner")
+            .expression_attribute_names("#owner", "owner")
+            .expression_attribute_values(":owner", AttributeValue::S(owner));
+        self.query_items(query).await
+    }
+
     async fn remove_item_and_get_old<'a, T>(
         &self,
         table_name: &str,
@@ -0,0 +1,32 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use aws_sdk_dynamodb::types::{AttributeValue, ReturnValue};
+
+use crate::database::RateLimitTables;
+
+use super::DynamoDB;
+
+#[async_trait]
+impl RateLimitTables for DynamoDB {
+    async fn increment_request_count(&self, name: String, ttl_sec: u64) -> Result<u32> {
+        let output = self
+            .client
+            .update_item()
+            .table_name(&self.table_name_request_counter)
+            .key("name", AttributeValue::S(name))
+            .update_expression("SET #ttl_sec = if_not_exists(#ttl_sec, :ttl_sec) ADD #count :one")
+            .expression_attribute_names("#ttl_sec", "ttl_sec")
+            .expression_attribute_values(":ttl_sec", AttributeValue::N(ttl_sec.to_string()))
+            .expression_attribute_names("#count", "count")
+            .expression_attribute_values(":one", AttributeValue::N("1".to_owned()))
+            .return_values(ReturnValue::UpdatedNew)
+            .send()
+            .await?;
+        let count = output
+            .attributes()
+            .and_then(|x| x.get("count"))
+            .and_then(|x| x.as_n().ok())
+            .ok_or_else(|| anyhow!("count not found"))?;
+        Ok(count.parse()?)
+    }
+}
@@ -24,10 +24,20 @@ impl database::ReservedRoomTables for DynamoDB {
             .await
     }
 
+    async fn find_public_rooms(&self) -> Result<Vec<ReservedRoom>> {
+        self.find_public_items(&self.table_name_reserved_room).await
+    }
+
+    async fn find_rooms_by_owner(&self, owner: String) -> Result<Vec<ReservedRoom>> {
+        self.find_items_by_owner(&self.table_name_reserved_room, owner)
+            .await
+    }
+
     async fn keep_room(
         &self,
         name: String,
         key: String,
+        spectator_id: Option<String>,
         spectator_offer_sdp: Option<CompressedSdp>,
         ttl_sec: u64,
     ) -> Result<Option<ReservedRoom>> {
@@ -43,15 +53,25 @@ impl database::ReservedRoomTables for DynamoDB {
             .expression_attribute_names("#ttl_sec", "ttl_sec")
             .expression_attribute_values(":ttl_sec", AttributeValue::N((ttl_sec).to_string()));
         if let Some(spectator_offer_sdp) = spectator_offer_sdp {
-            builder = builder
-                .expression_attribute_names("#spectator_offer_sdp", "spectator_offer_sdp")
-                .expression_attribute_values(
-                    ":spectator_offer_sdp",
-                    AttributeValue::S(spectator_offer_sdp.into_inner()),
-                )
-                .update_expression(
-                    "SET #ttl_sec = :ttl_sec, #spectator_offer_sdp = :spectator_offer_sdp",
-                );
+            builder = builder.expression_attribute_values(
+                ":spectator_offer_sdp",
+                AttributeValue::S(spectator_offer_sdp.into_inner()),
+            );
+            // spectator_offer_sdps はルームの作成時に空のマップとして保存されている
+            builder = if let Some(spectator_id) = spectator_id {
+                builder
+                    .expression_attribute_names("#spectator_offer_sdps", "spectator_offer_sdps")
+                    .expression_attribute_names("#spectator_id", spectator_id)
+                    .update_expression(
+                        "SET #ttl_sec = :ttl_sec, #spectator_offer_sdps.#spectator_id = :spectator_offer_sdp",
+                    )
+            } else {
+                builder
+                    .expression_attribute_names("#spectator_offer_sdp", "spectator_offer_sdp")
+                    .update_expression(
+                        "SET #ttl_sec = :ttl_sec, #spectator_offer_sdp = :spectator_offer_sdp",
+                    )
+            };
         } else {
             builder = builder.update_expression("SET #ttl_sec = :ttl_sec");
         }
@@ -96,17 +116,28 @@ impl database::ReservedRoomTables for DynamoDB {
         Ok(true)
     }
 
-    async fn remove_spectator_offer_sdp_in_room(&self, name: String) -> Result<bool> {
-        let result = self
+    async fn remove_spectator_offer_sdp_in_room(
+        &self,
+        name: String,
+        spectator_id: Option<String>,
+    ) -> Result<bool> {
+        let builder = self
             .client
             .update_item()
             .table_name(&self.table_name_reserved_room)
-            .key("name", AttributeValue::S(name))
-            .update_expression("SET #spectator_offer_sdp = :spectator_offer_sdp")
-            .expression_attribute_names("#spectator_offer_sdp", "spectator_offer_sdp")
-            .expression_attribute_values(":spectator_offer_sdp", AttributeValue::Null(true))
-            .send()
-            .await;
+            .key("name", AttributeValue::S(name));
+        let builder = if let Some(spectator_id) = spectator_id {
+            builder
+                .update_expression("REMOVE #spectator_offer_sdps.#spectator_id")
+                .expression_attribute_names("#spectator_offer_sdps", "spectator_offer_sdps")
+                .expression_attribute_names("#spectator_id", spectator_id)
+        } else {
+            builder
+                .update_expression("SET #spectator_offer_sdp = :spectator_offer_sdp")
+                .expression_attribute_names("#spectator_offer_sdp", "spectator_offer_sdp")
+                .expression_attribute_values(":spectator_offer_sdp", AttributeValue::Null(true))
+        };
+        let result = builder.send().await;
         if let Err(err) = result {
             if let SdkError::ServiceError(service_error) = &err {
                 if service_error.err().is_conditional_check_failed_exception() {
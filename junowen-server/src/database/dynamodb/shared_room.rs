@@ -4,7 +4,7 @@ use aws_sdk_dynamodb::{error::SdkError, types::AttributeValue};
 
 use crate::database::{PutError, SharedRoom, SharedRoomOpponentAnswer, SharedRoomTables};
 
-use super::DynamoDB;
+use super::{DynamoDB, QUICK_MATCH_INDEX, QUICK_MATCH_INDEX_KEY};
 
 #[async_trait]
 impl SharedRoomTables for DynamoDB {
@@ -17,6 +17,50 @@ impl SharedRoomTables for DynamoDB {
             .await
     }
 
+    async fn find_public_rooms(&self) -> Result<Vec<SharedRoom>> {
+        self.find_public_items(&self.table_name_shared_room).await
+    }
+
+    async fn find_quick_match_rooms(&self) -> Result<Vec<SharedRoom>> {
+        let query = self
+            .client
+            .query()
+            .table_name(&self.table_name_shared_room)
+            .index_name(QUICK_MATCH_INDEX)
+            .key_condition_expression("#key = :key")
+            .expression_attribute_names("#key", QUICK_MATCH_INDEX_KEY)
+            .expression_attribute_values(":key", AttributeValue::S("quick_match".to_owned()));
+        self.query_items(query).await
+    }
+
+    async fn claim_quick_match_room(&self, name: String) -> Result<bool> {
+        let result = self
+            .client
+            .update_item()
+            .table_name(&self.table_name_shared_room)
+            .key("name", AttributeValue::S(name))
+            .condition_expression("attribute_exists(#quick_match)")
+            .update_expression("REMOVE #quick_match, #key")
+            .expression_attribute_names("#quick_match", "quick_match")
+            .expression_attribute_names("#key", QUICK_MATCH_INDEX_KEY)
+            .send()
+            .await;
+        if let Err(err) = result {
+            if let SdkError::ServiceError(service_error) = &err {
+                if service_error.err().is_conditional_check_failed_exception() {
+                    return Ok(false);
+                }
+            }
+            return Err(err.into());
+        }
+        Ok(true)
+    }
+
+    async fn find_rooms_by_owner(&self, owner: String) -> Result<Vec<SharedRoom>> {
+        self.find_items_by_owner(&self.table_name_shared_room, owner)
+            .await
+    }
+
     async fn keep_room(&self, name: String, key: String, ttl_sec: u64) -> Result<bool> {
         let result = self
             .client
@@ -1,199 +1,212 @@
+use std::{io::ErrorKind, path::PathBuf};
+
 use anyhow::Result;
 use async_trait::async_trait;
 use junowen_lib::connection::signaling::CompressedSdp;
-use serde_json::Value;
-use tokio::fs;
+use tokio::{fs, sync::Mutex};
 
 use super::{
-    Answer, Database, PutError, ReservedRoom, ReservedRoomOpponentAnswer,
+    Database, Memory, PutError, RateLimitTables, ReservedRoom, ReservedRoomOpponentAnswer,
     ReservedRoomSpectatorAnswer, ReservedRoomTables, SharedRoom, SharedRoomOpponentAnswer,
     SharedRoomTables,
 };
 
-pub struct File;
+/// Memory の内容を変更の度に JSON ファイルへ書き出す
+pub struct File {
+    path: PathBuf,
+    memory: Memory,
+    write_lock: Mutex<()>,
+}
 
 impl File {
-    async fn read(&self) -> Result<Value> {
-        Ok(
-            serde_json::from_str(&fs::read_to_string("store.json").await.unwrap_or_default())
-                .unwrap_or_default(),
-        )
+    pub async fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let memory = match fs::read_to_string(&path).await {
+            Ok(json) => serde_json::from_str(&json)?,
+            Err(err) if err.kind() == ErrorKind::NotFound => Memory::default(),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(Self {
+            path,
+            memory,
+            write_lock: Mutex::new(()),
+        })
+    }
+
+    async fn write(&self) -> Result<()> {
+        let _guard = self.write_lock.lock().await;
+        let json = serde_json::to_string_pretty(&self.memory)?;
+        let tmp_path = self.path.with_extension("tmp");
+        fs::write(&tmp_path, json).await?;
+        fs::rename(&tmp_path, &self.path).await?;
+        Ok(())
+    }
+
+    async fn write_and_return<T>(&self, ret: Result<T>) -> Result<T> {
+        self.write().await?;
+        ret
     }
 
-    async fn write(&self, value: Value) -> Result<()> {
-        Ok(fs::write("store.json", serde_json::to_string_pretty(&value)?).await?)
+    async fn write_and_return_put(&self, ret: Result<(), PutError>) -> Result<(), PutError> {
+        self.write().await.map_err(PutError::Unknown)?;
+        ret
     }
 }
 
 #[async_trait]
 impl SharedRoomTables for File {
-    async fn put_room(&self, offer: SharedRoom) -> Result<(), PutError> {
-        let mut store = self.read().await.map_err(PutError::Unknown)?;
-        if store.get("offers").is_none() {
-            store["offers"] = Value::Array(vec![]);
-        }
-        let array = store["offers"].as_array_mut().unwrap();
-        if array
-            .iter()
-            .map(|x| serde_json::from_value::<SharedRoom>(x.clone()).unwrap())
-            .any(|x| x.name() == &offer.name)
-        {
-            return Err(PutError::Conflict);
-        }
-        array.push(serde_json::to_value(offer).map_err(|err| PutError::Unknown(err.into()))?);
-
-        self.write(store).await.map_err(PutError::Unknown)?;
-        Ok(())
+    async fn put_room(&self, room: SharedRoom) -> Result<(), PutError> {
+        let ret = SharedRoomTables::put_room(&self.memory, room).await;
+        self.write_and_return_put(ret).await
     }
 
     async fn find_room(&self, name: String) -> Result<Option<SharedRoom>> {
-        let store = self.read().await?;
-        let Some(offers) = store.get("offers") else {
-            return Ok(None);
-        };
-        let Some(offers) = offers.as_array() else {
-            return Ok(None);
-        };
-        Ok(offers
-            .iter()
-            .map(|x| serde_json::from_value(x.clone()).unwrap())
-            .find(|x: &SharedRoom| x.name == name))
+        SharedRoomTables::find_room(&self.memory, name).await
+    }
+
+    async fn find_public_rooms(&self) -> Result<Vec<SharedRoom>> {
+        SharedRoomTables::find_public_rooms(&self.memory).await
+    }
+
+    async fn find_quick_match_rooms(&self) -> Result<Vec<SharedRoom>> {
+        self.memory.find_quick_match_rooms().await
+    }
+
+    async fn claim_quick_match_room(&self, name: String) -> Result<bool> {
+        let ret = self.memory.claim_quick_match_room(name).await;
+        self.write_and_return(ret).await
+    }
+
+    async fn find_rooms_by_owner(&self, owner: String) -> Result<Vec<SharedRoom>> {
+        SharedRoomTables::find_rooms_by_owner(&self.memory, owner).await
     }
 
     async fn keep_room(&self, name: String, key: String, ttl_sec: u64) -> Result<bool> {
-        let mut store = self.read().await?;
-        if store.get("offers").is_none() {
-            store["offers"] = Value::Array(vec![]);
-        }
-        let array = store["offers"].as_array_mut().unwrap();
-        let Some(offer) = array.iter_mut().find(|x| {
-            let offer = serde_json::from_value::<SharedRoom>((*x).to_owned()).unwrap();
-            offer.name() == &name && offer.key() == &key
-        }) else {
-            return Ok(false);
-        };
-        let mut new_offer = serde_json::from_value::<SharedRoom>(offer.to_owned()).unwrap();
-        new_offer.ttl_sec = ttl_sec;
-        *offer = serde_json::to_value(new_offer).unwrap();
-        self.write(store).await?;
-        unimplemented!()
-    }
-
-    async fn remove_room(&self, name: String, _key: Option<String>) -> Result<bool> {
-        let mut store = self.read().await?;
-        if store.get("offers").is_none() {
-            store["offers"] = Value::Array(vec![]);
-        }
-        store["offers"].as_array_mut().unwrap().retain(|x| {
-            serde_json::from_value::<SharedRoom>(x.clone())
-                .unwrap()
-                .name
-                == name
-        });
-        self.write(store).await?;
-        todo!()
+        let ret = SharedRoomTables::keep_room(&self.memory, name, key, ttl_sec).await;
+        self.write_and_return(ret).await
+    }
+
+    async fn remove_room(&self, name: String, key: Option<String>) -> Result<bool> {
+        let ret = SharedRoomTables::remove_room(&self.memory, name, key).await;
+        self.write_and_return(ret).await
     }
 
     async fn put_room_opponent_answer(
         &self,
         answer: SharedRoomOpponentAnswer,
     ) -> Result<(), PutError> {
-        let mut store = self.read().await.map_err(PutError::Unknown)?;
-        if store.get("answers").is_none() {
-            store["answers"] = Value::Array(vec![]);
-        }
-        let array = store["answers"].as_array_mut().unwrap();
-        if array
-            .iter()
-            .map(|x| serde_json::from_value::<SharedRoom>(x.clone()).unwrap())
-            .any(|x| x.name() == &answer.name)
-        {
-            return Err(PutError::Conflict);
-        }
-        array.push(serde_json::to_value(answer).map_err(|err| PutError::Unknown(err.into()))?);
-
-        self.write(store).await.map_err(PutError::Unknown)?;
-        Ok(())
+        let ret = SharedRoomTables::put_room_opponent_answer(&self.memory, answer).await;
+        self.write_and_return_put(ret).await
     }
 
     async fn remove_room_opponent_answer(
         &self,
         name: String,
     ) -> Result<Option<SharedRoomOpponentAnswer>> {
-        let store = self.read().await?;
-        let Some(offers) = store.get("answer") else {
-            return Ok(None);
-        };
-        let Some(offers) = offers.as_array() else {
-            return Ok(None);
-        };
-        let _item = offers
-            .iter()
-            .filter_map(|x| serde_json::from_value(x.clone()).unwrap())
-            .find(|x: &Answer| x.name == name);
-
-        unimplemented!()
+        let ret = SharedRoomTables::remove_room_opponent_answer(&self.memory, name).await;
+        self.write_and_return(ret).await
     }
 }
 
 #[async_trait]
 impl ReservedRoomTables for File {
-    async fn put_room(&self, _offer: ReservedRoom) -> Result<(), PutError> {
-        unimplemented!();
+    async fn put_room(&self, room: ReservedRoom) -> Result<(), PutError> {
+        let ret = ReservedRoomTables::put_room(&self.memory, room).await;
+        self.write_and_return_put(ret).await
+    }
+
+    async fn find_room(&self, name: String) -> Result<Option<ReservedRoom>> {
+        ReservedRoomTables::find_room(&self.memory, name).await
+    }
+
+    async fn find_public_rooms(&self) -> Result<Vec<ReservedRoom>> {
+        ReservedRoomTables::find_public_rooms(&self.memory).await
     }
 
-    async fn find_room(&self, _name: String) -> Result<Option<ReservedRoom>> {
-        unimplemented!();
+    async fn find_rooms_by_owner(&self, owner: String) -> Result<Vec<ReservedRoom>> {
+        ReservedRoomTables::find_rooms_by_owner(&self.memory, owner).await
+    }
+
+    async fn keep_room(
+        &self,
+        name: String,
+        key: String,
+        spectator_id: Option<String>,
+        spectator_offer_sdp: Option<CompressedSdp>,
+        ttl_sec: u64,
+    ) -> Result<Option<ReservedRoom>> {
+        let ret = ReservedRoomTables::keep_room(
+            &self.memory,
+            name,
+            key,
+            spectator_id,
+            spectator_offer_sdp,
+            ttl_sec,
+        )
+        .await;
+        self.write_and_return(ret).await
     }
 
-    async fn remove_room(&self, _name: String, _key: Option<String>) -> Result<bool> {
-        unimplemented!();
+    async fn remove_opponent_offer_sdp_in_room(&self, name: String) -> Result<bool> {
+        let ret = self.memory.remove_opponent_offer_sdp_in_room(name).await;
+        self.write_and_return(ret).await
     }
 
-    async fn remove_opponent_offer_sdp_in_room(&self, _name: String) -> Result<bool> {
-        unimplemented!()
+    async fn remove_spectator_offer_sdp_in_room(
+        &self,
+        name: String,
+        spectator_id: Option<String>,
+    ) -> Result<bool> {
+        let ret = self
+            .memory
+            .remove_spectator_offer_sdp_in_room(name, spectator_id)
+            .await;
+        self.write_and_return(ret).await
     }
 
-    async fn remove_spectator_offer_sdp_in_room(&self, _name: String) -> Result<bool> {
-        unimplemented!()
+    async fn remove_room(&self, name: String, key: Option<String>) -> Result<bool> {
+        let ret = ReservedRoomTables::remove_room(&self.memory, name, key).await;
+        self.write_and_return(ret).await
     }
 
     async fn put_room_opponent_answer(
         &self,
-        _answer: ReservedRoomOpponentAnswer,
+        answer: ReservedRoomOpponentAnswer,
     ) -> Result<(), PutError> {
-        unimplemented!()
+        let ret = ReservedRoomTables::put_room_opponent_answer(&self.memory, answer).await;
+        self.write_and_return_put(ret).await
     }
 
     async fn remove_room_opponent_answer(
         &self,
-        _name: String,
+        name: String,
     ) -> Result<Option<ReservedRoomOpponentAnswer>> {
-        unimplemented!()
+        let ret = ReservedRoomTables::remove_room_opponent_answer(&self.memory, name).await;
+        self.write_and_return(ret).await
     }
 
     async fn put_room_spectator_answer(
         &self,
-        _answer: ReservedRoomSpectatorAnswer,
+        answer: ReservedRoomSpectatorAnswer,
     ) -> Result<(), PutError> {
-        unimplemented!()
+        let ret = self.memory.put_room_spectator_answer(answer).await;
+        self.write_and_return_put(ret).await
     }
 
     async fn remove_room_spectator_answer(
         &self,
-        _name: String,
+        name: String,
     ) -> Result<Option<ReservedRoomSpectatorAnswer>> {
-        unimplemented!()
+        let ret = self.memory.remove_room_spectator_answer(name).await;
+        self.write_and_return(ret).await
     }
+}
 
-    async fn keep_room(
-        &self,
-        _name: String,
-        _key: String,
-        _spectator_offer_sdp: Option<CompressedSdp>,
-        _ttl_sec: u64,
-    ) -> Result<Option<ReservedRoom>> {
-        unimplemented!()
+/// カウンターはファイルに書き出さない
+#[async_trait]
+impl RateLimitTables for File {
+    async fn increment_request_count(&self, name: String, ttl_sec: u64) -> Result<u32> {
+        self.memory.increment_request_count(name, ttl_sec).await
     }
 }
 
@@ -0,0 +1,280 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use junowen_lib::connection::signaling::CompressedSdp;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    now_sec, Database, Item, PutError, RateLimitTables, RequestCounter, ReservedRoom,
+    ReservedRoomOpponentAnswer, ReservedRoomSpectatorAnswer, ReservedRoomTables, SharedRoom,
+    SharedRoomOpponentAnswer, SharedRoomTables,
+};
+
+/// name をプライマリキーとするテーブル
+///
+/// DynamoDB の TTL と同様に、ttl_sec を過ぎたアイテムは存在しないものとして扱う。
+#[derive(Deserialize, Serialize)]
+#[serde(transparent)]
+struct Table<T>(Mutex<HashMap<String, T>>);
+
+impl<T> Default for Table<T> {
+    fn default() -> Self {
+        Self(Mutex::new(HashMap::new()))
+    }
+}
+
+impl<T: Item> Table<T> {
+    fn with<R>(&self, func: impl FnOnce(&mut HashMap<String, T>) -> R) -> R {
+        let mut items = self.0.lock().unwrap();
+        let now_sec = now_sec();
+        items.retain(|_, item| !item.is_expired(now_sec));
+        func(&mut items)
+    }
+
+    fn put(&self, item: T) -> Result<(), PutError> {
+        self.with(|items| {
+            if items.contains_key(item.name()) {
+                return Err(PutError::Conflict);
+            }
+            items.insert(item.name().to_owned(), item);
+            Ok(())
+        })
+    }
+
+    fn find(&self, name: &str) -> Option<T> {
+        self.with(|items| items.get(name).cloned())
+    }
+
+    fn find_all(&self, predicate: impl Fn(&T) -> bool) -> Vec<T> {
+        self.with(|items| items.values().filter(|x| predicate(x)).cloned().collect())
+    }
+
+    fn remove(&self, name: &str) -> Option<T> {
+        self.with(|items| items.remove(name))
+    }
+}
+
+fn remove_room<T: Item>(
+    table: &Table<T>,
+    name: &str,
+    key: Option<String>,
+    room_key: fn(&T) -> &str,
+) -> bool {
+    table.with(|rooms| {
+        let Some(room) = rooms.get(name) else {
+            return key.is_none();
+        };
+        if key.is_some_and(|key| room_key(room) != key) {
+            return false;
+        }
+        rooms.remove(name);
+        true
+    })
+}
+
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Memory {
+    shared_rooms: Table<SharedRoom>,
+    shared_room_opponent_answers: Table<SharedRoomOpponentAnswer>,
+    reserved_rooms: Table<ReservedRoom>,
+    reserved_room_opponent_answers: Table<ReservedRoomOpponentAnswer>,
+    reserved_room_spectator_answers: Table<ReservedRoomSpectatorAnswer>,
+    /// 再起動を跨いで残す必要はない
+    #[serde(skip)]
+    request_counters: Table<RequestCounter>,
+}
+
+#[async_trait]
+impl SharedRoomTables for Memory {
+    async fn put_room(&self, room: SharedRoom) -> Result<(), PutError> {
+        self.shared_rooms.put(room)
+    }
+
+    async fn find_room(&self, name: String) -> Result<Option<SharedRoom>> {
+        Ok(self.shared_rooms.find(&name))
+    }
+
+    async fn find_public_rooms(&self) -> Result<Vec<SharedRoom>> {
+        Ok(self.shared_rooms.find_all(|room| room.public))
+    }
+
+    async fn find_quick_match_rooms(&self) -> Result<Vec<SharedRoom>> {
+        Ok(self
+            .shared_rooms
+            .find_all(|room| room.quick_match.is_some()))
+    }
+
+    async fn claim_quick_match_room(&self, name: String) -> Result<bool> {
+        Ok(self.shared_rooms.with(|rooms| {
+            rooms
+                .get_mut(&name)
+                .and_then(|room| room.quick_match.take())
+                .is_some()
+        }))
+    }
+
+    async fn find_rooms_by_owner(&self, owner: String) -> Result<Vec<SharedRoom>> {
+        Ok(self
+            .shared_rooms
+            .find_all(|room| room.owner.as_ref() == Some(&owner)))
+    }
+
+    async fn keep_room(&self, name: String, key: String, ttl_sec: u64) -> Result<bool> {
+        Ok(self.shared_rooms.with(|rooms| {
+            let Some(room) = rooms.get_mut(&name) else {
+                return false;
+            };
+            if room.key != key {
+                return false;
+            }
+            room.ttl_sec = ttl_sec;
+            true
+        }))
+    }
+
+    async fn remove_room(&self, name: String, key: Option<String>) -> Result<bool> {
+        Ok(remove_room(&self.shared_rooms, &name, key, |room| {
+            &room.key
+        }))
+    }
+
+    async fn put_room_opponent_answer(
+        &self,
+        answer: SharedRoomOpponentAnswer,
+    ) -> Result<(), PutError> {
+        self.shared_room_opponent_answers.put(answer)
+    }
+
+    async fn remove_room_opponent_answer(
+        &self,
+        name: String,
+    ) -> Result<Option<SharedRoomOpponentAnswer>> {
+        Ok(self.shared_room_opponent_answers.remove(&name))
+    }
+}
+
+#[async_trait]
+impl ReservedRoomTables for Memory {
+    async fn put_room(&self, room: ReservedRoom) -> Result<(), PutError> {
+        self.reserved_rooms.put(room)
+    }
+
+    async fn find_room(&self, name: String) -> Result<Option<ReservedRoom>> {
+        Ok(self.reserved_rooms.find(&name))
+    }
+
+    async fn find_public_rooms(&self) -> Result<Vec<ReservedRoom>> {
+        Ok(self.reserved_rooms.find_all(|room| room.public))
+    }
+
+    async fn find_rooms_by_owner(&self, owner: String) -> Result<Vec<ReservedRoom>> {
+        Ok(self
+            .reserved_rooms
+            .find_all(|room| room.owner.as_ref() == Some(&owner)))
+    }
+
+    async fn keep_room(
+        &self,
+        name: String,
+        key: String,
+        spectator_id: Option<String>,
+        spectator_offer_sdp: Option<CompressedSdp>,
+        ttl_sec: u64,
+    ) -> Result<Option<ReservedRoom>> {
+        Ok(self.reserved_rooms.with(|rooms| {
+            let room = rooms.get_mut(&name)?;
+            if room.key != key {
+                return None;
+            }
+            room.ttl_sec = ttl_sec;
+            if let Some(spectator_offer_sdp) = spectator_offer_sdp {
+                if let Some(spectator_id) = spectator_id {
+                    room.spectator_offer_sdps
+                        .insert(spectator_id, spectator_offer_sdp);
+                } else {
+                    room.spectator_offer_sdp = Some(spectator_offer_sdp);
+                }
+            }
+            Some(room.clone())
+        }))
+    }
+
+    async fn remove_opponent_offer_sdp_in_room(&self, name: String) -> Result<bool> {
+        Ok(self.reserved_rooms.with(|rooms| {
+            let Some(room) = rooms.get_mut(&name) else {
+                return false;
+            };
+            room.opponent_offer_sdp = None;
+            true
+        }))
+    }
+
+    async fn remove_spectator_offer_sdp_in_room(
+        &self,
+        name: String,
+        spectator_id: Option<String>,
+    ) -> Result<bool> {
+        Ok(self.reserved_rooms.with(|rooms| {
+            let Some(room) = rooms.get_mut(&name) else {
+                return false;
+            };
+            if let Some(spectator_id) = spectator_id {
+                room.spectator_offer_sdps.remove(&spectator_id);
+            } else {
+                room.spectator_offer_sdp = None;
+            }
+            true
+        }))
+    }
+
+    async fn remove_room(&self, name: String, key: Option<String>) -> Result<bool> {
+        Ok(remove_room(&self.reserved_rooms, &name, key, |room| {
+            &room.key
+        }))
+    }
+
+    async fn put_room_opponent_answer(
+        &self,
+        answer: ReservedRoomOpponentAnswer,
+    ) -> Result<(), PutError> {
+        self.reserved_room_opponent_answers.put(answer)
+    }
+
+    async fn remove_room_opponent_answer(
+        &self,
+        name: String,
+    ) -> Result<Option<ReservedRoomOpponentAnswer>> {
+        Ok(self.reserved_room_opponent_answers.remove(&name))
+    }
+
+    async fn put_room_spectator_answer(
+        &self,
+        answer: ReservedRoomSpectatorAnswer,
+    ) -> Result<(), PutError> {
+        self.reserved_room_spectator_answers.put(answer)
+    }
+
+    async fn remove_room_spectator_answer(
+        &self,
+        name: String,
+    ) -> Result<Option<ReservedRoomSpectatorAnswer>> {
+        Ok(self.reserved_room_spectator_answers.remove(&name))
+    }
+}
+
+#[async_trait]
+impl RateLimitTables for Memory {
+    async fn increment_request_count(&self, name: String, ttl_sec: u64) -> Result<u32> {
+        Ok(self.request_counters.with(|counters| {
+            let counter = counters
+                .entry(name.clone())
+                .or_insert_with(|| RequestCounter::new(name, 0, ttl_sec));
+            counter.count += 1;
+            counter.count
+        }))
+    }
+}
+
+impl Database for Memory {}
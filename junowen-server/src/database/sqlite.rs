@@ -0,0 +1,431 @@
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use junowen_lib::connection::signaling::CompressedSdp;
+use rusqlite::{params, Connection, OptionalExtension, Transaction, TransactionBehavior};
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::task::spawn_blocking;
+
+use super::{
+    now_sec, Database, Item, PutError, RateLimitTables, RequestCounter, ReservedRoom,
+    ReservedRoomOpponentAnswer, ReservedRoomSpectatorAnswer, ReservedRoomTables, SharedRoom,
+    SharedRoomOpponentAnswer, SharedRoomTables,
+};
+
+const TABLE_SHARED_ROOM: &str = "shared_room";
+const TABLE_SHARED_ROOM_OPPONENT_ANSWER: &str = "shared_room_opponent_answer";
+const TABLE_RESERVED_ROOM: &str = "reserved_room";
+const TABLE_RESERVED_ROOM_OPPONENT_ANSWER: &str = "reserved_room_opponent_answer";
+const TABLE_RESERVED_ROOM_SPECTATOR_ANSWER: &str = "reserved_room_spectator_answer";
+const TABLE_REQUEST_COUNTER: &str = "request_counter";
+
+const TABLES: [&str; 6] = [
+    TABLE_SHARED_ROOM,
+    TABLE_SHARED_ROOM_OPPONENT_ANSWER,
+    TABLE_RESERVED_ROOM,
+    TABLE_RESERVED_ROOM_OPPONENT_ANSWER,
+    TABLE_RESERVED_ROOM_SPECTATOR_ANSWER,
+    TABLE_REQUEST_COUNTER,
+];
+
+/// 失効したアイテムを削除した上で挿入する。同名のアイテムが残っていれば Conflict
+fn put_item(tx: &Transaction, table: &str, item: &impl ItemRow) -> Result<(), PutError> {
+    let inner = || -> Result<bool> {
+        tx.execute(
+            &format!("DELETE FROM {} WHERE ttl_sec < ?1", table),
+            params![now_sec()],
+        )?;
+        let changes = tx.execute(
+            &format!(
+                "INSERT OR IGNORE INTO {} (name, ttl_sec, item) VALUES (?1, ?2, ?3)",
+                table
+            ),
+            params![item.name(), item.ttl_sec(), serde_json::to_string(item)?],
+        )?;
+        Ok(changes > 0)
+    };
+    match inner() {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(PutError::Conflict),
+        Err(err) => Err(PutError::Unknown(err)),
+    }
+}
+
+fn find_item<T: ItemRow>(tx: &Transaction, table: &str, name: &str) -> Result<Option<T>> {
+    let json: Option<String> = tx
+        .query_row(
+            &format!(
+                "SELECT item FROM {} WHERE name = ?1 AND ttl_sec >= ?2",
+                table
+            ),
+            params![name, now_sec()],
+            |row| row.get(0),
+        )
+        .optional()?;
+    json.map(|json| Ok(serde_json::from_str(&json)?))
+        .transpose()
+}
+
+fn find_items<T: ItemRow>(tx: &Transaction, table: &str) -> Result<Vec<T>> {
+    let mut stmt = tx.prepare(&format!("SELECT item FROM {} WHERE ttl_sec >= ?1", table))?;
+    let rows = stmt.query_map(params![now_sec()], |row| row.get::<_, String>(0))?;
+    rows.map(|json| Ok(serde_json::from_str(&json?)?)).collect()
+}
+
+fn update_item(tx: &Transaction, table: &str, item: &impl ItemRow) -> Result<()> {
+    tx.execute(
+        &format!(
+            "UPDATE {} SET ttl_sec = ?2, item = ?3 WHERE name = ?1",
+            table
+        ),
+        params![item.name(), item.ttl_sec(), serde_json::to_string(item)?],
+    )?;
+    Ok(())
+}
+
+fn remove_item<T: ItemRow>(tx: &Transaction, table: &str, name: &str) -> Result<Option<T>> {
+    let json: Option<String> = tx
+        .query_row(
+            &format!("DELETE FROM {} WHERE name = ?1 RETURNING item", table),
+            params![name],
+            |row| row.get(0),
+        )
+        .optional()?;
+    let Some(json) = json else {
+        return Ok(None);
+    };
+    let item: T = serde_json::from_str(&json)?;
+    Ok((!item.is_expired(now_sec())).then_some(item))
+}
+
+trait ItemRow: Item + DeserializeOwned + Serialize + Send + 'static {}
+
+impl<T: Item + DeserializeOwned + Serialize + Send + 'static> ItemRow for T {}
+
+/// 1 テーブル 1 アイテム種別で、アイテムを JSON として保存する。
+/// rusqlite はブロックするので、ランタイムのワーカーを止めないように別スレッドで呼ぶ
+pub struct Sqlite {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl Sqlite {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let conn = Connection::open(path)?;
+        for table in TABLES {
+            conn.execute(
+                &format!(
+                    "CREATE TABLE IF NOT EXISTS {} (
+                        name TEXT PRIMARY KEY NOT NULL,
+                        ttl_sec INTEGER NOT NULL,
+                        item TEXT NOT NULL
+                    )",
+                    table
+                ),
+                [],
+            )?;
+        }
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    async fn transaction<T: Send + 'static>(
+        &self,
+        func: impl FnOnce(&Transaction) -> Result<T> + Send + 'static,
+    ) -> Result<T> {
+        let conn = self.conn.clone();
+        spawn_blocking(move || {
+            let mut conn = conn.lock().unwrap();
+            let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+            let ret = func(&tx)?;
+            tx.commit()?;
+            Ok(ret)
+        })
+        .await?
+    }
+
+    async fn transaction_put(
+        &self,
+        func: impl FnOnce(&Transaction) -> Result<(), PutError> + Send + 'static,
+    ) -> Result<(), PutError> {
+        let conn = self.conn.clone();
+        spawn_blocking(move || {
+            let mut conn = conn.lock().unwrap();
+            let tx = conn
+                .transaction_with_behavior(TransactionBehavior::Immediate)
+                .map_err(|err| PutError::Unknown(err.into()))?;
+            func(&tx)?;
+            tx.commit().map_err(|err| PutError::Unknown(err.into()))
+        })
+        .await
+        .map_err(|err| PutError::Unknown(err.into()))?
+    }
+
+    async fn remove_room<T: ItemRow>(
+        &self,
+        table: &'static str,
+        name: String,
+        key: Option<String>,
+        room_key: fn(&T) -> &str,
+    ) -> Result<bool> {
+        self.transaction(move |tx| {
+            let Some(room) = find_item::<T>(tx, table, &name)? else {
+                return Ok(key.is_none());
+            };
+            if key.is_some_and(|key| room_key(&room) != key) {
+                return Ok(false);
+            }
+            remove_item::<T>(tx, table, &name)?;
+            Ok(true)
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl SharedRoomTables for Sqlite {
+    async fn put_room(&self, room: SharedRoom) -> Result<(), PutError> {
+        self.transaction_put(move |tx| put_item(tx, TABLE_SHARED_ROOM, &room))
+            .await
+    }
+
+    async fn find_room(&self, name: String) -> Result<Option<SharedRoom>> {
+        self.transaction(move |tx| find_item(tx, TABLE_SHARED_ROOM, &name))
+            .await
+    }
+
+    async fn find_public_rooms(&self) -> Result<Vec<SharedRoom>> {
+        let rooms: Vec<SharedRoom> = self
+            .transaction(move |tx| find_items(tx, TABLE_SHARED_ROOM))
+            .await?;
+        Ok(rooms.into_iter().filter(|room| room.public).collect())
+    }
+
+    async fn find_quick_match_rooms(&self) -> Result<Vec<SharedRoom>> {
+        let rooms: Vec<SharedRoom> = self
+            .transaction(move |tx| find_items(tx, TABLE_SHARED_ROOM))
+            .await?;
+        Ok(rooms
+            .into_iter()
+            .filter(|room| room.quick_match.is_some())
+            .collect())
+    }
+
+    async fn claim_quick_match_room(&self, name: String) -> Result<bool> {
+        self.transaction(move |tx| {
+            let Some(mut room) = find_item::<SharedRoom>(tx, TABLE_SHARED_ROOM, &name)? else {
+                return Ok(false);
+            };
+            if room.quick_match.take().is_none() {
+                return Ok(false);
+            }
+            update_item(tx, TABLE_SHARED_ROOM, &room)?;
+            Ok(true)
+        })
+        .await
+    }
+
+    async fn find_rooms_by_owner(&self, owner: String) -> Result<Vec<SharedRoom>> {
+        let rooms: Vec<SharedRoom> = self
+            .transaction(move |tx| find_items(tx, TABLE_SHARED_ROOM))
+            .await?;
+        Ok(rooms
+            .into_iter()
+            .filter(|room| room.owner.as_ref() == Some(&owner))
+            .collect())
+    }
+
+    async fn keep_room(&self, name: String, key: String, ttl_sec: u64) -> Result<bool> {
+        self.transaction(move |tx| {
+            let Some(mut room) = find_item::<SharedRoom>(tx, TABLE_SHARED_ROOM, &name)? else {
+                return Ok(false);
+            };
+            if room.key != key {
+                return Ok(false);
+            }
+            room.ttl_sec = ttl_sec;
+            update_item(tx, TABLE_SHARED_ROOM, &room)?;
+            Ok(true)
+        })
+        .await
+    }
+
+    async fn remove_room(&self, name: String, key: Option<String>) -> Result<bool> {
+        self.remove_room::<SharedRoom>(TABLE_SHARED_ROOM, name, key, |room| &room.key)
+            .await
+    }
+
+    async fn put_room_opponent_answer(
+        &self,
+        answer: SharedRoomOpponentAnswer,
+    ) -> Result<(), PutError> {
+        self.transaction_put(move |tx| put_item(tx, TABLE_SHARED_ROOM_OPPONENT_ANSWER, &answer))
+            .await
+    }
+
+    async fn remove_room_opponent_answer(
+        &self,
+        name: String,
+    ) -> Result<Option<SharedRoomOpponentAnswer>> {
+        self.transaction(move |tx| remove_item(tx, TABLE_SHARED_ROOM_OPPONENT_ANSWER, &name))
+            .await
+    }
+}
+
+#[async_trait]
+impl ReservedRoomTables for Sqlite {
+    async fn put_room(&self, room: ReservedRoom) -> Result<(), PutError> {
+        self.transaction_put(move |tx| put_item(tx, TABLE_RESERVED_ROOM, &room))
+            .await
+    }
+
+    async fn find_room(&self, name: String) -> Result<Option<ReservedRoom>> {
+        self.transaction(move |tx| find_item(tx, TABLE_RESERVED_ROOM, &name))
+            .await
+    }
+
+    async fn find_public_rooms(&self) -> Result<Vec<ReservedRoom>> {
+        let rooms: Vec<ReservedRoom> = self
+            .transaction(move |tx| find_items(tx, TABLE_RESERVED_ROOM))
+            .await?;
+        Ok(rooms.into_iter().filter(|room| room.public).collect())
+    }
+
+    async fn find_rooms_by_owner(&self, owner: String) -> Result<Vec<ReservedRoom>> {
+        let rooms: Vec<ReservedRoom> = self
+            .transaction(move |tx| find_items(tx, TABLE_RESERVED_ROOM))
+            .await?;
+        Ok(rooms
+            .into_iter()
+            .filter(|room| room.owner.as_ref() == Some(&owner))
+            .collect())
+    }
+
+    async fn keep_room(
+        &self,
+        name: String,
+        key: String,
+        spectator_id: Option<String>,
+        spectator_offer_sdp: Option<CompressedSdp>,
+        ttl_sec: u64,
+    ) -> Result<Option<ReservedRoom>> {
+        self.transaction(move |tx| {
+            let Some(mut room) = find_item::<ReservedRoom>(tx, TABLE_RESERVED_ROOM, &name)? else {
+                return Ok(None);
+            };
+            if room.key != key {
+                return Ok(None);
+            }
+            room.ttl_sec = ttl_sec;
+            if let Some(spectator_offer_sdp) = spectator_offer_sdp {
+                if let Some(spectator_id) = spectator_id {
+                    room.spectator_offer_sdps
+                        .insert(spectator_id, spectator_offer_sdp);
+                } else {
+                    room.spectator_offer_sdp = Some(spectator_offer_sdp);
+                }
+            }
+            update_item(tx, TABLE_RESERVED_ROOM, &room)?;
+            Ok(Some(room))
+        })
+        .await
+    }
+
+    async fn remove_opponent_offer_sdp_in_room(&self, name: String) -> Result<bool> {
+        self.transaction(move |tx| {
+            let Some(mut room) = find_item::<ReservedRoom>(tx, TABLE_RESERVED_ROOM, &name)? else {
+                return Ok(false);
+            };
+            room.opponent_offer_sdp = None;
+            update_item(tx, TABLE_RESERVED_ROOM, &room)?;
+            Ok(true)
+        })
+        .await
+    }
+
+    async fn remove_spectator_offer_sdp_in_room(
+        &self,
+        name: String,
+        spectator_id: Option<String>,
+    ) -> Result<bool> {
+        self.transaction(move |tx| {
+            let Some(mut room) = find_item::<ReservedRoom>(tx, TABLE_RESERVED_ROOM, &name)? else {
+                return Ok(false);
+            };
+            if let Some(spectator_id) = spectator_id {
+                room.spectator_offer_sdps.remove(&spectator_id);
+            } else {
+                room.spectator_offer_sdp = None;
+            }
+            update_item(tx, TABLE_RESERVED_ROOM, &room)?;
+            Ok(true)
+        })
+        .await
+    }
+
+    async fn remove_room(&self, name: String, key: Option<String>) -> Result<bool> {
+        self.remove_room::<ReservedRoom>(TABLE_RESERVED_ROOM, name, key, |room| &room.key)
+            .await
+    }
+
+    async fn put_room_opponent_answer(
+        &self,
+        answer: ReservedRoomOpponentAnswer,
+    ) -> Result<(), PutError> {
+        self.transaction_put(move |tx| put_item(tx, TABLE_RESERVED_ROOM_OPPONENT_ANSWER, &answer))
+            .await
+    }
+
+    async fn remove_room_opponent_answer(
+        &self,
+        name: String,
+    ) -> Result<Option<ReservedRoomOpponentAnswer>> {
+        self.transaction(move |tx| remove_item(tx, TABLE_RESERVED_ROOM_OPPONENT_ANSWER, &name))
+            .await
+    }
+
+    async fn put_room_spectator_answer(
+        &self,
+        answer: ReservedRoomSpectatorAnswer,
+    ) -> Result<(), PutError> {
+        self.transaction_put(move |tx| put_item(tx, TABLE_RESERVED_ROOM_SPECTATOR_ANSWER, &answer))
+            .await
+    }
+
+    async fn remove_room_spectator_answer(
+        &self,
+        name: String,
+    ) -> Result<Option<ReservedRoomSpectatorAnswer>> {
+        self.transaction(move |tx| remove_item(tx, TABLE_RESERVED_ROOM_SPECTATOR_ANSWER, &name))
+            .await
+    }
+}
+
+#[async_trait]
+impl RateLimitTables for Sqlite {
+    async fn increment_request_count(&self, name: String, ttl_sec: u64) -> Result<u32> {
+        self.transaction(move |tx| {
+            if let Some(mut counter) =
+                find_item::<RequestCounter>(tx, TABLE_REQUEST_COUNTER, &name)?
+            {
+                counter.count += 1;
+                update_item(tx, TABLE_REQUEST_COUNTER, &counter)?;
+                return Ok(counter.count);
+            }
+            // IMMEDIATE トランザクションで書き込みを直列化しているので、見つからなければ競合しない
+            let counter = RequestCounter::new(name, 1, ttl_sec);
+            put_item(tx, TABLE_REQUEST_COUNTER, &counter).map_err(|err| match err {
+                PutError::Conflict => anyhow!("request counter conflicted: {}", counter.name),
+                PutError::Unknown(err) => err,
+            })?;
+            Ok(1)
+        })
+        .await
+    }
+}
+
+impl Database for Sqlite {}
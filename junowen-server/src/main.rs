@@ -14,7 +14,7 @@ mod local {
     use crate::{database, routes::routes, tracing_helper};
 
     async fn func(req: Request) -> Result<impl IntoResponse, anyhow::Error> {
-        let db = database::File;
+        let db = database::File::load("store.json").await?;
         routes(&req, &db).await
     }
 
@@ -60,14 +60,161 @@ mod lambda {
     }
 }
 
+mod serve {
+    use std::{
+        convert::Infallible,
+        env::{self, args},
+        net::{IpAddr, SocketAddr},
+        sync::Arc,
+    };
+
+    use anyhow::bail;
+    use bytes::Bytes;
+    use http_body_util::{BodyExt, Full};
+    use hyper::{body::Incoming, server::conn::http1, service::service_fn};
+    use hyper_util::rt::TokioIo;
+    use lambda_http::{
+        http::{HeaderValue, StatusCode},
+        Body, IntoResponse, Request, Response,
+    };
+    use tokio::net::TcpListener;
+    use tracing::{debug, error, info};
+
+    use crate::{
+        database::{self, Database},
+        routes::routes,
+        tracing_helper,
+    };
+
+    const DEFAULT_ADDR: &str = "0.0.0.0:8080";
+    /// memory | file:{path} | sqlite:{path} | dynamodb
+    const DEFAULT_DATABASE: &str = "file:store.json";
+
+    /// TRUSTED_PROXIES: x-forwarded-for を信用するプロキシの IP アドレス (カンマ区切り)
+    fn trusted_proxies() -> Vec<IpAddr> {
+        env::var("TRUSTED_PROXIES")
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|x| x.trim().parse().ok())
+            .collect()
+    }
+
+    fn from_body(body: Bytes) -> Body {
+        if body.is_empty() {
+            return Body::Empty;
+        }
+        match String::from_utf8(body.to_vec()) {
+            Ok(text) => Body::Text(text),
+            Err(err) => Body::Binary(err.into_bytes()),
+        }
+    }
+
+    fn into_bytes(body: Body) -> Bytes {
+        match body {
+            Body::Empty => Bytes::new(),
+            Body::Text(text) => text.into(),
+            Body::Binary(binary) => binary.into(),
+        }
+    }
+
+    async fn func(
+        req: hyper::Request<Incoming>,
+        remote_addr: SocketAddr,
+        trusted_proxies: &[IpAddr],
+        db: &impl Database,
+    ) -> Response<Body> {
+        let (mut parts, body) = req.into_parts();
+        let body = match body.collect().await {
+            Ok(body) => body.to_bytes(),
+            Err(err) => {
+                debug!("{:?}", err);
+                return Response::builder()
+                    .status(StatusCode::BAD_REQUEST)
+                    .body(Body::Empty)
+                    .unwrap();
+            }
+        };
+        // 信用するプロキシを経由していなければ、クライアントが送ってきた値は捨てる
+        if !trusted_proxies.contains(&remote_addr.ip())
+            || !parts.headers.contains_key("x-forwarded-for")
+        {
+            let ip = HeaderValue::from_str(&remote_addr.ip().to_string()).unwrap();
+            parts.headers.insert("x-forwarded-for", ip);
+        }
+        let req = Request::from_parts(parts, from_body(body));
+        match routes(&req, db).await {
+            Ok(res) => res.into_response().await,
+            Err(err) => {
+                error!("Fatal error: {:?}", err);
+                Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .body(Body::Empty)
+                    .unwrap()
+            }
+        }
+    }
+
+    async fn serve(listener: TcpListener, db: impl Database) -> anyhow::Result<()> {
+        let db = Arc::new(db);
+        let trusted_proxies: Arc<[IpAddr]> = trusted_proxies().into();
+        loop {
+            let (stream, remote_addr) = listener.accept().await?;
+            let db = db.clone();
+            let trusted_proxies = trusted_proxies.clone();
+            tokio::spawn(async move {
+                let service = service_fn(|req| {
+                    let db = db.clone();
+                    let trusted_proxies = trusted_proxies.clone();
+                    async move {
+                        let res = func(req, remote_addr, &trusted_proxies, db.as_ref()).await;
+                        Ok::<_, Infallible>(res.map(|body| Full::new(into_bytes(body))))
+                    }
+                });
+                let conn = http1::Builder::new().serve_connection(TokioIo::new(stream), service);
+                if let Err(err) = conn.await {
+                    debug!("{:?}", err);
+                }
+            });
+        }
+    }
+
+    pub async fn main() -> anyhow::Result<()> {
+        tracing_helper::init_daemon_tracing();
+
+        let addr = args().nth(2).unwrap_or_else(|| DEFAULT_ADDR.to_owned());
+        let database = env::var("DATABASE").unwrap_or_else(|_| DEFAULT_DATABASE.to_owned());
+        let listener = TcpListener::bind(&addr).await?;
+        info!("Listening on {} with {}", addr, database);
+        match database.split_once(':') {
+            None if database == "memory" => serve(listener, database::Memory::default()).await,
+            None if database == "dynamodb" => {
+                serve(listener, database::DynamoDB::new().await).await
+            }
+            Some(("file", path)) => serve(listener, database::File::load(path).await?).await,
+            Some(("sqlite", path)) => serve(listener, database::Sqlite::open(path)?).await,
+            _ => bail!("unknown database: {}", database),
+        }
+    }
+}
+
+fn is_serve_mode() -> bool {
+    std::env::args().nth(1).as_deref() == Some("serve")
+}
+
 #[cfg(not(target_os = "linux"))]
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    if is_serve_mode() {
+        return serve::main().await;
+    }
     local::main().await
 }
 
 #[cfg(target_os = "linux")]
 #[tokio::main]
 async fn main() -> Result<(), lambda_http::Error> {
+    if is_serve_mode() {
+        return Ok(serve::main().await?);
+    }
     lambda::main().await
 }
@@ -1,11 +1,11 @@
 mod custom;
+mod public_rooms;
+mod quick_match;
+mod rate_limit;
 mod reserved_room;
 mod room_utils;
-
-use std::{
-    collections::hash_map::DefaultHasher,
-    hash::{Hash, Hasher},
-};
+#[cfg(test)]
+mod tests;
 
 use anyhow::{bail, Result};
 use base_custom::BaseCustom;
@@ -15,9 +15,13 @@ use lambda_http::{
 };
 use once_cell::sync::Lazy;
 use serde::Deserialize;
+use sha2::{Digest, Sha256};
 use tracing::{info_span, trace, Instrument};
 
-use crate::{database::Database, routes::room_utils::RETRY_AFTER_INTERVAL_SEC};
+use crate::{
+    database::Database,
+    routes::room_utils::{now_sec, RETRY_AFTER_INTERVAL_SEC},
+};
 
 static BASE_YOTEICHI_MOD: Lazy<BaseCustom<char>> = Lazy::new(|| {
     const CHARS: &str = concat!(
@@ -66,20 +70,29 @@ fn to_response(status_code: StatusCode, body: impl Into<Body>) -> Response<Body>
         .unwrap()
 }
 
-fn ip_hash(req: &Request) -> u64 {
-    let ip = req
-        .headers()
+/// x-forwarded-for の先頭側はクライアントが自由に書けるので、
+/// 直前のプロキシが追記した最後の値だけを信用する
+fn client_ip(req: &Request) -> &str {
+    req.headers()
         .get("x-forwarded-for")
         .and_then(|x| x.to_str().ok())
-        .unwrap_or_default();
-    let mut s = DefaultHasher::new();
-    ip.hash(&mut s);
-    s.finish()
+        .and_then(|x| x.rsplit(',').next())
+        .unwrap_or_default()
+        .trim()
+}
+
+/// 所有者として保存されるので、Rust のバージョンで変わらないハッシュを使う
+fn ip_hash(req: &Request) -> u64 {
+    let digest = Sha256::digest(client_ip(req).as_bytes());
+    u64::from_be_bytes(digest[..8].try_into().unwrap())
 }
 
 pub async fn routes(req: &Request, db: &impl Database) -> Result<impl IntoResponse> {
     trace!("{:?}", req);
 
+    if let Some(res) = rate_limit::check_requests(db, &rate_limit::owner(req), now_sec()).await? {
+        return Ok(res);
+    }
     if let Some(relative_uri) = req.uri().path().strip_prefix("/custom/") {
         return custom::route(relative_uri, req, db)
             .instrument(info_span!("req", ip_hash = base_yoteichi_mod(ip_hash(req))))
@@ -90,5 +103,15 @@ pub async fn routes(req: &Request, db: &impl Database) -> Result<impl IntoRespon
             .instrument(info_span!("req", ip_hash = base_yoteichi_mod(ip_hash(req))))
             .await;
     }
+    if req.uri().path() == "/public-rooms" {
+        return public_rooms::route(req, db)
+            .instrument(info_span!("req", ip_hash = base_yoteichi_mod(ip_hash(req))))
+            .await;
+    }
+    if req.uri().path() == "/quick-match" {
+        return quick_match::route(req, db)
+            .instrument(info_span!("req", ip_hash = base_yoteichi_mod(ip_hash(req))))
+            .await;
+    }
     Ok(to_response(StatusCode::NOT_FOUND, Body::Empty))
 }
@@ -9,6 +9,7 @@ use junowen_lib::signaling_server::{
         PostRoomKeepResponse, PutRoomRequestBody, PutRoomResponseAnswerBody,
         PutRoomResponseWaitingBody,
     },
+    version::ClientVersion,
 };
 use lambda_http::{
     http::{Method, StatusCode},
@@ -19,11 +20,12 @@ use tracing::{debug, info};
 use uuid::Uuid;
 
 use crate::{
-    database::{PutError, SharedRoom, SharedRoomOpponentAnswer, SharedRoomTables},
-    routes::room_utils::{now_sec, ttl_sec, RETRY_AFTER_INTERVAL_SEC},
+    database::{Database, PutError, SharedRoom, SharedRoomOpponentAnswer, SharedRoomTables},
+    routes::room_utils::{check_compatibility, now_sec, ttl_sec, RETRY_AFTER_INTERVAL_SEC},
 };
 
 use super::{
+    rate_limit::{check_rooms, owner},
     room_utils::{decode_room_name, from_post_room_keep_response, from_put_room_response},
     to_response, try_parse,
 };
@@ -56,25 +58,42 @@ async fn find_guest(
 }
 
 async fn put_room(
-    db: &impl SharedRoomTables,
+    db: &impl Database,
     name: &str,
     body: PutRoomRequestBody,
-) -> Result<PutSharedRoomResponse> {
+    client_version: Option<ClientVersion>,
+    owner: String,
+) -> Result<Result<PutSharedRoomResponse, Response<Body>>> {
     let now_sec = now_sec();
     let key = Uuid::new_v4().to_string();
-    let room = SharedRoom::new(
+    let mut room = SharedRoom::new(
         name.to_owned(),
         key.clone(),
         body.offer().clone(),
+        client_version.clone(),
+        body.public(),
+        None,
         ttl_sec(now_sec),
     );
+    room.set_owner(Some(owner.clone()));
     for retry in 0.. {
         if let Some(room) = find_valid_room(db, now_sec, name.to_owned()).await? {
+            let incompatible =
+                check_compatibility(room.client_version().as_ref(), client_version.as_ref());
+            if let Some(body) = incompatible {
+                info!("[Shared Room] Incompatible: {}", name);
+                let response = PutSharedRoomResponse::incompatible(RETRY_AFTER_INTERVAL_SEC, body);
+                return Ok(Ok(response));
+            }
             let body = PutSharedRoomResponseConflictBody::new(room.into_sdp());
             let response = PutSharedRoomResponse::conflict(RETRY_AFTER_INTERVAL_SEC, body);
-            return Ok(response);
+            return Ok(Ok(response));
+        }
+        // 既存のルームに入るだけならルームは増えないので、作るときだけ数える
+        if let Some(res) = check_rooms(db, &owner, now_sec).await? {
+            return Ok(Err(res));
         }
-        match db.put_room(room.clone()).await {
+        match SharedRoomTables::put_room(db, room.clone()).await {
             Ok(()) => break,
             Err(PutError::Conflict) => {
                 if retry >= 2 {
@@ -86,7 +105,7 @@ async fn put_room(
         }
     }
     info!("[Shared Room] Created: {}", name);
-    Ok(
+    Ok(Ok(
         if let Some(answer) = find_guest(db, name.to_owned()).await? {
             let body = PutRoomResponseAnswerBody::new(answer.into_sdp());
             PutSharedRoomResponse::created_with_answer(RETRY_AFTER_INTERVAL_SEC, body)
@@ -94,7 +113,7 @@ async fn put_room(
             let body = PutRoomResponseWaitingBody::new(key);
             PutSharedRoomResponse::created_with_key(RETRY_AFTER_INTERVAL_SEC, body)
         },
-    )
+    ))
 }
 
 async fn post_room_keep(
@@ -159,7 +178,7 @@ async fn post_room_join(
 pub async fn route(
     relative_uri: &str,
     req: &Request,
-    db: &impl SharedRoomTables,
+    db: &impl Database,
 ) -> Result<Response<Body>> {
     let regex = Regex::new(r"^([^/]+)$").unwrap();
     if let Some(c) = regex.captures(relative_uri) {
@@ -177,8 +196,11 @@ pub async fn route(
                     to_response(StatusCode::BAD_REQUEST, Body::Empty)
                 }
                 Ok(body) => {
-                    let res = put_room(db, &room_name, body).await?;
-                    from_put_room_response(res)
+                    let client_version = ClientVersion::from_headers(req.headers());
+                    match put_room(db, &room_name, body, client_version, owner(req)).await? {
+                        Ok(res) => from_put_room_response(res),
+                        Err(res) => res,
+                    }
                 }
             },
             Method::DELETE => match try_parse(req.body()) {
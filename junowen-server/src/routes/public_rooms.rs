@@ -0,0 +1,72 @@
+use anyhow::Result;
+use junowen_lib::signaling_server::public_room::{
+    GetPublicRoomsResponse, GetPublicRoomsResponseOkBody, PublicRoom, PublicRoomKind,
+};
+use lambda_http::{
+    http::{Method, StatusCode},
+    Body, Request, Response,
+};
+
+use crate::{
+    database::{Database, ReservedRoomTables, SharedRoomTables},
+    routes::room_utils::now_sec,
+};
+
+use super::to_response;
+
+async fn get_public_rooms(db: &impl Database) -> Result<GetPublicRoomsResponse> {
+    let now_sec = now_sec();
+    let shared_rooms = SharedRoomTables::find_public_rooms(db).await?;
+    let reserved_rooms = ReservedRoomTables::find_public_rooms(db).await?;
+    let shared_rooms = shared_rooms
+        .into_iter()
+        .filter(|room| !room.is_expired(now_sec))
+        .map(|room| {
+            PublicRoom::new(
+                PublicRoomKind::Shared,
+                room.name().clone(),
+                now_sec.saturating_sub(room.created_at_sec()),
+                true,
+                false,
+                false,
+                room.client_version().clone(),
+            )
+        });
+    let reserved_rooms = reserved_rooms
+        .into_iter()
+        .filter(|room| !room.is_expired(now_sec))
+        .map(|room| {
+            let opponent_slot_free = room.opponent_offer_sdp().is_some();
+            // 対戦が始まり、観戦者を待っている
+            let spectator_slot_free = !opponent_slot_free && room.spectator_offer_sdp().is_some();
+            let password_required = if opponent_slot_free {
+                room.player_password_hash().is_some()
+            } else {
+                room.spectator_password_hash().is_some()
+            };
+            PublicRoom::new(
+                PublicRoomKind::Reserved,
+                room.name().clone(),
+                now_sec.saturating_sub(room.created_at_sec()),
+                opponent_slot_free,
+                spectator_slot_free,
+                password_required,
+                room.client_version().clone(),
+            )
+        })
+        .filter(|room| room.opponent_slot_free() || room.spectator_slot_free());
+    let mut rooms: Vec<_> = shared_rooms.chain(reserved_rooms).collect();
+    rooms.sort_by(|a, b| b.age_sec().cmp(&a.age_sec()).then(a.name().cmp(b.name())));
+    let body = GetPublicRoomsResponseOkBody::new(rooms);
+    Ok(GetPublicRoomsResponse::Ok(body))
+}
+
+pub async fn route(req: &Request, db: &impl Database) -> Result<Response<Body>> {
+    Ok(match *req.method() {
+        Method::GET => {
+            let res = get_public_rooms(db).await?;
+            to_response(res.status_code(), Body::Text(res.to_body()))
+        }
+        _ => to_response(StatusCode::METHOD_NOT_ALLOWED, Body::Empty),
+    })
+}
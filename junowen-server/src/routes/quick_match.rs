@@ -0,0 +1,111 @@
+use anyhow::{bail, Result};
+use junowen_lib::signaling_server::{
+    quick_match::{
+        PostQuickMatchRequestBody, PostQuickMatchResponse, PostQuickMatchResponseMatchedBody,
+        PostQuickMatchResponseWaitingBody,
+    },
+    version::ClientVersion,
+};
+use lambda_http::{
+    http::{Method, StatusCode},
+    Body, Request, Response,
+};
+use tracing::{debug, info};
+use uuid::Uuid;
+
+use crate::{
+    database::{Database, PutError, SharedRoom, SharedRoomTables},
+    routes::room_utils::{check_compatibility, now_sec, ttl_sec, RETRY_AFTER_INTERVAL_SEC},
+};
+
+use super::{
+    rate_limit::{check_rooms, owner},
+    to_response, try_parse,
+};
+
+async fn post_quick_match(
+    db: &impl Database,
+    body: PostQuickMatchRequestBody,
+    client_version: Option<ClientVersion>,
+    owner: String,
+) -> Result<Result<PostQuickMatchResponse, Response<Body>>> {
+    let now_sec = now_sec();
+    let (offer, filter) = body.into_inner();
+    let mut rooms: Vec<_> = db
+        .find_quick_match_rooms()
+        .await?
+        .into_iter()
+        .filter(|room| !room.is_expired(now_sec))
+        .filter(|room| {
+            room.quick_match()
+                .as_ref()
+                .map(|room_filter| room_filter.matches(&filter))
+                .unwrap_or(false)
+        })
+        .filter(|room| {
+            check_compatibility(room.client_version().as_ref(), client_version.as_ref()).is_none()
+        })
+        .collect();
+    rooms.sort_by(|a, b| {
+        a.created_at_sec()
+            .cmp(&b.created_at_sec())
+            .then(a.name().cmp(b.name()))
+    });
+    // 同時に来た他のリクエストと同じルームに入らないように、待ち行列から外せたルームに入る
+    for room in rooms {
+        if !db.claim_quick_match_room(room.name().clone()).await? {
+            continue;
+        }
+        info!("[Quick Match] Matched: {}", room.name());
+        let room_name = room.name().clone();
+        let body = PostQuickMatchResponseMatchedBody::new(room_name, room.into_sdp());
+        return Ok(Ok(PostQuickMatchResponse::Matched(body)));
+    }
+
+    // 相手がいなければ自分が待ち行列に入る
+    if let Some(res) = check_rooms(db, &owner, now_sec).await? {
+        return Ok(Err(res));
+    }
+    let name = format!("quick-match-{}", Uuid::new_v4());
+    let key = Uuid::new_v4().to_string();
+    let mut room = SharedRoom::new(
+        name.clone(),
+        key.clone(),
+        offer,
+        client_version,
+        false,
+        Some(filter),
+        ttl_sec(now_sec),
+    );
+    room.set_owner(Some(owner));
+    match SharedRoomTables::put_room(db, room).await {
+        Ok(()) => {}
+        Err(PutError::Conflict) => bail!("room name conflicted: {}", name),
+        Err(PutError::Unknown(err)) => bail!("{:?}", err),
+    }
+    info!("[Quick Match] Queued: {}", name);
+    let body = PostQuickMatchResponseWaitingBody::new(name, key);
+    Ok(Ok(PostQuickMatchResponse::Waiting {
+        retry_after: RETRY_AFTER_INTERVAL_SEC,
+        body,
+    }))
+}
+
+pub async fn route(req: &Request, db: &impl Database) -> Result<Response<Body>> {
+    Ok(match *req.method() {
+        Method::POST => match try_parse(req.body()) {
+            Err(err) => {
+                debug!("{:?}", err);
+                to_response(StatusCode::BAD_REQUEST, Body::Empty)
+            }
+            Ok(body) => {
+                let client_version = ClientVersion::from_headers(req.headers());
+                match post_quick_match(db, body, client_version, owner(req)).await? {
+                    Ok(res) => to_response(res.status_code(), Body::Text(res.to_body())),
+                    Err(res) => res,
+                }
+            }
+        },
+        _ => to_response(StatusCode::METHOD_NOT_ALLOWED, Body::Empty),
+    })
+}
@@ -0,0 +1,115 @@
+use std::env;
+
+use anyhow::Result;
+use getset::CopyGetters;
+use lambda_http::{
+    http::{header::RETRY_AFTER, StatusCode},
+    Body, Request, Response,
+};
+use once_cell::sync::Lazy;
+use tracing::info;
+
+use crate::database::{Database, RateLimitTables, ReservedRoomTables, SharedRoomTables};
+
+use super::{ip_hash, room_utils::RETRY_AFTER_INTERVAL_SEC};
+
+/// 環境変数で上書きできる
+/// * RATE_LIMIT_REQUESTS: 時間枠あたりのリクエスト数
+/// * RATE_LIMIT_WINDOW_SEC: 時間枠の長さ
+/// * RATE_LIMIT_ROOMS: 同時に持てるルーム数
+#[derive(CopyGetters)]
+pub struct RateLimit {
+    #[get_copy = "pub"]
+    requests: u32,
+    #[get_copy = "pub"]
+    window_sec: u64,
+    #[get_copy = "pub"]
+    rooms: usize,
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        // ルームの維持に 3 秒毎の keep が必要なので、数部屋分は余裕を持たせる
+        Self {
+            requests: 120,
+            window_sec: 60,
+            rooms: 4,
+        }
+    }
+}
+
+impl RateLimit {
+    fn from_env() -> Self {
+        fn var<T: std::str::FromStr>(key: &str, default: T) -> T {
+            env::var(key)
+                .ok()
+                .and_then(|x| x.parse().ok())
+                .unwrap_or(default)
+        }
+        let default = Self::default();
+        Self {
+            requests: var("RATE_LIMIT_REQUESTS", default.requests),
+            window_sec: var("RATE_LIMIT_WINDOW_SEC", default.window_sec).max(1),
+            rooms: var("RATE_LIMIT_ROOMS", default.rooms),
+        }
+    }
+}
+
+pub static RATE_LIMIT: Lazy<RateLimit> = Lazy::new(RateLimit::from_env);
+
+/// ルームの所有者として記録する値
+pub fn owner(req: &Request) -> String {
+    format!("{:016x}", ip_hash(req))
+}
+
+fn too_many_requests(retry_after: u64) -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::TOO_MANY_REQUESTS)
+        .header(RETRY_AFTER, retry_after)
+        .body(Body::Empty)
+        .unwrap()
+}
+
+/// 時間枠内のリクエスト数が上限を超えていれば 429 を返す
+pub async fn check_requests(
+    db: &impl RateLimitTables,
+    owner: &str,
+    now_sec: u64,
+) -> Result<Option<Response<Body>>> {
+    let window = now_sec / RATE_LIMIT.window_sec;
+    let window_end_sec = (window + 1) * RATE_LIMIT.window_sec;
+    let name = format!("{}:{}", owner, window);
+    let count = db.increment_request_count(name, window_end_sec).await?;
+    if count <= RATE_LIMIT.requests {
+        return Ok(None);
+    }
+    if count == RATE_LIMIT.requests + 1 {
+        info!("[Rate Limit] Too many requests: {}", owner);
+    }
+    Ok(Some(too_many_requests(window_end_sec - now_sec)))
+}
+
+/// 同じ IP アドレスから作られた有効なルームが上限に達していれば 429 を返す
+///
+/// 数えてから作るまでの間は排他しないので、同時に作られたルームの分だけ上限を超えることがある
+pub async fn check_rooms(
+    db: &impl Database,
+    owner: &str,
+    now_sec: u64,
+) -> Result<Option<Response<Body>>> {
+    let shared_rooms = SharedRoomTables::find_rooms_by_owner(db, owner.to_owned()).await?;
+    let reserved_rooms = ReservedRoomTables::find_rooms_by_owner(db, owner.to_owned()).await?;
+    let rooms = shared_rooms
+        .iter()
+        .filter(|room| !room.is_expired(now_sec))
+        .count()
+        + reserved_rooms
+            .iter()
+            .filter(|room| !room.is_expired(now_sec))
+            .count();
+    if rooms < RATE_LIMIT.rooms {
+        return Ok(None);
+    }
+    info!("[Rate Limit] Too many rooms: {}", owner);
+    Ok(Some(too_many_requests(RETRY_AFTER_INTERVAL_SEC as u64)))
+}
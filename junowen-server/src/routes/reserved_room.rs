@@ -1,9 +1,11 @@
 mod create;
 mod delete;
+mod password;
 mod read;
 mod update;
 
 use anyhow::Result;
+use junowen_lib::signaling_server::{reserved_room::ROOM_PASSWORD_HEADER, version::ClientVersion};
 use lambda_http::{
     http::{Method, StatusCode},
     Body, Request, Response,
@@ -11,7 +13,7 @@ use lambda_http::{
 use regex::Regex;
 use tracing::debug;
 
-use crate::database::ReservedRoomTables;
+use crate::database::Database;
 
 use self::{
     create::put_room,
@@ -21,14 +23,21 @@ use self::{
 };
 
 use super::{
+    rate_limit::owner,
     room_utils::{decode_room_name, from_post_room_keep_response, from_put_room_response},
     to_response, try_parse,
 };
 
+fn room_password(req: &Request) -> Option<&str> {
+    req.headers()
+        .get(ROOM_PASSWORD_HEADER)
+        .and_then(|x| x.to_str().ok())
+}
+
 pub async fn route(
     relative_uri: &str,
     req: &Request,
-    db: &impl ReservedRoomTables,
+    db: &impl Database,
 ) -> Result<Response<Body>> {
     let regex = Regex::new(r"^([^/]+)$").unwrap();
     if let Some(c) = regex.captures(relative_uri) {
@@ -46,12 +55,17 @@ pub async fn route(
                     to_response(StatusCode::BAD_REQUEST, Body::Empty)
                 }
                 Ok(body) => {
-                    let res = put_room(db, &room_name, body).await?;
-                    from_put_room_response(res)
+                    let client_version = ClientVersion::from_headers(req.headers());
+                    match put_room(db, &room_name, body, client_version, owner(req)).await? {
+                        Ok(res) => from_put_room_response(res),
+                        Err(res) => res,
+                    }
                 }
             },
             Method::GET => {
-                let res = get_room(db, &room_name).await?;
+                let client_version = ClientVersion::from_headers(req.headers());
+                let password = room_password(req);
+                let res = get_room(db, &room_name, client_version, password).await?;
                 to_response(
                     res.status_code(),
                     res.to_body().map(Body::Text).unwrap_or_else(|| Body::Empty),
@@ -86,7 +100,8 @@ pub async fn route(
                     to_response(StatusCode::BAD_REQUEST, Body::Empty)
                 }
                 Ok(body) => {
-                    let res = post_room_join(db, &room_name, body).await?;
+                    let password = room_password(req);
+                    let res = post_room_join(db, &room_name, body, password).await?;
                     to_response(res.status_code_old(), Body::Empty)
                 }
             },
@@ -109,7 +124,8 @@ pub async fn route(
                     to_response(StatusCode::BAD_REQUEST, Body::Empty)
                 }
                 Ok(body) => {
-                    let res = post_room_spectate(db, &room_name, body).await?;
+                    let password = room_password(req);
+                    let res = post_room_spectate(db, &room_name, body, password).await?;
                     to_response(res.status_code_old(), Body::Empty)
                 }
             },
@@ -1,40 +1,75 @@
 use anyhow::{bail, Result};
 use junowen_lib::signaling_server::{
-    reserved_room::{PutReservedRoomResponse, PutReservedRoomResponseConflictBody},
-    room::{PutRoomRequestBody, PutRoomResponseAnswerBody, PutRoomResponseWaitingBody},
+    reserved_room::{
+        PutReservedRoomRequestBody, PutReservedRoomResponse, PutReservedRoomResponseConflictBody,
+    },
+    room::{PutRoomResponseAnswerBody, PutRoomResponseWaitingBody},
+    version::ClientVersion,
 };
+use lambda_http::{Body, Response};
 use tracing::info;
 use uuid::Uuid;
 
 use crate::{
-    database::{PutError, ReservedRoom, ReservedRoomTables},
+    database::{Database, PutError, ReservedRoom, ReservedRoomTables},
     routes::{
-        reserved_room::{read::find_valid_room, update::find_opponent},
-        room_utils::{now_sec, ttl_sec, RETRY_AFTER_INTERVAL_SEC},
+        rate_limit::check_rooms,
+        reserved_room::{
+            password::{hash_password, verify_password},
+            read::find_valid_room,
+            update::find_opponent,
+        },
+        room_utils::{check_compatibility, now_sec, ttl_sec, RETRY_AFTER_INTERVAL_SEC},
     },
 };
 
 pub async fn put_room(
-    db: &impl ReservedRoomTables,
+    db: &impl Database,
     name: &str,
-    body: PutRoomRequestBody,
-) -> Result<PutReservedRoomResponse> {
+    body: PutReservedRoomRequestBody,
+    client_version: Option<ClientVersion>,
+    owner: String,
+) -> Result<Result<PutReservedRoomResponse, Response<Body>>> {
     let now_sec = now_sec();
     let key = Uuid::new_v4().to_string();
-    let room = ReservedRoom::new(
+    let mut room = ReservedRoom::new(
         name.to_owned(),
         key.clone(),
         Some(body.offer().clone()),
         None,
+        client_version.clone(),
+        body.public(),
         ttl_sec(now_sec),
     );
+    room.set_owner(Some(owner.clone()));
+    room.set_player_password_hash(body.player_password().as_deref().map(hash_password));
+    room.set_spectator_password_hash(body.spectator_password().as_deref().map(hash_password));
     for retry in 0.. {
         if let Some(room) = find_valid_room(db, now_sec, name.to_owned()).await? {
+            let password = body.player_password().as_deref();
+            if !verify_password(room.player_password_hash().as_ref(), password) {
+                info!("[Reserved Room] Wrong password: {}", name);
+                return Ok(Ok(PutReservedRoomResponse::forbidden(
+                    RETRY_AFTER_INTERVAL_SEC,
+                )));
+            }
+            let incompatible =
+                check_compatibility(room.client_version().as_ref(), client_version.as_ref());
+            if let Some(body) = incompatible {
+                info!("[Reserved Room] Incompatible: {}", name);
+                let response =
+                    PutReservedRoomResponse::incompatible(RETRY_AFTER_INTERVAL_SEC, body);
+                return Ok(Ok(response));
+            }
             let body = PutReservedRoomResponseConflictBody::new(room.into_opponent_offer_sdp());
             let response = PutReservedRoomResponse::conflict(RETRY_AFTER_INTERVAL_SEC, body);
-            return Ok(response);
+            return Ok(Ok(response));
+        }
+        // 既存のルームに入るだけならルームは増えないので、作るときだけ数える
+        if let Some(res) = check_rooms(db, &owner, now_sec).await? {
+            return Ok(Err(res));
         }
-        match db.put_room(room.clone()).await {
+        match ReservedRoomTables::put_room(db, room.clone()).await {
             Ok(()) => break,
             Err(PutError::Conflict) => {
                 if retry >= 2 {
@@ -46,7 +81,7 @@ pub async fn put_room(
         }
     }
     info!("[Reserved Room] Created: {}", name);
-    Ok(
+    Ok(Ok(
         if let Some(answer) = find_opponent(db, name.to_owned()).await? {
             let body = PutRoomResponseAnswerBody::new(answer.0.into_sdp());
             PutReservedRoomResponse::created_with_answer(RETRY_AFTER_INTERVAL_SEC, body)
@@ -54,5 +89,5 @@ pub async fn put_room(
             let body = PutRoomResponseWaitingBody::new(key);
             PutReservedRoomResponse::created_with_key(RETRY_AFTER_INTERVAL_SEC, body)
         },
-    )
+    ))
 }
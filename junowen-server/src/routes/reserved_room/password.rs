@@ -0,0 +1,26 @@
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+
+/// Argon2id の PHC 文字列で保存する
+pub fn hash_password(password: &str) -> String {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string()
+}
+
+/// パスワードが設定されていなければ誰でも通す
+pub fn verify_password(hash: Option<&String>, password: Option<&str>) -> bool {
+    let Some(hash) = hash else {
+        return true;
+    };
+    let (Ok(hash), Some(password)) = (PasswordHash::new(hash), password) else {
+        return false;
+    };
+    Argon2::default()
+        .verify_password(password.as_bytes(), &hash)
+        .is_ok()
+}
@@ -1,11 +1,15 @@
 use anyhow::Result;
-use junowen_lib::signaling_server::reserved_room::{
-    GetReservedRoomResponse, GetReservedRoomResponseOkBody,
+use junowen_lib::signaling_server::{
+    reserved_room::{GetReservedRoomResponse, GetReservedRoomResponseOkBody, SpectatorOffer},
+    version::ClientVersion,
 };
 
 use crate::{
     database::{ReservedRoom, ReservedRoomTables},
-    routes::room_utils::now_sec,
+    routes::{
+        reserved_room::password::verify_password,
+        room_utils::{check_compatibility, now_sec},
+    },
 };
 
 pub async fn find_valid_room(
@@ -26,13 +30,32 @@ pub async fn find_valid_room(
     Ok(None)
 }
 
-pub async fn get_room(db: &impl ReservedRoomTables, name: &str) -> Result<GetReservedRoomResponse> {
+pub async fn get_room(
+    db: &impl ReservedRoomTables,
+    name: &str,
+    client_version: Option<ClientVersion>,
+    password: Option<&str>,
+) -> Result<GetReservedRoomResponse> {
     let now_sec = now_sec();
     let Some(room) = find_valid_room(db, now_sec, name.to_owned()).await? else {
         return Ok(GetReservedRoomResponse::NotFound);
     };
-    let (opponent_offer_sdp, spectator_offer_sdp) =
-        room.into_opponent_offer_sdp_spectator_offer_sdp();
-    let body = GetReservedRoomResponseOkBody::new(opponent_offer_sdp, spectator_offer_sdp);
+    if !verify_password(room.spectator_password_hash().as_ref(), password) {
+        return Ok(GetReservedRoomResponse::Forbidden);
+    }
+    let incompatible = check_compatibility(room.client_version().as_ref(), client_version.as_ref());
+    if let Some(body) = incompatible {
+        return Ok(GetReservedRoomResponse::Incompatible(body));
+    }
+    let (opponent_offer_sdp, spectator_offer_sdp, spectator_offer_sdps) = room.into_offer_sdps();
+    let spectator_offers = spectator_offer_sdps
+        .into_iter()
+        .map(|(spectator_id, sdp)| SpectatorOffer::new(spectator_id, sdp))
+        .collect();
+    let body = GetReservedRoomResponseOkBody::new(
+        opponent_offer_sdp,
+        spectator_offer_sdp,
+        spectator_offers,
+    );
     Ok(GetReservedRoomResponse::Ok(body))
 }
@@ -4,7 +4,7 @@ use junowen_lib::signaling_server::{
         PostReservedRoomKeepRequestBody, PostReservedRoomKeepResponse,
         PostReservedRoomKeepResponseOkBody, PostReservedRoomKeepResponseOkOpponentAnswerBody,
         PostReservedRoomKeepResponseOkSpectatorAnswerBody, PostReservedRoomSpectateRequestBody,
-        PostReservedRoomSpectateResponse,
+        PostReservedRoomSpectateResponse, MAX_SPECTATOR_SLOTS,
     },
     room::{PostRoomJoinRequestBody, PostRoomJoinResponse},
 };
@@ -16,7 +16,10 @@ use crate::{
         Answer, PutError, ReservedRoomOpponentAnswer, ReservedRoomSpectatorAnswer,
         ReservedRoomTables,
     },
-    routes::room_utils::{now_sec, ttl_sec, RETRY_AFTER_INTERVAL_SEC},
+    routes::{
+        reserved_room::{password::verify_password, read::find_valid_room},
+        room_utils::{now_sec, ttl_sec, RETRY_AFTER_INTERVAL_SEC},
+    },
 };
 
 pub async fn find_opponent(
@@ -30,32 +33,72 @@ pub async fn find_opponent(
     Ok(Some(answer))
 }
 
+/// 観戦枠の応答はルーム名と観戦枠の ID を繋げた名前で保存する
+fn spectator_answer_name(name: &str, spectator_id: Option<&str>) -> String {
+    match spectator_id {
+        Some(spectator_id) => format!("{}/{}", name, spectator_id),
+        None => name.to_owned(),
+    }
+}
+
 pub async fn find_spectator(
     db: &impl ReservedRoomTables,
     name: String,
+    spectator_id: Option<String>,
 ) -> Result<Option<ReservedRoomSpectatorAnswer>> {
-    let Some(answer) = db.remove_room_spectator_answer(name.clone()).await? else {
+    let answer_name = spectator_answer_name(&name, spectator_id.as_deref());
+    let Some(answer) = db.remove_room_spectator_answer(answer_name).await? else {
         return Ok(None);
     };
-    db.remove_spectator_offer_sdp_in_room(name).await?;
+    db.remove_spectator_offer_sdp_in_room(name, spectator_id)
+        .await?;
     Ok(Some(answer))
 }
 
+fn spectator_answer_response(
+    answer: Option<ReservedRoomSpectatorAnswer>,
+) -> PostReservedRoomKeepResponse {
+    if let Some(answer) = answer {
+        PostReservedRoomKeepResponseOkBody::from(
+            PostReservedRoomKeepResponseOkSpectatorAnswerBody::new(answer.0.into_sdp()),
+        )
+        .into()
+    } else {
+        let retry_after = RETRY_AFTER_INTERVAL_SEC;
+        PostReservedRoomKeepResponse::NoContent { retry_after }
+    }
+}
+
 pub async fn post_room_keep(
     db: &impl ReservedRoomTables,
     name: &str,
     body: PostReservedRoomKeepRequestBody,
 ) -> Result<PostReservedRoomKeepResponse> {
-    let (key, spectator_offer) = body.into_inner();
-    if Uuid::parse_str(&key).is_err() {
+    let (key, spectator_offer, spectator_id) = body.into_inner();
+    if Uuid::parse_str(&key).is_err()
+        || spectator_id
+            .as_ref()
+            .is_some_and(|id| Uuid::parse_str(id).is_err())
+    {
         return Ok(PostReservedRoomKeepResponse::BadRequest);
     }
     let room = db
-        .keep_room(name.to_owned(), key, spectator_offer, ttl_sec(now_sec()))
+        .keep_room(
+            name.to_owned(),
+            key,
+            spectator_id.clone(),
+            spectator_offer,
+            ttl_sec(now_sec()),
+        )
         .await?;
     let Some(room) = room else {
         return Ok(PostReservedRoomKeepResponse::BadRequest);
     };
+    if room.spectator_offer_sdps().len() > MAX_SPECTATOR_SLOTS {
+        db.remove_spectator_offer_sdp_in_room(name.to_owned(), spectator_id)
+            .await?;
+        return Ok(PostReservedRoomKeepResponse::BadRequest);
+    }
     if room.opponent_offer_sdp().is_some() {
         return Ok(
             if let Some(answer) = find_opponent(db, name.to_owned()).await? {
@@ -69,18 +112,9 @@ pub async fn post_room_keep(
             },
         );
     }
-    if room.spectator_offer_sdp().is_some() {
-        return Ok(
-            if let Some(answer) = find_spectator(db, name.to_owned()).await? {
-                PostReservedRoomKeepResponseOkBody::from(
-                    PostReservedRoomKeepResponseOkSpectatorAnswerBody::new(answer.0.into_sdp()),
-                )
-                .into()
-            } else {
-                let retry_after = RETRY_AFTER_INTERVAL_SEC;
-                PostReservedRoomKeepResponse::NoContent { retry_after }
-            },
-        );
+    if spectator_id.is_some() || room.spectator_offer_sdp().is_some() {
+        let answer = find_spectator(db, name.to_owned(), spectator_id).await?;
+        return Ok(spectator_answer_response(answer));
     }
     let retry_after = RETRY_AFTER_INTERVAL_SEC;
     Ok(PostReservedRoomKeepResponse::NoContent { retry_after })
@@ -90,7 +124,15 @@ pub async fn post_room_join(
     db: &impl ReservedRoomTables,
     name: &str,
     body: PostRoomJoinRequestBody,
+    password: Option<&str>,
 ) -> Result<PostRoomJoinResponse> {
+    let room = find_valid_room(db, now_sec(), name.to_owned()).await?;
+    if let Some(room) = room {
+        if !verify_password(room.player_password_hash().as_ref(), password) {
+            info!("[Reserved Room] Wrong password: {}", name);
+            return Ok(PostRoomJoinResponse::Forbidden);
+        }
+    }
     let answer = ReservedRoomOpponentAnswer(Answer::new(
         name.to_owned(),
         body.into_answer(),
@@ -110,15 +152,37 @@ pub async fn post_room_spectate(
     db: &impl ReservedRoomTables,
     name: &str,
     body: PostReservedRoomSpectateRequestBody,
+    password: Option<&str>,
 ) -> Result<PostReservedRoomSpectateResponse> {
+    let (answer, spectator_id) = body.into_inner();
+    let room = find_valid_room(db, now_sec(), name.to_owned()).await?;
+    if let Some(room) = &room {
+        if !verify_password(room.spectator_password_hash().as_ref(), password) {
+            info!("[Reserved Room] Wrong password: {}", name);
+            return Ok(PostReservedRoomSpectateResponse::Forbidden);
+        }
+    }
+    if let Some(spectator_id) = &spectator_id {
+        // 他の観戦者が先に応答した観戦枠は一覧から消えている
+        let Some(room) = room else {
+            return Ok(PostReservedRoomSpectateResponse::Conflict);
+        };
+        if !room.spectator_offer_sdps().contains_key(spectator_id) {
+            return Ok(PostReservedRoomSpectateResponse::Conflict);
+        }
+    }
     let answer = ReservedRoomSpectatorAnswer(Answer::new(
-        name.to_owned(),
-        body.into_answer(),
+        spectator_answer_name(name, spectator_id.as_deref()),
+        answer,
         ttl_sec(now_sec()),
     ));
     match db.put_room_spectator_answer(answer).await {
         Ok(()) => {
             info!("[Reserved Room] Spectate: {}", name);
+            if spectator_id.is_some() {
+                db.remove_spectator_offer_sdp_in_room(name.to_owned(), spectator_id)
+                    .await?;
+            }
             Ok(PostRoomJoinResponse::Ok)
         }
         Err(PutError::Conflict) => Ok(PostRoomJoinResponse::Conflict),
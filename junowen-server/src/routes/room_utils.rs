@@ -3,7 +3,10 @@ use std::{
     time::{SystemTime, UNIX_EPOCH},
 };
 
-use junowen_lib::signaling_server::room::{PostRoomKeepResponse, PutRoomResponse};
+use junowen_lib::signaling_server::{
+    room::{PostRoomKeepResponse, PutRoomResponse},
+    version::{ClientVersion, IncompatibleResponseBody},
+};
 use lambda_http::{Body, Response};
 use serde::{Deserialize, Serialize};
 
@@ -34,10 +37,25 @@ where
             Body::Text(serde_json::to_string(&body).unwrap())
         }
         PutRoomResponse::Conflict { body, .. } => Body::Text(serde_json::to_string(&body).unwrap()),
+        PutRoomResponse::Incompatible { body, .. } => {
+            Body::Text(serde_json::to_string(&body).unwrap())
+        }
+        PutRoomResponse::Forbidden { .. } => Body::Empty,
     };
     to_response(status_code, body)
 }
 
+/// バージョンを申告していないクライアントがいれば判断できないので通す
+pub fn check_compatibility(
+    room: Option<&ClientVersion>,
+    client: Option<&ClientVersion>,
+) -> Option<IncompatibleResponseBody> {
+    let (Some(room), Some(client)) = (room, client) else {
+        return None;
+    };
+    (!room.is_compatible_with(client)).then(|| IncompatibleResponseBody::new(room.clone()))
+}
+
 pub fn from_post_room_keep_response<'a, T>(value: PostRoomKeepResponse<T>) -> Response<Body>
 where
     T: Deserialize<'a> + Serialize,
@@ -0,0 +1,118 @@
+mod public_rooms;
+mod quick_match;
+mod rate_limit;
+mod reserved_room;
+mod shared_room;
+
+use junowen_lib::{
+    connection::signaling::CompressedSdp,
+    signaling_server::{reserved_room::ROOM_PASSWORD_HEADER, version::ClientVersion},
+};
+use lambda_http::{
+    http::{header::RETRY_AFTER, Method, StatusCode},
+    Body, IntoResponse, Request,
+};
+use serde::Serialize;
+
+use crate::database::Memory;
+
+use super::routes;
+
+pub fn sdp(value: &str) -> CompressedSdp {
+    serde_json::from_value(serde_json::Value::String(value.to_owned())).unwrap()
+}
+
+/// クライアントと同じ方法でルーム名をエンコードする
+pub fn encode_room_name(room_name: &str) -> String {
+    urlencoding::encode(room_name).replace("%20", "+")
+}
+
+pub struct TestResponse {
+    pub status: StatusCode,
+    pub retry_after: Option<u32>,
+    pub text: Option<String>,
+}
+
+impl TestResponse {
+    pub fn text(&self) -> &str {
+        self.text.as_deref().unwrap_or_default()
+    }
+}
+
+/// routes をサーバーの外側から HTTP リクエストで叩く
+#[derive(Default)]
+pub struct TestClient {
+    pub db: Memory,
+    /// None なら古いクライアントと同様にバージョンヘッダーを送らない
+    pub version: Option<ClientVersion>,
+    /// None なら 192.0.2.1
+    pub ip: Option<&'static str>,
+    pub room_password: Option<&'static str>,
+}
+
+impl TestClient {
+    pub async fn request(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&impl Serialize>,
+    ) -> TestResponse {
+        let body = body
+            .map(|body| Body::Text(serde_json::to_string(body).unwrap()))
+            .unwrap_or(Body::Empty);
+        let mut req: Request = lambda_http::http::Request::builder()
+            .method(method)
+            .uri(path)
+            .header("x-forwarded-for", self.ip.unwrap_or("192.0.2.1"))
+            .body(body)
+            .unwrap();
+        if let Some(version) = &self.version {
+            req.headers_mut().extend(version.to_headers());
+        }
+        if let Some(room_password) = self.room_password {
+            let value = room_password.parse().unwrap();
+            req.headers_mut().insert(ROOM_PASSWORD_HEADER, value);
+        }
+        let res = routes(&req, &self.db).await.unwrap().into_response().await;
+        let retry_after = res
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|x| x.to_str().ok())
+            .and_then(|x| x.parse().ok());
+        let text = match res.body() {
+            Body::Empty => None,
+            Body::Text(text) => Some(text.clone()),
+            Body::Binary(binary) => Some(String::from_utf8(binary.clone()).unwrap()),
+        };
+        TestResponse {
+            status: res.status(),
+            retry_after,
+            text,
+        }
+    }
+
+    pub async fn get(&self, path: &str) -> TestResponse {
+        self.request(Method::GET, path, None::<&()>).await
+    }
+
+    pub async fn put(&self, path: &str, body: &impl Serialize) -> TestResponse {
+        self.request(Method::PUT, path, Some(body)).await
+    }
+
+    pub async fn post(&self, path: &str, body: &impl Serialize) -> TestResponse {
+        self.request(Method::POST, path, Some(body)).await
+    }
+
+    pub async fn delete(&self, path: &str, body: &impl Serialize) -> TestResponse {
+        self.request(Method::DELETE, path, Some(body)).await
+    }
+}
+
+#[tokio::test]
+async fn unknown_path_is_not_found() {
+    let client = TestClient::default();
+    let res = client.get("/unknown/room").await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+    let res = client.get("/custom/room/unknown").await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+}
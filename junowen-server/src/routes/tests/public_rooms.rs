@@ -0,0 +1,110 @@
+use junowen_lib::signaling_server::{
+    public_room::{GetPublicRoomsResponse, PublicRoom, PublicRoomKind},
+    reserved_room::{PostReservedRoomKeepRequestBody, PutReservedRoomRequestBody},
+    room::{PostRoomJoinRequestBody, PutRoomRequestBody},
+};
+use lambda_http::http::StatusCode;
+
+use super::{encode_room_name, sdp, TestClient};
+
+async fn create(client: &TestClient, path: &str, room_name: &str, public: bool) -> String {
+    let url = format!("{}/{}", path, encode_room_name(room_name));
+    let res = client
+        .put(&url, &PutRoomRequestBody::new(sdp("offer"), public))
+        .await;
+    assert_eq!(res.status, StatusCode::CREATED);
+    let body: serde_json::Value = serde_json::from_str(res.text()).unwrap();
+    body["key"].as_str().unwrap().to_owned()
+}
+
+async fn list(client: &TestClient) -> Vec<PublicRoom> {
+    let res = client.get("/public-rooms").await;
+    assert!(res.retry_after.is_some());
+    let GetPublicRoomsResponse::Ok(body) =
+        GetPublicRoomsResponse::parse(res.status, res.text.as_deref()).unwrap();
+    body.into_rooms()
+}
+
+#[tokio::test]
+async fn only_public_rooms_are_listed() {
+    let client = TestClient::default();
+    create(&client, "/custom", "public shared", true).await;
+    create(&client, "/custom", "private shared", false).await;
+    create(&client, "/reserved-room", "public reserved", true).await;
+    create(&client, "/reserved-room", "private reserved", false).await;
+
+    let mut rooms = list(&client).await;
+    rooms.sort_by(|a, b| a.name().cmp(b.name()));
+    assert_eq!(rooms.len(), 2);
+    assert_eq!(rooms[0].name(), "public reserved");
+    assert_eq!(rooms[0].kind(), PublicRoomKind::Reserved);
+    assert!(rooms[0].opponent_slot_free());
+    assert!(!rooms[0].spectator_slot_free());
+    assert_eq!(rooms[1].name(), "public shared");
+    assert_eq!(rooms[1].kind(), PublicRoomKind::Shared);
+    assert!(rooms[1].opponent_slot_free());
+    assert!(rooms[1].age_sec() <= 1);
+}
+
+#[tokio::test]
+async fn reserved_room_in_match_is_listed_while_spectator_slot_is_free() {
+    let client = TestClient::default();
+    let key = create(&client, "/reserved-room", "room", true).await;
+    let res = client
+        .post(
+            "/reserved-room/room/join",
+            &PostRoomJoinRequestBody::new(sdp("answer")),
+        )
+        .await;
+    assert!(res.status.is_success());
+    let body = PostReservedRoomKeepRequestBody::new(key.clone(), None, None);
+    let res = client.post("/reserved-room/room/keep", &body).await;
+    assert_eq!(res.status, StatusCode::OK);
+
+    // 対戦中で観戦者を受け付けていない
+    assert!(list(&client).await.is_empty());
+
+    let body = PostReservedRoomKeepRequestBody::new(key, Some(sdp("spectator offer")), None);
+    client.post("/reserved-room/room/keep", &body).await;
+    let rooms = list(&client).await;
+    assert_eq!(rooms.len(), 1);
+    assert!(!rooms[0].opponent_slot_free());
+    assert!(rooms[0].spectator_slot_free());
+}
+
+#[tokio::test]
+async fn matched_shared_room_is_unlisted() {
+    let client = TestClient::default();
+    let key = create(&client, "/custom", "room", true).await;
+    client
+        .post(
+            "/custom/room/join",
+            &PostRoomJoinRequestBody::new(sdp("answer")),
+        )
+        .await;
+    let body = serde_json::json!({ "key": key });
+    let res = client.post("/custom/room/keep", &body).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert!(list(&client).await.is_empty());
+}
+
+#[tokio::test]
+async fn password_protected_room_is_marked() {
+    let client = TestClient::default();
+    create(&client, "/custom", "shared", true).await;
+    create(&client, "/reserved-room", "open", true).await;
+    let body = PutReservedRoomRequestBody::new(sdp("offer"), true, Some("pw".to_owned()), None);
+    let res = client.put("/reserved-room/locked", &body).await;
+    assert_eq!(res.status, StatusCode::CREATED);
+
+    let mut rooms = list(&client).await;
+    rooms.sort_by(|a, b| a.name().cmp(b.name()));
+    let required: Vec<_> = rooms
+        .iter()
+        .map(|room| (room.name().as_str(), room.password_required()))
+        .collect();
+    assert_eq!(
+        required,
+        vec![("locked", true), ("open", false), ("shared", false)]
+    );
+}
@@ -0,0 +1,136 @@
+use junowen_lib::signaling_server::{
+    quick_match::{PostQuickMatchRequestBody, PostQuickMatchResponse, QuickMatchFilter},
+    room::PostRoomJoinRequestBody,
+    version::ClientVersion,
+};
+use lambda_http::http::StatusCode;
+
+use crate::{
+    database::{SharedRoom, SharedRoomTables},
+    routes::room_utils::{now_sec, ttl_sec},
+};
+
+use super::{sdp, TestClient};
+
+fn filter(round: Option<u8>, region: Option<&str>) -> QuickMatchFilter {
+    QuickMatchFilter::new(round, None, region.map(|x| x.to_owned()))
+}
+
+async fn quick_match(
+    client: &TestClient,
+    offer: &str,
+    filter: QuickMatchFilter,
+) -> PostQuickMatchResponse {
+    let body = PostQuickMatchRequestBody::new(sdp(offer), filter);
+    let res = client.post("/quick-match", &body).await;
+    PostQuickMatchResponse::parse(res.status, res.retry_after, res.text.as_deref()).unwrap()
+}
+
+fn waiting_room_name(res: PostQuickMatchResponse) -> (String, String) {
+    let PostQuickMatchResponse::Waiting { body, .. } = res else {
+        panic!("expected waiting: {:?}", res);
+    };
+    body.into_inner()
+}
+
+#[tokio::test]
+async fn second_player_is_matched_with_waiting_player() {
+    let client = TestClient::default();
+    let (room_name, key) =
+        waiting_room_name(quick_match(&client, "offer", filter(None, None)).await);
+
+    let PostQuickMatchResponse::Matched(body) =
+        quick_match(&client, "other offer", filter(Some(3), None)).await
+    else {
+        panic!();
+    };
+    let (matched_room_name, offer) = body.into_inner();
+    assert_eq!(matched_room_name, room_name);
+    assert_eq!(offer.into_inner(), "offer");
+
+    // 以降は共有ルームと同じ手順で応答を交換する
+    let path = format!("/custom/{}/join", room_name);
+    let res = client
+        .post(&path, &PostRoomJoinRequestBody::new(sdp("answer")))
+        .await;
+    assert!(res.status.is_success());
+    let path = format!("/custom/{}/keep", room_name);
+    let res = client.post(&path, &serde_json::json!({ "key": key })).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert!(client.db.find_quick_match_rooms().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn mismatched_filters_are_not_matched() {
+    let client = TestClient::default();
+    waiting_room_name(quick_match(&client, "offer", filter(Some(3), Some("Asia"))).await);
+    waiting_room_name(quick_match(&client, "offer", filter(Some(5), None)).await);
+    waiting_room_name(quick_match(&client, "offer", filter(Some(3), Some("Europe"))).await);
+    assert_eq!(client.db.find_quick_match_rooms().await.unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn incompatible_players_are_not_matched() {
+    let mut client = TestClient {
+        version: Some(ClientVersion::new(
+            "junowen/0".to_owned(),
+            "0.0.0".to_owned(),
+        )),
+        ..Default::default()
+    };
+    waiting_room_name(quick_match(&client, "offer", filter(None, None)).await);
+    client.version = Some(ClientVersion::current());
+    waiting_room_name(quick_match(&client, "offer", filter(None, None)).await);
+}
+
+#[tokio::test]
+async fn racing_players_are_not_matched_with_the_same_room() {
+    let client = TestClient::default();
+    let (room_name, _) = waiting_room_name(quick_match(&client, "offer", filter(None, None)).await);
+
+    let (a, b) = tokio::join!(
+        quick_match(&client, "offer a", filter(None, None)),
+        quick_match(&client, "offer b", filter(None, None)),
+    );
+    let matched: Vec<_> = [a, b]
+        .into_iter()
+        .filter_map(|res| match res {
+            PostQuickMatchResponse::Matched(body) => Some(body.into_inner().0),
+            PostQuickMatchResponse::Waiting { .. } => None,
+        })
+        .collect();
+    assert_eq!(matched, vec![room_name]);
+    assert_eq!(client.db.find_quick_match_rooms().await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn oldest_waiting_player_is_matched_first() {
+    let client = TestClient::default();
+    let now_sec = now_sec();
+    for (name, age_sec) in [("newer", 1), ("oldest", 5), ("expired", 30)] {
+        let mut room: serde_json::Value = serde_json::to_value(SharedRoom::new(
+            name.to_owned(),
+            "key".to_owned(),
+            sdp(name),
+            None,
+            false,
+            Some(QuickMatchFilter::default()),
+            if name == "expired" {
+                now_sec - 1
+            } else {
+                ttl_sec(now_sec)
+            },
+        ))
+        .unwrap();
+        room["created_at_sec"] = (now_sec - age_sec).into();
+        let room: SharedRoom = serde_json::from_value(room).unwrap();
+        client.db.put_room(room).await.unwrap();
+    }
+
+    let PostQuickMatchResponse::Matched(body) =
+        quick_match(&client, "offer", filter(None, None)).await
+    else {
+        panic!();
+    };
+    assert_eq!(body.into_inner().0, "oldest");
+}
@@ -0,0 +1,105 @@
+use junowen_lib::signaling_server::room::{DeleteRoomRequestBody, PutRoomRequestBody};
+use lambda_http::http::StatusCode;
+
+use crate::routes::rate_limit::RATE_LIMIT;
+
+use super::{sdp, TestClient};
+
+async fn create(client: &TestClient, room_name: &str) -> (StatusCode, Option<String>) {
+    let url = format!("/custom/{}", room_name);
+    let res = client
+        .put(&url, &PutRoomRequestBody::new(sdp("offer"), false))
+        .await;
+    let key = serde_json::from_str::<serde_json::Value>(res.text())
+        .ok()
+        .and_then(|body| body["key"].as_str().map(|x| x.to_owned()));
+    (res.status, key)
+}
+
+#[tokio::test]
+async fn requests_over_budget_are_rejected_per_ip() {
+    let mut client = TestClient::default();
+    for _ in 0..RATE_LIMIT.requests() {
+        let res = client.get("/public-rooms").await;
+        assert_eq!(res.status, StatusCode::OK);
+    }
+    let res = client.get("/public-rooms").await;
+    assert_eq!(res.status, StatusCode::TOO_MANY_REQUESTS);
+    let retry_after = res.retry_after.unwrap();
+    assert!(0 < retry_after && retry_after as u64 <= RATE_LIMIT.window_sec());
+
+    client.ip = Some("192.0.2.2");
+    let res = client.get("/public-rooms").await;
+    assert_eq!(res.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn rooms_over_limit_are_rejected_until_one_is_removed() {
+    let mut client = TestClient::default();
+    let mut keys = vec![];
+    for i in 0..RATE_LIMIT.rooms() {
+        let (status, key) = create(&client, &format!("room{}", i)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        keys.push(key.unwrap());
+    }
+    let (status, _) = create(&client, "extra").await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    let res = client
+        .put(
+            "/reserved-room/extra",
+            &PutRoomRequestBody::new(sdp("offer"), false),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::TOO_MANY_REQUESTS);
+    assert!(res.retry_after.is_some());
+
+    // 他の IP アドレスには影響しない
+    client.ip = Some("192.0.2.2");
+    let (status, _) = create(&client, "other").await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    client.ip = None;
+    let body = DeleteRoomRequestBody::new(keys.pop().unwrap());
+    let res = client.delete("/custom/room0", &body).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+    let res = client
+        .delete(&format!("/custom/room{}", RATE_LIMIT.rooms() - 1), &body)
+        .await;
+    assert_eq!(res.status, StatusCode::NO_CONTENT);
+    let (status, _) = create(&client, "extra").await;
+    assert_eq!(status, StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn forged_forwarded_for_entries_do_not_change_the_owner() {
+    let mut client = TestClient::default();
+    for _ in 0..RATE_LIMIT.requests() {
+        let res = client.get("/public-rooms").await;
+        assert_eq!(res.status, StatusCode::OK);
+    }
+    // プロキシが追記した最後の値だけで区別する
+    client.ip = Some("198.51.100.1, 192.0.2.1");
+    let res = client.get("/public-rooms").await;
+    assert_eq!(res.status, StatusCode::TOO_MANY_REQUESTS);
+    client.ip = Some("198.51.100.2,192.0.2.1");
+    let res = client.get("/public-rooms").await;
+    assert_eq!(res.status, StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[tokio::test]
+async fn clients_over_room_limit_can_still_join_existing_rooms() {
+    let mut client = TestClient::default();
+    let (status, _) = create(&client, "host").await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    client.ip = Some("192.0.2.2");
+    for i in 0..RATE_LIMIT.rooms() {
+        let (status, _) = create(&client, &format!("room{}", i)).await;
+        assert_eq!(status, StatusCode::CREATED);
+    }
+    // 既存のルームへの PUT はルームを増やさないので、相手のオファーを受け取れる
+    let (status, _) = create(&client, "host").await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, _) = create(&client, "extra").await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+}
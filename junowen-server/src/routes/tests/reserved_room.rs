@@ -0,0 +1,511 @@
+use junowen_lib::signaling_server::{
+    reserved_room::{
+        GetReservedRoomResponse, PostReservedRoomKeepRequestBody, PostReservedRoomKeepResponse,
+        PostReservedRoomKeepResponseOkBody, PostReservedRoomSpectateRequestBody,
+        PostReservedRoomSpectateResponse, PutReservedRoomRequestBody, PutReservedRoomResponse,
+        MAX_SPECTATOR_SLOTS,
+    },
+    room::{
+        DeleteRoomRequestBody, DeleteRoomResponse, PostRoomJoinRequestBody, PostRoomJoinResponse,
+        PutRoomRequestBody,
+    },
+    version::ClientVersion,
+};
+
+use crate::{
+    database::{ReservedRoom, ReservedRoomTables},
+    routes::room_utils::now_sec,
+};
+
+use super::{encode_room_name, sdp, TestClient};
+
+fn path(room_name: &str) -> String {
+    format!("/reserved-room/{}", encode_room_name(room_name))
+}
+
+async fn put(client: &TestClient, room_name: &str, offer: &str) -> PutReservedRoomResponse {
+    let res = client
+        .put(
+            &path(room_name),
+            &PutRoomRequestBody::new(sdp(offer), false),
+        )
+        .await;
+    PutReservedRoomResponse::parse(res.status, res.retry_after, res.text()).unwrap()
+}
+
+async fn create(client: &TestClient, room_name: &str, offer: &str) -> String {
+    let res = put(client, room_name, offer).await;
+    let PutReservedRoomResponse::CreatedWithKey { body, .. } = res else {
+        panic!("unexpected response: {:?}", res);
+    };
+    body.into_key()
+}
+
+async fn get(client: &TestClient, room_name: &str) -> GetReservedRoomResponse {
+    let res = client.get(&path(room_name)).await;
+    assert!(res.retry_after.is_some());
+    GetReservedRoomResponse::parse(res.status, res.text.as_deref()).unwrap()
+}
+
+async fn keep(
+    client: &TestClient,
+    room_name: &str,
+    key: &str,
+    spectator_offer: Option<&str>,
+) -> PostReservedRoomKeepResponse {
+    let url = format!("{}/keep", path(room_name));
+    let body = PostReservedRoomKeepRequestBody::new(key.to_owned(), spectator_offer.map(sdp), None);
+    let res = client.post(&url, &body).await;
+    PostReservedRoomKeepResponse::parse(res.status, res.retry_after, res.text.as_deref()).unwrap()
+}
+
+async fn keep_slot(
+    client: &TestClient,
+    room_name: &str,
+    key: &str,
+    spectator_id: &str,
+    spectator_offer: Option<&str>,
+) -> PostReservedRoomKeepResponse {
+    let url = format!("{}/keep", path(room_name));
+    let body = PostReservedRoomKeepRequestBody::new(
+        key.to_owned(),
+        spectator_offer.map(sdp),
+        Some(spectator_id.to_owned()),
+    );
+    let res = client.post(&url, &body).await;
+    PostReservedRoomKeepResponse::parse(res.status, res.retry_after, res.text.as_deref()).unwrap()
+}
+
+async fn join(client: &TestClient, room_name: &str, answer: &str) -> PostRoomJoinResponse {
+    let url = format!("{}/join", path(room_name));
+    let res = client
+        .post(&url, &PostRoomJoinRequestBody::new(sdp(answer)))
+        .await;
+    PostRoomJoinResponse::parse(res.status).unwrap()
+}
+
+async fn spectate(
+    client: &TestClient,
+    room_name: &str,
+    answer: &str,
+) -> PostReservedRoomSpectateResponse {
+    spectate_slot(client, room_name, None, answer).await
+}
+
+async fn spectate_slot(
+    client: &TestClient,
+    room_name: &str,
+    spectator_id: Option<&str>,
+    answer: &str,
+) -> PostReservedRoomSpectateResponse {
+    let url = format!("{}/spectate", path(room_name));
+    let body =
+        PostReservedRoomSpectateRequestBody::new(sdp(answer), spectator_id.map(|x| x.to_owned()));
+    let res = client.post(&url, &body).await;
+    assert!(res.retry_after.is_some());
+    PostReservedRoomSpectateResponse::parse(res.status).unwrap()
+}
+
+fn spectator_answer(res: PostReservedRoomKeepResponse) -> String {
+    let PostReservedRoomKeepResponse::Ok(PostReservedRoomKeepResponseOkBody::SpectatorAnswer(body)) =
+        res
+    else {
+        panic!("unexpected response: {:?}", res);
+    };
+    body.into_spectator_answer().into_inner()
+}
+
+async fn start_match(client: &TestClient, room_name: &str) -> String {
+    let key = create(client, room_name, "offer").await;
+    assert!(matches!(
+        join(client, room_name, "answer").await,
+        PostRoomJoinResponse::Ok
+    ));
+    let res = keep(client, room_name, &key, None).await;
+    let PostReservedRoomKeepResponse::Ok(PostReservedRoomKeepResponseOkBody::OpponentAnswer(body)) =
+        res
+    else {
+        panic!("unexpected response: {:?}", res);
+    };
+    assert_eq!(body.into_opponent_answer().into_inner(), "answer");
+    key
+}
+
+#[tokio::test]
+async fn opponent_joins_by_offer_in_room() {
+    let client = TestClient::default();
+    let key = create(&client, "room", "offer").await;
+
+    let GetReservedRoomResponse::Ok(body) = get(&client, "room").await else {
+        panic!("room not found");
+    };
+    assert_eq!(body.opponent_offer().unwrap().clone().into_inner(), "offer");
+    assert!(body.into_spectator_offer().is_none());
+
+    let res = keep(&client, "room", &key, None).await;
+    assert!(matches!(
+        res,
+        PostReservedRoomKeepResponse::NoContent { .. }
+    ));
+
+    // 2 人目のホストはルームのオファーを受け取って応答する
+    let PutReservedRoomResponse::Conflict { body, .. } = put(&client, "room", "offer2").await
+    else {
+        panic!("unexpected response");
+    };
+    assert_eq!(body.into_offer().unwrap().into_inner(), "offer");
+    assert!(matches!(
+        join(&client, "room", "answer").await,
+        PostRoomJoinResponse::Ok
+    ));
+    assert!(matches!(
+        join(&client, "room", "answer2").await,
+        PostRoomJoinResponse::Conflict
+    ));
+
+    let res = keep(&client, "room", &key, None).await;
+    let PostReservedRoomKeepResponse::Ok(PostReservedRoomKeepResponseOkBody::OpponentAnswer(body)) =
+        res
+    else {
+        panic!("unexpected response: {:?}", res);
+    };
+    assert_eq!(body.into_opponent_answer().into_inner(), "answer");
+}
+
+#[tokio::test]
+async fn room_is_full_after_match_started() {
+    let client = TestClient::default();
+    start_match(&client, "room").await;
+
+    let GetReservedRoomResponse::Ok(body) = get(&client, "room").await else {
+        panic!("room not found");
+    };
+    assert!(body.opponent_offer().is_none());
+
+    // クライアントはオファーの無い Conflict を満室として扱う
+    let PutReservedRoomResponse::Conflict { body, .. } = put(&client, "room", "offer2").await
+    else {
+        panic!("unexpected response");
+    };
+    assert!(body.into_offer().is_none());
+}
+
+#[tokio::test]
+async fn spectator_joins_by_spectator_offer_in_room() {
+    let client = TestClient::default();
+    let key = start_match(&client, "room").await;
+
+    let GetReservedRoomResponse::Ok(body) = get(&client, "room").await else {
+        panic!("room not found");
+    };
+    assert!(body.into_spectator_offer().is_none());
+
+    let res = keep(&client, "room", &key, Some("s-offer")).await;
+    assert!(matches!(
+        res,
+        PostReservedRoomKeepResponse::NoContent { .. }
+    ));
+    let GetReservedRoomResponse::Ok(body) = get(&client, "room").await else {
+        panic!("room not found");
+    };
+    assert_eq!(body.into_spectator_offer().unwrap().into_inner(), "s-offer");
+
+    assert!(matches!(
+        spectate(&client, "room", "s-answer").await,
+        PostReservedRoomSpectateResponse::Ok
+    ));
+    assert!(matches!(
+        spectate(&client, "room", "s-answer2").await,
+        PostReservedRoomSpectateResponse::Conflict
+    ));
+
+    let res = keep(&client, "room", &key, None).await;
+    let PostReservedRoomKeepResponse::Ok(PostReservedRoomKeepResponseOkBody::SpectatorAnswer(body)) =
+        res
+    else {
+        panic!("unexpected response: {:?}", res);
+    };
+    assert_eq!(body.into_spectator_answer().into_inner(), "s-answer");
+
+    // 次の観戦者は新しいオファーを待つ
+    let GetReservedRoomResponse::Ok(body) = get(&client, "room").await else {
+        panic!("room not found");
+    };
+    assert!(body.into_spectator_offer().is_none());
+    let res = keep(&client, "room", &key, Some("s-offer2")).await;
+    assert!(matches!(
+        res,
+        PostReservedRoomKeepResponse::NoContent { .. }
+    ));
+}
+
+#[tokio::test]
+async fn spectators_join_multiple_slots_concurrently() {
+    const SLOT1: &str = "00000000-0000-0000-0000-000000000001";
+    const SLOT2: &str = "00000000-0000-0000-0000-000000000002";
+    let client = TestClient::default();
+    let key = start_match(&client, "room").await;
+
+    for (id, offer) in [(SLOT1, "s-offer1"), (SLOT2, "s-offer2")] {
+        let res = keep_slot(&client, "room", &key, id, Some(offer)).await;
+        assert!(matches!(
+            res,
+            PostReservedRoomKeepResponse::NoContent { .. }
+        ));
+    }
+    let GetReservedRoomResponse::Ok(body) = get(&client, "room").await else {
+        panic!("room not found");
+    };
+    let offers: Vec<_> = body
+        .into_spectator_offers()
+        .into_iter()
+        .map(|(id, offer)| (id.unwrap(), offer.into_inner()))
+        .collect();
+    assert_eq!(
+        offers,
+        [
+            (SLOT1.to_owned(), "s-offer1".to_owned()),
+            (SLOT2.to_owned(), "s-offer2".to_owned())
+        ]
+    );
+
+    assert!(matches!(
+        spectate_slot(&client, "room", Some(SLOT1), "s-answer1").await,
+        PostReservedRoomSpectateResponse::Ok
+    ));
+    // 応答済みの観戦枠には入れない
+    assert!(matches!(
+        spectate_slot(&client, "room", Some(SLOT1), "s-answer1b").await,
+        PostReservedRoomSpectateResponse::Conflict
+    ));
+    assert!(matches!(
+        spectate_slot(&client, "room", Some(SLOT2), "s-answer2").await,
+        PostReservedRoomSpectateResponse::Ok
+    ));
+    let GetReservedRoomResponse::Ok(body) = get(&client, "room").await else {
+        panic!("room not found");
+    };
+    assert!(body.into_spectator_offers().is_empty());
+
+    let res = keep_slot(&client, "room", &key, SLOT2, None).await;
+    assert_eq!(spectator_answer(res), "s-answer2");
+    let res = keep_slot(&client, "room", &key, SLOT1, None).await;
+    assert_eq!(spectator_answer(res), "s-answer1");
+    let res = keep_slot(&client, "room", &key, SLOT1, None).await;
+    assert!(matches!(
+        res,
+        PostReservedRoomKeepResponse::NoContent { .. }
+    ));
+}
+
+#[tokio::test]
+async fn spectator_slots_are_limited() {
+    let client = TestClient::default();
+    let key = start_match(&client, "room").await;
+
+    let res = keep_slot(&client, "room", &key, "not-a-uuid", Some("s-offer")).await;
+    assert!(matches!(res, PostReservedRoomKeepResponse::BadRequest));
+    for i in 0..MAX_SPECTATOR_SLOTS {
+        let id = format!("00000000-0000-0000-0000-{:012}", i);
+        let res = keep_slot(&client, "room", &key, &id, Some("s-offer")).await;
+        assert!(matches!(
+            res,
+            PostReservedRoomKeepResponse::NoContent { .. }
+        ));
+    }
+    let id = format!("00000000-0000-0000-0000-{:012}", MAX_SPECTATOR_SLOTS);
+    let res = keep_slot(&client, "room", &key, &id, Some("s-offer")).await;
+    assert!(matches!(res, PostReservedRoomKeepResponse::BadRequest));
+    let GetReservedRoomResponse::Ok(body) = get(&client, "room").await else {
+        panic!("room not found");
+    };
+    assert_eq!(body.into_spectator_offers().len(), MAX_SPECTATOR_SLOTS);
+}
+
+#[tokio::test]
+async fn keep_with_wrong_key_is_bad_request() {
+    let client = TestClient::default();
+    create(&client, "room", "offer").await;
+
+    let res = keep(&client, "room", "not-a-uuid", None).await;
+    assert!(matches!(res, PostReservedRoomKeepResponse::BadRequest));
+    let res = keep(
+        &client,
+        "room",
+        "00000000-0000-0000-0000-000000000000",
+        None,
+    )
+    .await;
+    assert!(matches!(res, PostReservedRoomKeepResponse::BadRequest));
+}
+
+#[tokio::test]
+async fn only_owner_can_delete_room() {
+    let client = TestClient::default();
+    let key = create(&client, "room", "offer").await;
+
+    let url = path("room");
+    let body = DeleteRoomRequestBody::new("00000000-0000-0000-0000-000000000000".to_owned());
+    let res = client.delete(&url, &body).await;
+    assert!(matches!(
+        DeleteRoomResponse::parse(res.status).unwrap(),
+        DeleteRoomResponse::BadRequest
+    ));
+    let res = client.delete(&url, &DeleteRoomRequestBody::new(key)).await;
+    assert!(matches!(
+        DeleteRoomResponse::parse(res.status).unwrap(),
+        DeleteRoomResponse::NoContent
+    ));
+    assert!(matches!(
+        get(&client, "room").await,
+        GetReservedRoomResponse::NotFound
+    ));
+}
+
+#[tokio::test]
+async fn expired_room_is_not_found() {
+    let client = TestClient::default();
+    let room = ReservedRoom::new(
+        "room".to_owned(),
+        "00000000-0000-0000-0000-000000000000".to_owned(),
+        Some(sdp("stale offer")),
+        None,
+        None,
+        false,
+        now_sec() - 1,
+    );
+    client.db.put_room(room).await.unwrap();
+
+    assert!(matches!(
+        get(&client, "room").await,
+        GetReservedRoomResponse::NotFound
+    ));
+    let res = keep(
+        &client,
+        "room",
+        "00000000-0000-0000-0000-000000000000",
+        None,
+    )
+    .await;
+    assert!(matches!(res, PostReservedRoomKeepResponse::BadRequest));
+    create(&client, "room", "offer").await;
+}
+
+#[tokio::test]
+async fn incompatible_opponent_and_spectator_are_rejected() {
+    let mut client = TestClient {
+        version: Some(ClientVersion::new("JUNOWEN/1.1".into(), "1.1.0".into())),
+        ..Default::default()
+    };
+    create(&client, "room", "offer").await;
+
+    client.version = Some(ClientVersion::new("JUNOWEN/2.0".into(), "2.0.0".into()));
+    let PutReservedRoomResponse::Incompatible { body, .. } = put(&client, "room", "offer2").await
+    else {
+        panic!("unexpected response");
+    };
+    assert_eq!(body.opponent().version(), "1.1.0");
+    let GetReservedRoomResponse::Incompatible(body) = get(&client, "room").await else {
+        panic!("unexpected response");
+    };
+    assert_eq!(body.opponent().protocol(), "JUNOWEN/1.1");
+
+    client.version = None;
+    assert!(matches!(
+        get(&client, "room").await,
+        GetReservedRoomResponse::Ok(_)
+    ));
+}
+
+async fn put_with_passwords(
+    client: &TestClient,
+    room_name: &str,
+    offer: &str,
+    player_password: Option<&str>,
+    spectator_password: Option<&str>,
+) -> PutReservedRoomResponse {
+    let body = PutReservedRoomRequestBody::new(
+        sdp(offer),
+        false,
+        player_password.map(|x| x.to_owned()),
+        spectator_password.map(|x| x.to_owned()),
+    );
+    let res = client.put(&path(room_name), &body).await;
+    PutReservedRoomResponse::parse(res.status, res.retry_after, res.text()).unwrap()
+}
+
+#[tokio::test]
+async fn player_password_is_required_to_enter_and_join() {
+    let mut client = TestClient::default();
+    let res = put_with_passwords(&client, "room", "offer", Some("player"), None).await;
+    assert!(matches!(
+        res,
+        PutReservedRoomResponse::CreatedWithKey { .. }
+    ));
+    let stored = client
+        .db
+        .find_room("room".to_owned())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!stored
+        .player_password_hash()
+        .as_ref()
+        .unwrap()
+        .contains("player"));
+
+    let res = put(&client, "room", "offer2").await;
+    assert!(matches!(res, PutReservedRoomResponse::Forbidden { .. }));
+    let res = put_with_passwords(&client, "room", "offer2", Some("wrong"), None).await;
+    assert!(matches!(res, PutReservedRoomResponse::Forbidden { .. }));
+    let res = put_with_passwords(&client, "room", "offer2", Some("player"), None).await;
+    let PutReservedRoomResponse::Conflict { body, .. } = res else {
+        panic!("unexpected response: {:?}", res);
+    };
+    assert_eq!(body.into_offer().unwrap().into_inner(), "offer");
+
+    assert!(matches!(
+        join(&client, "room", "answer").await,
+        PostRoomJoinResponse::Forbidden
+    ));
+    client.room_password = Some("player");
+    assert!(matches!(
+        join(&client, "room", "answer").await,
+        PostRoomJoinResponse::Ok
+    ));
+}
+
+#[tokio::test]
+async fn spectator_password_is_separate_from_player_password() {
+    let mut client = TestClient::default();
+    let res = put_with_passwords(&client, "room", "offer", Some("player"), Some("spectator")).await;
+    let PutReservedRoomResponse::CreatedWithKey { body, .. } = res else {
+        panic!("unexpected response: {:?}", res);
+    };
+    let key = body.into_key();
+    client.room_password = Some("player");
+    join(&client, "room", "answer").await;
+    keep(&client, "room", &key, None).await;
+    keep(&client, "room", &key, Some("spectator offer")).await;
+
+    assert!(matches!(
+        get(&client, "room").await,
+        GetReservedRoomResponse::Forbidden
+    ));
+    assert!(matches!(
+        spectate(&client, "room", "spectator answer").await,
+        PostReservedRoomSpectateResponse::Forbidden
+    ));
+    client.room_password = Some("spectator");
+    let GetReservedRoomResponse::Ok(body) = get(&client, "room").await else {
+        panic!();
+    };
+    assert_eq!(
+        body.into_spectator_offer().unwrap().into_inner(),
+        "spectator offer"
+    );
+    assert!(matches!(
+        spectate(&client, "room", "spectator answer").await,
+        PostReservedRoomSpectateResponse::Ok
+    ));
+}
@@ -0,0 +1,264 @@
+use junowen_lib::signaling_server::{
+    custom::{PostSharedRoomKeepRequestBody, PostSharedRoomKeepResponse, PutSharedRoomResponse},
+    room::{
+        DeleteRoomRequestBody, DeleteRoomResponse, PostRoomJoinRequestBody, PostRoomJoinResponse,
+        PutRoomRequestBody,
+    },
+    version::ClientVersion,
+};
+use lambda_http::http::{Method, StatusCode};
+
+use crate::{
+    database::{SharedRoom, SharedRoomTables},
+    routes::room_utils::{now_sec, RETRY_AFTER_INTERVAL_SEC},
+};
+
+use super::{encode_room_name, sdp, TestClient, TestResponse};
+
+fn path(room_name: &str) -> String {
+    format!("/custom/{}", encode_room_name(room_name))
+}
+
+fn parse_put(res: &TestResponse) -> PutSharedRoomResponse {
+    PutSharedRoomResponse::parse(res.status, res.retry_after, res.text()).unwrap()
+}
+
+fn parse_keep(res: &TestResponse) -> PostSharedRoomKeepResponse {
+    PostSharedRoomKeepResponse::parse(res.status, res.retry_after, res.text.as_deref()).unwrap()
+}
+
+async fn create(client: &TestClient, room_name: &str, offer: &str) -> String {
+    let res = client
+        .put(
+            &path(room_name),
+            &PutRoomRequestBody::new(sdp(offer), false),
+        )
+        .await;
+    let PutSharedRoomResponse::CreatedWithKey { retry_after, body } = parse_put(&res) else {
+        panic!("unexpected response: {:?}", res.text);
+    };
+    assert_eq!(retry_after, RETRY_AFTER_INTERVAL_SEC);
+    body.into_key()
+}
+
+async fn join(client: &TestClient, room_name: &str, answer: &str) -> PostRoomJoinResponse {
+    let url = format!("{}/join", path(room_name));
+    let res = client
+        .post(&url, &PostRoomJoinRequestBody::new(sdp(answer)))
+        .await;
+    PostRoomJoinResponse::parse(res.status).unwrap()
+}
+
+async fn keep(client: &TestClient, room_name: &str, key: &str) -> PostSharedRoomKeepResponse {
+    let url = format!("{}/keep", path(room_name));
+    let res = client
+        .post(&url, &PostSharedRoomKeepRequestBody::new(key.to_owned()))
+        .await;
+    parse_keep(&res)
+}
+
+async fn delete(client: &TestClient, room_name: &str, key: &str) -> DeleteRoomResponse {
+    let res = client
+        .delete(
+            &path(room_name),
+            &DeleteRoomRequestBody::new(key.to_owned()),
+        )
+        .await;
+    DeleteRoomResponse::parse(res.status).unwrap()
+}
+
+#[tokio::test]
+async fn host_waits_and_receives_answer_by_keep() {
+    let client = TestClient::default();
+    let key = create(&client, "room", "offer").await;
+
+    let res = keep(&client, "room", &key).await;
+    let PostSharedRoomKeepResponse::NoContent { retry_after } = res else {
+        panic!("unexpected response: {:?}", res);
+    };
+    assert_eq!(retry_after, RETRY_AFTER_INTERVAL_SEC);
+
+    assert!(matches!(
+        join(&client, "room", "answer").await,
+        PostRoomJoinResponse::Ok
+    ));
+
+    let res = keep(&client, "room", &key).await;
+    let PostSharedRoomKeepResponse::Ok(body) = res else {
+        panic!("unexpected response: {:?}", res);
+    };
+    assert_eq!(body.into_answer().into_inner(), "answer");
+
+    // 応答を受け取ったルームは閉じられる
+    let res = keep(&client, "room", &key).await;
+    assert!(matches!(res, PostSharedRoomKeepResponse::BadRequest));
+    create(&client, "room", "offer2").await;
+}
+
+#[tokio::test]
+async fn second_host_receives_first_offer() {
+    let client = TestClient::default();
+    create(&client, "room", "offer1").await;
+
+    let res = client
+        .put(
+            &path("room"),
+            &PutRoomRequestBody::new(sdp("offer2"), false),
+        )
+        .await;
+    let PutSharedRoomResponse::Conflict { body, .. } = parse_put(&res) else {
+        panic!("unexpected response: {:?}", res.text);
+    };
+    assert_eq!(body.into_offer().into_inner(), "offer1");
+}
+
+#[tokio::test]
+async fn host_receives_answer_posted_before_creation() {
+    let client = TestClient::default();
+    assert!(matches!(
+        join(&client, "room", "answer").await,
+        PostRoomJoinResponse::Ok
+    ));
+
+    let res = client
+        .put(&path("room"), &PutRoomRequestBody::new(sdp("offer"), false))
+        .await;
+    let PutSharedRoomResponse::CreatedWithAnswer { body, .. } = parse_put(&res) else {
+        panic!("unexpected response: {:?}", res.text);
+    };
+    assert_eq!(body.into_answer().into_inner(), "answer");
+}
+
+#[tokio::test]
+async fn second_guest_conflicts() {
+    let client = TestClient::default();
+    create(&client, "room", "offer").await;
+    assert!(matches!(
+        join(&client, "room", "answer1").await,
+        PostRoomJoinResponse::Ok
+    ));
+    assert!(matches!(
+        join(&client, "room", "answer2").await,
+        PostRoomJoinResponse::Conflict
+    ));
+}
+
+#[tokio::test]
+async fn keep_with_wrong_key_is_bad_request() {
+    let client = TestClient::default();
+    create(&client, "room", "offer").await;
+
+    let res = keep(&client, "room", "not-a-uuid").await;
+    assert!(matches!(res, PostSharedRoomKeepResponse::BadRequest));
+    let res = keep(&client, "room", "00000000-0000-0000-0000-000000000000").await;
+    assert!(matches!(res, PostSharedRoomKeepResponse::BadRequest));
+}
+
+#[tokio::test]
+async fn only_owner_can_delete_room() {
+    let client = TestClient::default();
+    let key = create(&client, "room", "offer").await;
+
+    let res = delete(&client, "room", "00000000-0000-0000-0000-000000000000").await;
+    assert!(matches!(res, DeleteRoomResponse::BadRequest));
+    let res = delete(&client, "room", &key).await;
+    assert!(matches!(res, DeleteRoomResponse::NoContent));
+
+    create(&client, "room", "offer2").await;
+}
+
+#[tokio::test]
+async fn expired_room_is_replaced() {
+    let client = TestClient::default();
+    let room = SharedRoom::new(
+        "room".to_owned(),
+        "00000000-0000-0000-0000-000000000000".to_owned(),
+        sdp("stale offer"),
+        None,
+        false,
+        None,
+        now_sec() - 1,
+    );
+    client.db.put_room(room).await.unwrap();
+
+    let key = create(&client, "room", "offer").await;
+    assert_ne!(key, "00000000-0000-0000-0000-000000000000");
+}
+
+#[tokio::test]
+async fn room_name_with_spaces_and_non_ascii() {
+    let client = TestClient::default();
+    let room_name = "ゆっくり していってね";
+    let key = create(&client, room_name, "offer").await;
+    assert!(matches!(
+        join(&client, room_name, "answer").await,
+        PostRoomJoinResponse::Ok
+    ));
+    let res = keep(&client, room_name, &key).await;
+    assert!(matches!(res, PostSharedRoomKeepResponse::Ok(_)));
+}
+
+#[tokio::test]
+async fn invalid_requests() {
+    let client = TestClient::default();
+    let res = client.get(&path("room")).await;
+    assert_eq!(res.status, StatusCode::METHOD_NOT_ALLOWED);
+    let res = client
+        .request(Method::PUT, &path("room"), None::<&()>)
+        .await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+    let res = client.put(&path("room"), &"not an object").await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn incompatible_host_is_rejected_with_opponent_version() {
+    let mut client = TestClient {
+        version: Some(ClientVersion::new("JUNOWEN/1.1".into(), "1.1.0".into())),
+        ..Default::default()
+    };
+    create(&client, "room", "offer").await;
+
+    client.version = Some(ClientVersion::new("JUNOWEN/2.0".into(), "2.0.0".into()));
+    let res = client
+        .put(
+            &path("room"),
+            &PutRoomRequestBody::new(sdp("offer2"), false),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::PRECONDITION_FAILED);
+    let PutSharedRoomResponse::Incompatible { body, .. } = parse_put(&res) else {
+        panic!("unexpected response: {:?}", res.text);
+    };
+    assert_eq!(body.opponent().version(), "1.1.0");
+    assert_eq!(
+        body.into_error().to_string(),
+        "your opponent uses version 1.1.0 (JUNOWEN/1.1)"
+    );
+}
+
+#[tokio::test]
+async fn compatible_or_unversioned_host_receives_offer() {
+    let mut client = TestClient {
+        version: Some(ClientVersion::new("JUNOWEN/1.1".into(), "1.1.0".into())),
+        ..Default::default()
+    };
+    create(&client, "room", "offer").await;
+
+    for version in [
+        Some(ClientVersion::new("JUNOWEN/1.1".into(), "1.1.1".into())),
+        None,
+    ] {
+        client.version = version;
+        let res = client
+            .put(
+                &path("room"),
+                &PutRoomRequestBody::new(sdp("offer2"), false),
+            )
+            .await;
+        assert!(matches!(
+            parse_put(&res),
+            PutSharedRoomResponse::Conflict { .. }
+        ));
+    }
+}
@@ -9,7 +9,7 @@ use tracing_subscriber::{
     fmt::{
         self,
         format::{Compact, DefaultFields, Format},
-        time::{FormatTime, LocalTime, SystemTime},
+        time::{FormatTime, LocalTime, SystemTime, UtcTime},
     },
     prelude::__tracing_subscriber_SubscriberExt,
     EnvFilter, Layer, Registry,
@@ -54,3 +54,7 @@ pub fn init_local_tracing() {
 pub fn init_server_tracing() {
     init_tracing(|layer| layer.without_time().with_ansi(false));
 }
+
+pub fn init_daemon_tracing() {
+    init_tracing(|layer| layer.with_timer(UtcTime::rfc_3339()));
+}
@@ -79,11 +79,32 @@ pub async fn move_old_log_to_new_path(old_log_path: &str, module_dir: &str, log_
 #[serde(rename_all = "kebab-case")]
 pub enum Features {
     ShowSettings,
+    /// 対戦中に RTT などの通信状況を表示する
+    ShowNetworkStats,
+    /// オンライン対戦をリプレイファイルに記録する
+    RecordReplays,
+    /// 対戦者のチャットを観戦者にも送る
+    ShareChatWithSpectators,
 }
 
 const FEATURES: &str = "features";
 const SHARED_ROOM_NAME: &str = "shared_room_name";
 const RESERVED_ROOM_NAME: &str = "reserved_room_name";
+const SPECTATOR_DELAY: &str = "spectator_delay";
+
+const FRAMES_PER_SECOND: f64 = 60.0;
+
+/// 数値なら秒数、"120f" のような文字列ならフレーム数として読む
+fn parse_spectator_delay(item: &Item) -> Option<u32> {
+    if let Some(frames) = item.as_str().and_then(|x| x.strip_suffix('f')) {
+        return frames.trim().parse().ok();
+    }
+    let secs = item
+        .as_float()
+        .or_else(|| item.as_integer().map(|x| x as f64))
+        .or_else(|| item.as_str()?.strip_suffix('s')?.trim().parse().ok())?;
+    (secs >= 0.0).then(|| (secs * FRAMES_PER_SECOND).round() as u32)
+}
 
 #[derive(new)]
 pub struct SettingsRepo {
@@ -134,6 +155,15 @@ impl SettingsRepo {
             .unwrap_or_default()
     }
 
+    /// 観戦者に送る内容を遅らせるフレーム数
+    pub async fn spectator_delay_frames(&self) -> u32 {
+        self.load()
+            .await
+            .get(SPECTATOR_DELAY)
+            .and_then(parse_spectator_delay)
+            .unwrap_or_default()
+    }
+
     pub async fn reserved_room_name(&self, th19: &Th19) -> String {
         match self.read_string(RESERVED_ROOM_NAME).await {
             Some(value) => value,
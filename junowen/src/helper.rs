@@ -9,3 +9,9 @@ pub fn pushed_f1(input_devices: &InputDevices) -> bool {
     let raw_keys = input_devices.keyboard_input().raw_keys();
     raw_keys[0x70] & 0x80 != 0
 }
+
+/// 押されているチャットのキー (F5-F8) の番号
+pub fn pushed_chat_key(input_devices: &InputDevices) -> Option<usize> {
+    let raw_keys = input_devices.keyboard_input().raw_keys();
+    (0..4).find(|i| raw_keys[0x74 + i] & 0x80 != 0)
+}
@@ -1,7 +1,7 @@
 mod menu;
-mod menu_controller;
+pub mod menu_controller;
 mod menu_item;
-mod text_input;
+pub mod text_input;
 
 use std::ffi::c_void;
 
@@ -40,6 +40,8 @@ pub enum LobbyScene {
     Root,
     SharedRoom,
     ReservedRoom,
+    PublicRooms,
+    QuickMatch,
     PureP2pHost,
     PureP2pGuest,
     PureP2pSpectator,
@@ -19,7 +19,9 @@ use super::{
     common_menu::{CommonMenu, LobbyScene, Menu, MenuItem, OnMenuInputResult},
     pure_p2p_guest::PureP2pGuest,
     pure_p2p_offerer::{pure_p2p_host, pure_p2p_spectator, PureP2pOfferer},
-    room::{reserved::ReservedRoom, shared::SharedRoom},
+    room::{
+        public::PublicRooms, quick_match::QuickMatch, reserved::ReservedRoom, shared::SharedRoom,
+    },
 };
 
 pub struct Root {
@@ -34,6 +36,8 @@ impl Root {
             vec![
                 MenuItem::sub_scene("Shared Room", LobbyScene::SharedRoom),
                 MenuItem::sub_scene("Reserved Room", LobbyScene::ReservedRoom),
+                MenuItem::sub_scene("Public Rooms", LobbyScene::PublicRooms),
+                MenuItem::sub_scene("Quick Match", LobbyScene::QuickMatch),
                 MenuItem::sub_menu(
                     "Pure P2P",
                     None,
@@ -92,6 +96,8 @@ pub struct Lobby {
     root: Root,
     shared_room: SharedRoom,
     reserved_room: ReservedRoom,
+    public_rooms: PublicRooms,
+    quick_match: QuickMatch,
     pure_p2p_host: Option<PureP2pOfferer<BattleSession>>,
     pure_p2p_guest: Option<PureP2pGuest>,
     pure_p2p_spectator: Option<PureP2pOfferer<SpectatorSession>>,
@@ -110,6 +116,8 @@ impl Lobby {
             waiting_for_match: None,
             shared_room: SharedRoom::new(),
             reserved_room: ReservedRoom::new(),
+            public_rooms: PublicRooms::new(),
+            quick_match: QuickMatch::new(),
             pure_p2p_host: None,
             pure_p2p_guest: None,
             pure_p2p_spectator: None,
@@ -157,6 +165,30 @@ impl Lobby {
                 th19,
                 &mut self.waiting_for_match,
             ),
+            LobbyScene::PublicRooms => self.public_rooms.on_input_menu(
+                current_input,
+                self.prev_input,
+                th19,
+                &mut self.waiting_for_match,
+            ),
+            LobbyScene::QuickMatch => {
+                let mut waiting = match self.waiting_for_match.take() {
+                    Some(WaitingForMatch::Opponent(WaitingForOpponent::SharedRoom(waiting))) => {
+                        Some(waiting)
+                    }
+                    _ => None,
+                };
+                let ret = self.quick_match.on_input_menu(
+                    current_input,
+                    self.prev_input,
+                    th19,
+                    &mut waiting,
+                );
+                self.waiting_for_match = waiting
+                    .map(WaitingForOpponent::SharedRoom)
+                    .map(WaitingForMatch::Opponent);
+                ret
+            }
             LobbyScene::PureP2pHost => {
                 if self.pure_p2p_host.is_none() {
                     self.waiting_for_match = None;
@@ -255,6 +287,21 @@ impl Lobby {
                         .on_render_texts(none, th19, text_renderer);
                 }
             },
+            LobbyScene::PublicRooms => self.public_rooms.on_render_texts(
+                self.waiting_for_match.as_ref(),
+                th19,
+                text_renderer,
+            ),
+            LobbyScene::QuickMatch => {
+                let waiting = self.waiting_for_match.as_ref().and_then(|x| match x {
+                    WaitingForMatch::Opponent(WaitingForOpponent::SharedRoom(waiting)) => {
+                        Some(waiting)
+                    }
+                    _ => None,
+                });
+                self.quick_match
+                    .on_render_texts(waiting, th19, text_renderer);
+            }
             LobbyScene::PureP2pHost => self
                 .pure_p2p_host
                 .as_ref()
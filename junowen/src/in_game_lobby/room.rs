@@ -1,3 +1,5 @@
+pub mod public;
+pub mod quick_match;
 pub mod reserved;
 pub mod shared;
 
@@ -114,6 +116,15 @@ fn render_progress(th19: &Th19, text_renderer: *const c_void, progress: f64) {
     render_progress_item(th19, text_renderer, 0xff, &base_text);
 }
 
+fn render_waiting<T>(waiting: &WaitingInRoom<T>, th19: &Th19, text_renderer: *const c_void) {
+    let elapsed = waiting.elapsed();
+    render_progress(th19, text_renderer, elapsed.as_secs_f64() / 4.0);
+    for (i, error) in waiting.errors().iter().rev().enumerate() {
+        let error_msg = format!("Failed: {}", error);
+        render_text_line(th19, text_renderer, 13 + i as u32, error_msg.as_bytes());
+    }
+}
+
 pub fn on_render_texts<T>(
     menu: &CommonMenu,
     waiting: Option<&WaitingInRoom<T>>,
@@ -124,12 +135,7 @@ pub fn on_render_texts<T>(
     menu.on_render_texts(th19, text_renderer);
 
     if let Some(waiting) = waiting {
-        let elapsed = waiting.elapsed();
-        render_progress(th19, text_renderer, elapsed.as_secs_f64() / 4.0);
-        for (i, error) in waiting.errors().iter().rev().enumerate() {
-            let error_msg = format!("Failed: {}", error);
-            render_text_line(th19, text_renderer, 13 + i as u32, error_msg.as_bytes());
-        }
+        render_waiting(waiting, th19, text_renderer);
     } else if let Some(room_name) = room_name {
         render_label_value(th19, text_renderer, 240 - 56, 1, "Room name", room_name);
     }
@@ -0,0 +1,304 @@
+use std::{
+    ffi::c_void,
+    time::{Duration, Instant},
+};
+
+use junowen_lib::{
+    signaling_server::{
+        public_room::{PublicRoom, PublicRoomKind},
+        version::ClientVersion,
+    },
+    structs::input_devices::InputValue,
+    Th19,
+};
+
+use crate::signaling::waiting_for_match::{
+    FetchingPublicRooms, WaitingForMatch, WaitingForOpponent, WaitingForOpponentInReservedRoom,
+    WaitingForOpponentInSharedRoom, WaitingForSpectatorHost, WaitingForSpectatorHostInReservedRoom,
+};
+
+use super::{
+    super::{
+        common_menu::{
+            menu_controller::{MenuController, MenuControllerInputResult},
+            text_input::{OnMenuInputResult, TextInput},
+            LobbyScene,
+        },
+        helper::{render_label_value, render_menu_item, render_text_line, render_title},
+    },
+    render_waiting,
+};
+
+const VISIBLE_ROOMS: usize = 8;
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+fn is_compatible(room: &PublicRoom) -> bool {
+    room.client_version()
+        .as_ref()
+        .map(|version| version.is_compatible_with(&ClientVersion::current()))
+        .unwrap_or(true)
+}
+
+fn room_label(room: &PublicRoom) -> String {
+    let kind = match room.kind() {
+        PublicRoomKind::Shared => "Shared",
+        PublicRoomKind::Reserved if room.opponent_slot_free() => "Reserved",
+        PublicRoomKind::Reserved => "Spectate",
+    };
+    let age_sec = room.age_sec();
+    let age = if age_sec < 60 {
+        format!("{}s", age_sec)
+    } else {
+        format!("{}m", age_sec / 60)
+    };
+    let lock = if room.password_required() { " *" } else { "" };
+    format!("[{}] {}{} ({})", kind, room.name(), lock, age)
+}
+
+fn join(room: &PublicRoom, password: Option<String>) -> WaitingForMatch {
+    let room_name = room.name().clone();
+    match room.kind() {
+        PublicRoomKind::Shared => WaitingForMatch::Opponent(WaitingForOpponent::SharedRoom(
+            WaitingForOpponentInSharedRoom::new(room_name, false),
+        )),
+        PublicRoomKind::Reserved if room.opponent_slot_free() => {
+            WaitingForMatch::Opponent(WaitingForOpponent::ReservedRoom(
+                WaitingForOpponentInReservedRoom::new(room_name, false, password, None),
+            ))
+        }
+        PublicRoomKind::Reserved => {
+            WaitingForMatch::SpectatorHost(WaitingForSpectatorHost::ReservedRoom(
+                WaitingForSpectatorHostInReservedRoom::new(room_name, password),
+            ))
+        }
+    }
+}
+
+pub struct PublicRooms {
+    controller: MenuController,
+    rooms: Vec<PublicRoom>,
+    cursor: usize,
+    fetching: Option<FetchingPublicRooms>,
+    fetched_at: Option<Instant>,
+    error: Option<String>,
+    joined_room_name: Option<String>,
+    /// パスワードが要るルームに入る前に入力してもらう
+    password_input: Option<(PublicRoom, TextInput)>,
+}
+
+impl PublicRooms {
+    pub fn new() -> Self {
+        Self {
+            controller: MenuController::default(),
+            rooms: vec![],
+            cursor: 0,
+            fetching: None,
+            fetched_at: None,
+            error: None,
+            joined_room_name: None,
+            password_input: None,
+        }
+    }
+
+    fn update_rooms(&mut self) {
+        if let Some(fetching) = &mut self.fetching {
+            let Some(result) = fetching.try_recv() else {
+                return;
+            };
+            self.fetching = None;
+            self.fetched_at = Some(Instant::now());
+            match result {
+                Ok(rooms) => {
+                    self.rooms = rooms;
+                    self.cursor = self.cursor.min(self.rooms.len().saturating_sub(1));
+                    self.error = None;
+                }
+                Err(err) => self.error = Some(err.to_string()),
+            }
+            return;
+        }
+        if self
+            .fetched_at
+            .map(|fetched_at| fetched_at.elapsed() >= REFRESH_INTERVAL)
+            .unwrap_or(true)
+        {
+            self.fetching = Some(FetchingPublicRooms::start());
+        }
+    }
+
+    fn enter(
+        &mut self,
+        room: &PublicRoom,
+        password: Option<String>,
+        waiting: &mut Option<WaitingForMatch>,
+    ) {
+        *waiting = Some(join(room, password));
+        self.joined_room_name = Some(room.name().clone());
+    }
+
+    fn leave(&mut self, waiting: &mut Option<WaitingForMatch>) {
+        self.joined_room_name = None;
+        *waiting = None;
+        self.fetched_at = None;
+    }
+
+    pub fn on_input_menu(
+        &mut self,
+        current_input: InputValue,
+        prev_input: InputValue,
+        th19: &Th19,
+        waiting: &mut Option<WaitingForMatch>,
+    ) -> Option<LobbyScene> {
+        if let Some((room, text_input)) = &mut self.password_input {
+            match text_input.on_input_menu(th19) {
+                OnMenuInputResult::None => {}
+                OnMenuInputResult::Cancel => {
+                    th19.play_sound(th19.sound_manager(), 0x09, 0);
+                    self.password_input = None;
+                }
+                OnMenuInputResult::Decide(_, password) => {
+                    th19.play_sound(th19.sound_manager(), 0x07, 0);
+                    let room = room.clone();
+                    self.password_input = None;
+                    self.enter(&room, Some(password), waiting);
+                }
+            }
+            return None;
+        }
+        if self.joined_room_name.is_some() {
+            match waiting {
+                Some(WaitingForMatch::Opponent(WaitingForOpponent::SharedRoom(waiting))) => {
+                    waiting.recv();
+                }
+                Some(WaitingForMatch::Opponent(WaitingForOpponent::ReservedRoom(waiting))) => {
+                    waiting.recv();
+                }
+                Some(WaitingForMatch::SpectatorHost(WaitingForSpectatorHost::ReservedRoom(
+                    waiting,
+                ))) => {
+                    waiting.recv();
+                }
+                _ => {
+                    self.joined_room_name = None;
+                }
+            }
+        }
+        if self.joined_room_name.is_none() {
+            self.update_rooms();
+        }
+
+        match self.controller.input(current_input, prev_input, true, true) {
+            MenuControllerInputResult::None => None,
+            MenuControllerInputResult::Cancel => {
+                th19.play_sound(th19.sound_manager(), 0x09, 0);
+                if self.joined_room_name.is_some() {
+                    self.leave(waiting);
+                    return None;
+                }
+                self.fetched_at = None;
+                Some(LobbyScene::Root)
+            }
+            MenuControllerInputResult::Decide => {
+                if self.joined_room_name.is_some() {
+                    return None;
+                }
+                let room = self.rooms.get(self.cursor)?;
+                if !is_compatible(room) {
+                    return None;
+                }
+                th19.play_sound(th19.sound_manager(), 0x07, 0);
+                if room.password_required() {
+                    self.password_input = Some((room.clone(), TextInput::new(0, "Password")));
+                    return None;
+                }
+                let room = room.clone();
+                self.enter(&room, None, waiting);
+                None
+            }
+            MenuControllerInputResult::Up => {
+                if self.joined_room_name.is_some() || self.cursor == 0 {
+                    return None;
+                }
+                self.cursor -= 1;
+                th19.play_sound(th19.sound_manager(), 0x0a, 0);
+                None
+            }
+            MenuControllerInputResult::Down => {
+                if self.joined_room_name.is_some() || self.cursor + 1 >= self.rooms.len() {
+                    return None;
+                }
+                self.cursor += 1;
+                th19.play_sound(th19.sound_manager(), 0x0a, 0);
+                None
+            }
+        }
+    }
+
+    pub fn on_render_texts(
+        &self,
+        waiting: Option<&WaitingForMatch>,
+        th19: &Th19,
+        text_renderer: *const c_void,
+    ) {
+        if let Some((_, text_input)) = &self.password_input {
+            render_title(th19, text_renderer, b"Room Password");
+            text_input.on_render_texts(th19, text_renderer);
+            return;
+        }
+        render_title(th19, text_renderer, b"Public Rooms");
+
+        if let Some(room_name) = &self.joined_room_name {
+            render_label_value(th19, text_renderer, 240 - 56, 1, "Room name", room_name);
+            match waiting {
+                Some(WaitingForMatch::Opponent(WaitingForOpponent::SharedRoom(waiting))) => {
+                    render_waiting(waiting, th19, text_renderer);
+                }
+                Some(WaitingForMatch::Opponent(WaitingForOpponent::ReservedRoom(waiting))) => {
+                    render_waiting(waiting, th19, text_renderer);
+                }
+                Some(WaitingForMatch::SpectatorHost(WaitingForSpectatorHost::ReservedRoom(
+                    waiting,
+                ))) => {
+                    render_waiting(waiting, th19, text_renderer);
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        if let Some(error) = &self.error {
+            let error_msg = format!("Failed: {}", error);
+            render_text_line(th19, text_renderer, 13, error_msg.as_bytes());
+        }
+        if self.rooms.is_empty() {
+            let text: &[u8] = if self.fetched_at.is_none() {
+                b"Loading..."
+            } else {
+                b"No public rooms"
+            };
+            render_menu_item(th19, text_renderer, text, 240 + 56, false, false);
+            return;
+        }
+        let first = self
+            .cursor
+            .saturating_sub(VISIBLE_ROOMS - 1)
+            .min(self.rooms.len().saturating_sub(VISIBLE_ROOMS));
+        for (i, room) in self
+            .rooms
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(VISIBLE_ROOMS)
+        {
+            let height = 240 + 56 * (i - first) as u32;
+            render_menu_item(
+                th19,
+                text_renderer,
+                room_label(room).as_bytes(),
+                height,
+                is_compatible(room),
+                i == self.cursor,
+            );
+        }
+    }
+}
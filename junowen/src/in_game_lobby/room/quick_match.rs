@@ -0,0 +1,148 @@
+use std::ffi::c_void;
+
+use junowen_lib::{
+    signaling_server::quick_match::QuickMatchFilter, structs::input_devices::InputValue, Th19,
+};
+
+use crate::signaling::waiting_for_match::WaitingForOpponentInSharedRoom;
+
+use super::{
+    super::common_menu::{CommonMenu, LobbyScene, Menu, MenuItem, OnMenuInputResult},
+    on_render_texts,
+};
+
+const REGIONS: [(&str, Option<&str>); 4] = [
+    ("Region: Any", None),
+    ("Region: Asia", Some("asia")),
+    ("Region: America", Some("america")),
+    ("Region: Europe", Some("europe")),
+];
+
+fn make_menu() -> CommonMenu {
+    let items = vec![
+        MenuItem::plain("Start Matching", 0, true),
+        MenuItem::plain("Rules: Any", 1, true),
+        MenuItem::plain(REGIONS[0].0, 2, true),
+    ];
+    CommonMenu::new(false, 240 + 56, Menu::new("Quick Match", None, items, 0))
+}
+
+pub struct QuickMatch {
+    menu: CommonMenu,
+    enter: bool,
+    /// 対戦相手と試合形式 (ラウンド数と時間制限) を揃える
+    same_rules: bool,
+    region: usize,
+}
+
+impl QuickMatch {
+    pub fn new() -> Self {
+        Self {
+            menu: make_menu(),
+            enter: false,
+            same_rules: false,
+            region: 0,
+        }
+    }
+
+    fn filter(&self, th19: &Th19) -> QuickMatchFilter {
+        let game_settings = self
+            .same_rules
+            .then(|| th19.game_settings_in_menu().ok())
+            .flatten();
+        QuickMatchFilter::new(
+            game_settings.as_ref().map(|x| x.round() as u8),
+            game_settings.as_ref().map(|x| x.time_limit() as u8),
+            REGIONS[self.region].1.map(|x| x.to_owned()),
+        )
+    }
+
+    fn change_menu_to_enter(&mut self) {
+        self.enter = false;
+        let items = self.menu.menu_mut().items_mut();
+        items[0].set_label("Start Matching");
+        items[1].set_enabled(true);
+        items[2].set_enabled(true);
+    }
+    fn change_menu_to_leave(&mut self) {
+        self.enter = true;
+        let items = self.menu.menu_mut().items_mut();
+        items[0].set_label("Stop Matching");
+        items[1].set_enabled(false);
+        items[2].set_enabled(false);
+    }
+
+    fn toggle_same_rules(&mut self) {
+        self.same_rules = !self.same_rules;
+        let item = &mut self.menu.menu_mut().items_mut()[1];
+        item.set_label(if self.same_rules {
+            "Rules: Same as Current"
+        } else {
+            "Rules: Any"
+        });
+    }
+
+    fn next_region(&mut self) {
+        self.region = (self.region + 1) % REGIONS.len();
+        let item = &mut self.menu.menu_mut().items_mut()[2];
+        item.set_label(REGIONS[self.region].0);
+    }
+
+    pub fn on_input_menu(
+        &mut self,
+        current_input: InputValue,
+        prev_input: InputValue,
+        th19: &Th19,
+        waiting: &mut Option<WaitingForOpponentInSharedRoom>,
+    ) -> Option<LobbyScene> {
+        if waiting.is_some() != self.enter {
+            if waiting.is_some() {
+                self.change_menu_to_leave();
+            } else {
+                self.change_menu_to_enter();
+            }
+        }
+
+        if let Some(waiting) = waiting {
+            waiting.recv();
+        }
+        match self.menu.on_input_menu(current_input, prev_input, th19) {
+            OnMenuInputResult::None => None,
+            OnMenuInputResult::Cancel => Some(LobbyScene::Root),
+            OnMenuInputResult::SubScene(_) => unreachable!(),
+            OnMenuInputResult::Action(action) => match action.id() {
+                0 => {
+                    if waiting.is_none() {
+                        *waiting = Some(WaitingForOpponentInSharedRoom::new_quick_match(
+                            "Quick Match".to_owned(),
+                            self.filter(th19),
+                        ));
+                        self.change_menu_to_leave();
+                    } else {
+                        *waiting = None;
+                        self.change_menu_to_enter();
+                    }
+                    None
+                }
+                1 => {
+                    self.toggle_same_rules();
+                    None
+                }
+                2 => {
+                    self.next_region();
+                    None
+                }
+                _ => unreachable!(),
+            },
+        }
+    }
+
+    pub fn on_render_texts(
+        &self,
+        waiting: Option<&WaitingForOpponentInSharedRoom>,
+        th19: &Th19,
+        text_renderer: *const c_void,
+    ) {
+        on_render_texts(&self.menu, waiting, None, th19, text_renderer);
+    }
+}
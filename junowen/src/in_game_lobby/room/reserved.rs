@@ -42,6 +42,9 @@ fn make_menu() -> CommonMenu {
                 ),
             ),
             MenuItem::text_input("Change Room Name", 11, 12, "Room name"),
+            MenuItem::plain("Visibility: Private", 4, true),
+            MenuItem::text_input("Player Password: None", 13, 14, "Player password"),
+            MenuItem::text_input("Spectator Password: None", 15, 16, "Spectator password"),
         ],
         0,
     );
@@ -52,6 +55,10 @@ pub struct ReservedRoom {
     menu: CommonMenu,
     enter: bool,
     room_name: Option<String>,
+    public: bool,
+    /// 空ならパスワード無し。保存はしない
+    player_password: Option<String>,
+    spectator_password: Option<String>,
 }
 
 impl ReservedRoom {
@@ -60,6 +67,9 @@ impl ReservedRoom {
             menu: make_menu(),
             enter: false,
             room_name: None,
+            public: false,
+            player_password: None,
+            spectator_password: None,
         }
     }
 
@@ -67,6 +77,15 @@ impl ReservedRoom {
         self.room_name.as_ref().unwrap()
     }
 
+    fn set_text_input_value(&mut self, value: Option<String>) {
+        let MenuItem::TextInput(text_input_item) = self.menu.menu_mut().selected_item_mut() else {
+            unreachable!()
+        };
+        text_input_item
+            .text_input_mut()
+            .set_value(value.unwrap_or_default());
+    }
+
     pub fn on_input_menu(
         &mut self,
         settings_repo: &SettingsRepo,
@@ -108,7 +127,12 @@ impl ReservedRoom {
                 0 => {
                     self.enter = true;
                     *waiting = Some(WaitingForMatch::Opponent(WaitingForOpponent::ReservedRoom(
-                        WaitingForOpponentInReservedRoom::new(self.room_name().to_owned()),
+                        WaitingForOpponentInReservedRoom::new(
+                            self.room_name().to_owned(),
+                            self.public,
+                            self.player_password.clone(),
+                            self.spectator_password.clone(),
+                        ),
                     )));
                     None
                 }
@@ -123,19 +147,27 @@ impl ReservedRoom {
                     self.enter = true;
                     *waiting = Some(WaitingForMatch::SpectatorHost(
                         WaitingForSpectatorHost::ReservedRoom(
-                            WaitingForSpectatorHostInReservedRoom::new(self.room_name().to_owned()),
+                            WaitingForSpectatorHostInReservedRoom::new(
+                                self.room_name().to_owned(),
+                                self.spectator_password.clone(),
+                            ),
                         ),
                     ));
                     None
                 }
+                4 => {
+                    self.public = !self.public;
+                    let item = self.menu.menu_mut().selected_item_mut();
+                    item.set_label(if self.public {
+                        "Visibility: Public"
+                    } else {
+                        "Visibility: Private"
+                    });
+                    None
+                }
                 11 => {
                     let room_name = self.room_name().to_owned();
-                    let MenuItem::TextInput(text_input_item) =
-                        self.menu.menu_mut().selected_item_mut()
-                    else {
-                        unreachable!()
-                    };
-                    text_input_item.text_input_mut().set_value(room_name);
+                    self.set_text_input_value(Some(room_name));
                     None
                 }
                 12 => {
@@ -144,6 +176,36 @@ impl ReservedRoom {
                     TOKIO_RUNTIME.block_on(settings_repo.set_reserved_room_name(new_room_name));
                     None
                 }
+                13 => {
+                    self.set_text_input_value(self.player_password.clone());
+                    None
+                }
+                14 => {
+                    let password = action.value().filter(|x| !x.is_empty());
+                    self.player_password = password.map(|x| x.to_owned());
+                    let item = self.menu.menu_mut().selected_item_mut();
+                    item.set_label(if password.is_some() {
+                        "Player Password: Set"
+                    } else {
+                        "Player Password: None"
+                    });
+                    None
+                }
+                15 => {
+                    self.set_text_input_value(self.spectator_password.clone());
+                    None
+                }
+                16 => {
+                    let password = action.value().filter(|x| !x.is_empty());
+                    self.spectator_password = password.map(|x| x.to_owned());
+                    let item = self.menu.menu_mut().selected_item_mut();
+                    item.set_label(if password.is_some() {
+                        "Spectator Password: Set"
+                    } else {
+                        "Spectator Password: None"
+                    });
+                    None
+                }
                 _ => unreachable!(),
             },
         }
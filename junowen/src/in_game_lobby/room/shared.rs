@@ -15,6 +15,7 @@ fn make_menu() -> CommonMenu {
     let items = vec![
         MenuItem::plain("Enter the Room", 0, true),
         MenuItem::text_input("Change Room Name", 11, 12, "Room name"),
+        MenuItem::plain("Visibility: Private", 2, true),
     ];
     CommonMenu::new(false, 240 + 56, Menu::new("Shared Room", None, items, 0))
 }
@@ -23,6 +24,7 @@ pub struct SharedRoom {
     menu: CommonMenu,
     enter: bool,
     room_name: Option<String>,
+    public: bool,
 }
 
 impl SharedRoom {
@@ -31,6 +33,7 @@ impl SharedRoom {
             menu: make_menu(),
             enter: false,
             room_name: None,
+            public: false,
         }
     }
 
@@ -44,6 +47,8 @@ impl SharedRoom {
         item.set_label("Enter the Room");
         let item = &mut self.menu.menu_mut().items_mut()[1];
         item.set_enabled(true);
+        let item = &mut self.menu.menu_mut().items_mut()[2];
+        item.set_enabled(true);
     }
     fn change_menu_to_leave(&mut self) {
         self.enter = true;
@@ -51,6 +56,18 @@ impl SharedRoom {
         item.set_label("Leave the Room");
         let item = &mut self.menu.menu_mut().items_mut()[1];
         item.set_enabled(false);
+        let item = &mut self.menu.menu_mut().items_mut()[2];
+        item.set_enabled(false);
+    }
+
+    fn toggle_public(&mut self) {
+        self.public = !self.public;
+        let item = &mut self.menu.menu_mut().items_mut()[2];
+        item.set_label(if self.public {
+            "Visibility: Public"
+        } else {
+            "Visibility: Private"
+        });
     }
 
     pub fn on_input_menu(
@@ -83,7 +100,8 @@ impl SharedRoom {
                 0 => {
                     if waiting.is_none() {
                         let room_name = self.room_name().to_owned();
-                        *waiting = Some(WaitingForOpponentInSharedRoom::new(room_name));
+                        *waiting =
+                            Some(WaitingForOpponentInSharedRoom::new(room_name, self.public));
                         self.change_menu_to_leave();
                     } else {
                         *waiting = None;
@@ -91,6 +109,10 @@ impl SharedRoom {
                     }
                     None
                 }
+                2 => {
+                    self.toggle_public();
+                    None
+                }
                 11 => {
                     let room_name = self.room_name().to_owned();
                     let MenuItem::TextInput(text_input_item) =
@@ -160,6 +160,7 @@ async fn init(dll_stem: &str, old_log_dir_path: Option<&str>) {
     let (old_fn_from_13f9d0_0446, apply_hook_13f9d0_0446) =
         th19.hook_13f9d0_0446(on_loaded_game_settings);
 
+    let replay_dir = format!("{}/replays", module_dir);
     unsafe {
         PROPS = Some(Props {
             old_on_input_players,
@@ -173,7 +174,7 @@ async fn init(dll_stem: &str, old_log_dir_path: Option<&str>) {
             old_fn_from_13f9d0_0345,
             old_fn_from_13f9d0_0446,
         });
-        STATE = Some(State::new(SettingsRepo::new(ini_file_path), th19).await);
+        STATE = Some(State::new(SettingsRepo::new(ini_file_path), th19, replay_dir).await);
     }
     let th19 = &mut state_mut().th19_mut();
     apply_hook_on_input_players(th19);
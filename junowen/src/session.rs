@@ -1,5 +1,10 @@
 pub mod battle;
+mod chat;
 mod delayed_inputs;
+mod desync_detector;
+mod negotiation;
+mod network_stats;
+mod session_error;
 mod session_message;
 pub mod spectator;
 pub mod spectator_host;
@@ -14,12 +19,17 @@ use serde::Serialize;
 use tokio::spawn;
 use tracing::debug;
 
-pub use session_message::{MatchInitial, RoundInitial};
+pub use chat::{ChatEntry, ChatLog};
+pub use desync_detector::Desync;
+pub use negotiation::Negotiation;
+pub use network_stats::NetworkStats;
+pub use session_error::SessionError;
+pub use session_message::{ChatMessage, Emote, MatchInitial, RoundInitial};
 
 fn to_channel<T>(
     mut data_channel: DataChannel,
     decode: fn(input: &[u8]) -> Result<T, Error>,
-) -> (mpsc::Sender<T>, mpsc::Receiver<T>)
+) -> (mpsc::Sender<T>, mpsc::Receiver<Result<T, SessionError>>)
 where
     T: Serialize + Send + 'static,
 {
@@ -55,11 +65,18 @@ where
             let Some(data) = data_channel.recv().await else {
                 return;
             };
-            let msg = decode(&data).unwrap();
+            // 壊れたメッセージの後は続きを解釈できないので、受信をやめる
+            let (msg, failed) = match decode(&data) {
+                Ok(msg) => (Ok(msg), false),
+                Err(err) => (Err(SessionError::DecodeFailed(err.to_string())), true),
+            };
             if let Err(err) = hook_incoming_tx.send(msg) {
                 debug!("send hook incoming msg error: {}", err);
                 return;
             }
+            if failed {
+                return;
+            }
         }
     });
     (hook_outgoing_tx, hook_incoming_rx)
@@ -1,16 +1,58 @@
-use std::sync::mpsc::RecvError;
+use std::{
+    sync::{mpsc, Arc, Mutex},
+    time::Duration,
+};
 
 use anyhow::Result;
 use getset::{CopyGetters, Getters, Setters};
-use junowen_lib::connection::{DataChannel, PeerConnection};
-use tracing::{info, trace};
+use junowen_lib::{
+    connection::{DataChannel, PeerConnection},
+    structs::settings::GameSettings,
+};
+use tracing::{info, trace, warn};
+use uuid::Uuid;
+
+use crate::{signaling::waiting_for_match::reconnect, TOKIO_RUNTIME};
 
 use super::{
-    delayed_inputs::DelayedInputs,
-    session_message::{MatchInitial, RoundInitial},
+    chat::{ChatEntry, ChatLog},
+    delayed_inputs::{DelayedInputs, Reconnect},
+    desync_detector::Desync,
+    negotiation::Negotiation,
+    network_stats::NetworkStats,
+    session_error::SessionError,
+    session_message::{ChatMessage, MatchInitial, RoundInitial},
     to_channel,
 };
 
+const RESUMED_NOTICE_DURATION: Duration = Duration::from_secs(5);
+
+/// 繋ぎ直した接続は、次に繋ぎ直すかセッションが終わるまで持っておく
+fn reconnect_fn() -> Reconnect {
+    let conn: Arc<Mutex<Option<PeerConnection>>> = Arc::default();
+    Box::new(move |resume_token| {
+        let (tx, rx) = mpsc::channel();
+        let conn = conn.clone();
+        let resume_token = resume_token.to_owned();
+        TOKIO_RUNTIME.spawn(async move {
+            let channel = match reconnect(&resume_token).await {
+                Ok((new_conn, data_channel)) => {
+                    *conn.lock().unwrap() = Some(new_conn);
+                    Some(to_channel(data_channel, |input| {
+                        rmp_serde::from_slice(input)
+                    }))
+                }
+                Err(err) => {
+                    warn!("reconnection failed: {}", err);
+                    None
+                }
+            };
+            let _ = tx.send(channel);
+        });
+        rx
+    })
+}
+
 #[derive(CopyGetters, Getters, Setters)]
 pub struct BattleSession {
     _conn: PeerConnection,
@@ -21,6 +63,13 @@ pub struct BattleSession {
     delayed_inputs: DelayedInputs,
     #[getset(set = "pub")]
     match_initial: Option<MatchInitial>,
+    /// 現在のラウンドのシード
+    round_initial: Option<RoundInitial>,
+    /// ホストが数字キーでディレイを決めるまでは、ラウンドごとに RTT から決める
+    #[getset(get_copy = "pub")]
+    auto_delay: bool,
+    #[getset(get = "pub")]
+    chat_log: ChatLog,
 }
 
 impl Drop for BattleSession {
@@ -33,28 +82,123 @@ impl BattleSession {
     pub fn new(conn: PeerConnection, data_channel: DataChannel, host: bool) -> Self {
         let (hook_outgoing_tx, hook_incoming_rx) =
             to_channel(data_channel, |input| rmp_serde::from_slice(input));
+        let delayed_inputs = DelayedInputs::new(hook_outgoing_tx, hook_incoming_rx, host);
         Self {
             _conn: conn,
             remote_player_name: "".to_owned(),
             host,
-            delayed_inputs: DelayedInputs::new(hook_outgoing_tx, hook_incoming_rx, host),
+            delayed_inputs,
             match_initial: None,
+            round_initial: None,
+            auto_delay: true,
+            chat_log: ChatLog::default(),
         }
     }
 
+    /// 切断されたらシグナリングサーバーで繋ぎ直す。
+    /// 純粋な P2P のセッションはサーバーに繋がるとは限らないので使わない
+    pub fn new_resumable(conn: PeerConnection, data_channel: DataChannel, host: bool) -> Self {
+        let mut zelf = Self::new(conn, data_channel, host);
+        let resume_token = host.then(|| Uuid::new_v4().to_string());
+        zelf.delayed_inputs
+            .enable_resume(resume_token, reconnect_fn());
+        zelf
+    }
+
     pub fn match_initial(&self) -> Option<&MatchInitial> {
         self.match_initial.as_ref()
     }
 
+    pub fn round_initial(&self) -> Option<&RoundInitial> {
+        self.round_initial.as_ref()
+    }
+
     pub fn delay(&self) -> u8 {
         self.delayed_inputs.delay()
     }
 
+    pub fn network_stats(&self) -> &NetworkStats {
+        self.delayed_inputs.network_stats()
+    }
+
+    pub fn recommended_delay(&self) -> Option<u8> {
+        self.network_stats().recommended_delay()
+    }
+
+    /// 繋ぎ直している間はゲームが止まるので、再開した後しばらく知らせる
+    pub fn recently_resumed(&self) -> bool {
+        self.delayed_inputs
+            .resumed_at()
+            .is_some_and(|at| at.elapsed() < RESUMED_NOTICE_DURATION)
+    }
+
+    pub fn desync(&self) -> Option<Desync> {
+        self.delayed_inputs.desync_detector().desync()
+    }
+
+    /// frame 目の入力を反映する前のゲームの状態を相手と比べる
+    pub fn send_checksum(&mut self, frame: u32, checksum: u64) {
+        self.delayed_inputs.send_checksum(frame, checksum);
+    }
+
+    /// 送ったものと届いたものを合わせて、新しいチャットを返す
+    pub fn exchange_chats(&mut self, chat: Option<ChatMessage>) -> Vec<ChatEntry> {
+        let mut entries = Vec::new();
+        if let Some(chat) = chat.and_then(ChatMessage::sanitized) {
+            self.delayed_inputs.send_chat(chat.clone());
+            entries.push(ChatEntry::new(self.host, chat));
+        }
+        let remote = self.delayed_inputs.take_received_chats();
+        entries.extend(remote.into_iter().map(|x| ChatEntry::new(!self.host, x)));
+        for entry in &entries {
+            self.chat_log.push(entry.clone());
+        }
+        entries
+    }
+
+    pub fn negotiation(&self) -> &Negotiation {
+        self.delayed_inputs.negotiation()
+    }
+
+    /// 次の試合の設定。合意した提案があればそれ
+    pub fn next_game_settings(&self) -> Option<&GameSettings> {
+        self.negotiation()
+            .agreed()
+            .or_else(|| self.match_initial().map(|x| &x.game_settings))
+    }
+
+    pub fn propose_settings(&mut self, game_settings: GameSettings) {
+        self.delayed_inputs.propose_settings(game_settings);
+    }
+
+    pub fn answer_proposal(&mut self, accept: bool) {
+        self.delayed_inputs.answer_proposal(accept);
+    }
+
+    pub fn answer_rematch(&mut self, rematch: bool) {
+        self.delayed_inputs.answer_rematch(rematch);
+    }
+
+    /// 両者が再戦すると答えたら、合意した設定を次の試合に使って true を返す
+    pub fn poll_rematch(&mut self) -> Result<bool, SessionError> {
+        match self.delayed_inputs.rematch_result() {
+            None => Ok(false),
+            Some(false) => Err(SessionError::RematchDeclined),
+            Some(true) => {
+                if let Some(game_settings) = self.delayed_inputs.finish_negotiation() {
+                    info!("game settings changed");
+                    self.match_initial = Some(MatchInitial { game_settings });
+                }
+                Ok(true)
+            }
+        }
+    }
+
     pub fn init_match(
         &mut self,
         player_name: String,
         init: Option<MatchInitial>,
-    ) -> Result<(String, Option<MatchInitial>), RecvError> {
+    ) -> Result<(String, Option<MatchInitial>), SessionError> {
         debug_assert!(self.host == init.is_some());
         if let Some(init) = init {
             self.delayed_inputs
@@ -69,18 +213,33 @@ impl BattleSession {
     pub fn init_round(
         &mut self,
         init: Option<RoundInitial>,
-    ) -> Result<Option<RoundInitial>, RecvError> {
+    ) -> Result<Option<RoundInitial>, SessionError> {
         debug_assert!(self.host == init.is_some());
         trace!("init_round");
+        if self.host && self.auto_delay {
+            if let Some(delay) = self.recommended_delay() {
+                if delay != self.delay() {
+                    self.delayed_inputs.send_delay(delay);
+                }
+            }
+        }
+        self.round_initial = init.clone();
         self.delayed_inputs.send_init_round(init);
-        self.delayed_inputs.recv_init_round()
+        let remote = self.delayed_inputs.recv_init_round()?;
+        if remote.is_some() {
+            self.round_initial = remote.clone();
+        }
+        Ok(remote)
     }
 
     pub fn enqueue_input_and_dequeue(
         &mut self,
         input: u16,
         delay: Option<u8>,
-    ) -> Result<(u16, u16), RecvError> {
+    ) -> Result<(u16, u16), SessionError> {
+        if delay.is_some() {
+            self.auto_delay = false;
+        }
         self.delayed_inputs.enqueue_input_and_dequeue(input, delay)
     }
 }
@@ -0,0 +1,54 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use getset::{CopyGetters, Getters};
+
+use super::session_message::ChatMessage;
+
+/// チャットを画面に出しておく時間
+const CHAT_DISPLAY_DURATION: Duration = Duration::from_secs(10);
+/// 画面に出すチャットの最大の数
+const MAX_CHAT_LOG_LEN: usize = 5;
+
+#[derive(Clone, CopyGetters, Getters)]
+pub struct ChatEntry {
+    /// p1 が送ったものなら true
+    #[getset(get_copy = "pub")]
+    p1: bool,
+    #[getset(get = "pub")]
+    message: ChatMessage,
+    received_at: Instant,
+}
+
+impl ChatEntry {
+    pub fn new(p1: bool, message: ChatMessage) -> Self {
+        Self {
+            p1,
+            message,
+            received_at: Instant::now(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ChatLog {
+    entries: VecDeque<ChatEntry>,
+}
+
+impl ChatLog {
+    pub fn push(&mut self, entry: ChatEntry) {
+        if self.entries.len() >= MAX_CHAT_LOG_LEN {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    /// 画面に出す、古い順のチャット
+    pub fn recent(&self) -> impl Iterator<Item = &ChatEntry> {
+        self.entries
+            .iter()
+            .filter(|x| x.received_at.elapsed() < CHAT_DISPLAY_DURATION)
+    }
+}
@@ -1,29 +1,73 @@
 use std::{
-    collections::LinkedList,
+    collections::{LinkedList, VecDeque},
     sync::mpsc::{self, RecvError},
+    time::{Duration, Instant},
 };
 
 use anyhow::Result;
-use getset::CopyGetters;
-use tracing::{debug, trace};
+use getset::{CopyGetters, Getters};
+use junowen_lib::structs::settings::GameSettings;
+use tracing::{debug, info, trace};
 
-use super::session_message::{MatchInitial, RoundInitial, SessionMessage};
+use super::{
+    desync_detector::DesyncDetector,
+    negotiation::Negotiation,
+    network_stats::NetworkStats,
+    session_error::SessionError,
+    session_message::{ChatMessage, MatchInitial, RoundInitial, SessionMessage},
+};
+
+const PING_INTERVAL: Duration = Duration::from_secs(1);
+/// 繋ぎ直してから再開の合図が届くまでを合わせた待ち時間
+const RESUME_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub type RemoteChannel = (
+    mpsc::Sender<SessionMessage>,
+    mpsc::Receiver<Result<SessionMessage, SessionError>>,
+);
 
-#[derive(CopyGetters)]
+/// 合言葉を使って相手と繋ぎ直す処理をゲームのスレッドの外で始める。
+/// 繋がらなければ None が届く
+pub type Reconnect = Box<dyn FnMut(&str) -> mpsc::Receiver<Option<RemoteChannel>> + Send>;
+
+#[derive(CopyGetters, Getters)]
 pub struct DelayedInputs {
     host: bool,
     local: LinkedList<SessionMessage>,
     remote_sender: mpsc::Sender<SessionMessage>,
-    remote_receiver: mpsc::Receiver<SessionMessage>,
+    remote_receiver: mpsc::Receiver<Result<SessionMessage, SessionError>>,
+    /// 受信済みでまだ処理していない相手のメッセージ
+    remote: VecDeque<Result<SessionMessage, SessionError>>,
     remote_round_initial: Option<Option<RoundInitial>>,
     #[getset(get_copy = "pub")]
     delay: u8,
+    started_at: Instant,
+    last_ping_at: Option<Instant>,
+    #[getset(get = "pub")]
+    network_stats: NetworkStats,
+    #[getset(get = "pub")]
+    desync_detector: DesyncDetector,
+    /// 届いたがまだ取り出されていないチャット
+    received_chats: Vec<ChatMessage>,
+    #[getset(get = "pub")]
+    negotiation: Negotiation,
+    /// 相手が受け取ったか分からない順序付きのメッセージ
+    outbox: VecDeque<SessionMessage>,
+    /// outbox[0] の通し番号
+    outbox_start: u32,
+    /// 受け取った順序付きのメッセージの数
+    received: u32,
+    resume_token: Option<String>,
+    reconnect: Option<Reconnect>,
+    /// 最後に繋ぎ直せた時刻
+    #[getset(get_copy = "pub")]
+    resumed_at: Option<Instant>,
 }
 
 impl DelayedInputs {
     pub fn new(
         remote_sender: mpsc::Sender<SessionMessage>,
-        remote_receiver: mpsc::Receiver<SessionMessage>,
+        remote_receiver: mpsc::Receiver<Result<SessionMessage, SessionError>>,
         host: bool,
     ) -> Self {
         Self {
@@ -31,11 +75,228 @@ impl DelayedInputs {
             local: LinkedList::new(),
             remote_sender,
             remote_receiver,
+            remote: VecDeque::new(),
             remote_round_initial: None,
             delay: 1,
+            started_at: Instant::now(),
+            last_ping_at: None,
+            network_stats: NetworkStats::default(),
+            desync_detector: DesyncDetector::default(),
+            received_chats: Vec::new(),
+            negotiation: Negotiation::default(),
+            outbox: VecDeque::new(),
+            outbox_start: 0,
+            received: 0,
+            resume_token: None,
+            reconnect: None,
+            resumed_at: None,
+        }
+    }
+
+    /// 切断されたときに、ホストが決めた合言葉で繋ぎ直せるようにする
+    pub fn enable_resume(&mut self, resume_token: Option<String>, reconnect: Reconnect) {
+        debug_assert!(self.host == resume_token.is_some());
+        if let Some(resume_token) = resume_token {
+            let _ = self
+                .remote_sender
+                .send(SessionMessage::ResumeToken(resume_token.clone()));
+            self.resume_token = Some(resume_token);
+        }
+        self.reconnect = Some(reconnect);
+    }
+
+    /// 繋ぎ直したときに送り直せるように、送ったメッセージを残しておく
+    fn send_ordered(&mut self, msg: SessionMessage) {
+        let _ = self.remote_sender.send(msg.clone());
+        self.outbox.push_back(msg);
+    }
+
+    fn discard_received_by_remote(&mut self, received: u32) {
+        while self.outbox_start < received && self.outbox.pop_front().is_some() {
+            self.outbox_start += 1;
+        }
+    }
+
+    /// 繋ぎ直して、相手に届かなかったメッセージを送り直す
+    fn resume(&mut self) -> Result<(), SessionError> {
+        let (Some(resume_token), Some(reconnect)) = (&self.resume_token, &mut self.reconnect)
+        else {
+            return Err(SessionError::Disconnected);
+        };
+        info!("reconnecting...");
+        let deadline = Instant::now() + RESUME_TIMEOUT;
+        let (remote_sender, remote_receiver) = reconnect(resume_token)
+            .recv_timeout(RESUME_TIMEOUT)
+            .ok()
+            .flatten()
+            .ok_or(SessionError::Disconnected)?;
+        self.remote_sender = remote_sender;
+        self.remote_receiver = remote_receiver;
+        let _ = self
+            .remote_sender
+            .send(SessionMessage::Resume(self.received));
+        let received_by_remote = loop {
+            let msg = self
+                .remote_receiver
+                .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                .map_err(|_| SessionError::Disconnected)??;
+            if let SessionMessage::Resume(received) = msg {
+                break received;
+            }
+            if let Some(msg) = self.handle_out_of_band(msg) {
+                return Err(SessionError::unexpected_message(&msg));
+            }
+        };
+        if received_by_remote < self.outbox_start {
+            return Err(SessionError::unexpected_message(&SessionMessage::Resume(
+                received_by_remote,
+            )));
+        }
+        self.discard_received_by_remote(received_by_remote);
+        for msg in &self.outbox {
+            let _ = self.remote_sender.send(msg.clone());
+        }
+        info!("resumed: resent {} messages", self.outbox.len());
+        self.resumed_at = Some(Instant::now());
+        Ok(())
+    }
+
+    fn elapsed_ms(&self) -> u32 {
+        self.started_at.elapsed().as_millis() as u32
+    }
+
+    fn send_ping_if_needed(&mut self) {
+        if self
+            .last_ping_at
+            .is_some_and(|at| at.elapsed() < PING_INTERVAL)
+        {
+            return;
+        }
+        self.last_ping_at = Some(Instant::now());
+        let _ = self
+            .remote_sender
+            .send(SessionMessage::Ping(self.elapsed_ms()));
+        let _ = self
+            .remote_sender
+            .send(SessionMessage::Received(self.received));
+    }
+
+    /// 順序を問わないメッセージはその場で処理して、それ以外を返す
+    fn handle_out_of_band(&mut self, msg: SessionMessage) -> Option<SessionMessage> {
+        match msg {
+            SessionMessage::Ping(sent_ms) => {
+                let _ = self.remote_sender.send(SessionMessage::Pong(sent_ms));
+                None
+            }
+            SessionMessage::Pong(sent_ms) => {
+                let rtt = self.elapsed_ms().saturating_sub(sent_ms);
+                trace!("rtt: {}ms", rtt);
+                self.network_stats
+                    .add_rtt_sample(Duration::from_millis(rtt as u64));
+                None
+            }
+            SessionMessage::Checksum(checksum) => {
+                self.desync_detector.add_remote(&checksum);
+                None
+            }
+            SessionMessage::ResumeToken(resume_token) if !self.host => {
+                self.resume_token = Some(resume_token);
+                None
+            }
+            SessionMessage::Received(received) => {
+                self.discard_received_by_remote(received);
+                None
+            }
+            SessionMessage::Chat(chat) => {
+                self.received_chats.extend(chat.sanitized());
+                None
+            }
+            SessionMessage::Negotiation(msg) => {
+                self.received += 1;
+                self.negotiation.on_remote(msg, self.host);
+                None
+            }
+            msg => {
+                self.received += 1;
+                Some(msg)
+            }
+        }
+    }
+
+    /// 相手の入力を待たずに届いているメッセージを受け取る。
+    /// 入力の列に並んだ Ping を後回しにすると RTT にディレイが含まれてしまう
+    fn receive_remote_messages(&mut self) {
+        while let Ok(msg) = self.remote_receiver.try_recv() {
+            let msg = match msg {
+                Ok(msg) => self.handle_out_of_band(msg).map(Ok),
+                Err(err) => Some(Err(err)),
+            };
+            if let Some(msg) = msg {
+                self.remote.push_back(msg);
+            }
+        }
+    }
+
+    fn recv_remote(&mut self) -> Result<SessionMessage, SessionError> {
+        if let Some(msg) = self.remote.pop_front() {
+            return msg;
+        }
+        loop {
+            let msg = match self.remote_receiver.recv() {
+                Ok(msg) => msg?,
+                Err(RecvError) => {
+                    self.resume()?;
+                    continue;
+                }
+            };
+            if let Some(msg) = self.handle_out_of_band(msg) {
+                return Ok(msg);
+            }
+        }
+    }
+
+    pub fn send_checksum(&mut self, frame: u32, checksum: u64) {
+        let msg = self.desync_detector.add_local(frame, checksum);
+        let _ = self.remote_sender.send(SessionMessage::Checksum(msg));
+    }
+
+    pub fn send_chat(&mut self, chat: ChatMessage) {
+        let _ = self.remote_sender.send(SessionMessage::Chat(chat));
+    }
+
+    pub fn take_received_chats(&mut self) -> Vec<ChatMessage> {
+        self.receive_remote_messages();
+        std::mem::take(&mut self.received_chats)
+    }
+
+    pub fn propose_settings(&mut self, game_settings: GameSettings) {
+        if let Some(msg) = self.negotiation.propose(game_settings) {
+            self.send_ordered(SessionMessage::Negotiation(msg));
         }
     }
 
+    pub fn answer_proposal(&mut self, accept: bool) {
+        if let Some(msg) = self.negotiation.answer_proposal(accept) {
+            self.send_ordered(SessionMessage::Negotiation(msg));
+        }
+    }
+
+    pub fn answer_rematch(&mut self, rematch: bool) {
+        if let Some(msg) = self.negotiation.answer_rematch(rematch) {
+            self.send_ordered(SessionMessage::Negotiation(msg));
+        }
+    }
+
+    /// 届いている相手の答えを反映してから、再戦するかを返す
+    pub fn rematch_result(&mut self) -> Option<bool> {
+        self.receive_remote_messages();
+        self.negotiation.result()
+    }
+
+    pub fn finish_negotiation(&mut self) -> Option<GameSettings> {
+        self.negotiation.finish()
+    }
+
     /// positive value when buffer data is too much,
     /// negative value when buffer data is not enough
     fn delay_gap(&self) -> i8 {
@@ -48,30 +309,35 @@ impl DelayedInputs {
     }
 
     pub fn send_init_match(&mut self, init: (String, Option<MatchInitial>)) {
-        let _ = self.remote_sender.send(SessionMessage::InitMatch(init));
+        self.send_ordered(SessionMessage::InitMatch(init));
     }
 
-    pub fn recv_init_match(&mut self) -> Result<(String, Option<MatchInitial>), RecvError> {
-        let msg = self.remote_receiver.recv()?;
+    pub fn recv_init_match(&mut self) -> Result<(String, Option<MatchInitial>), SessionError> {
+        let msg = self.recv_remote()?;
         let SessionMessage::InitMatch(init) = msg else {
-            panic!("unexpected message: {:?}", msg);
+            return Err(SessionError::unexpected_message(&msg));
         };
         Ok(init)
     }
 
     pub fn send_init_round(&mut self, init: Option<RoundInitial>) {
-        let _ = self.remote_sender.send(SessionMessage::InitRound(init));
+        self.send_ordered(SessionMessage::InitRound(init));
     }
 
-    pub fn recv_init_round(&mut self) -> Result<Option<RoundInitial>, RecvError> {
+    /// 次のラウンドからディレイを変更する。ホストのみ
+    pub fn send_delay(&mut self, delay: u8) {
+        debug_assert!(self.host);
+        self.send_ordered(SessionMessage::Delay(delay));
+        self.local.push_back(SessionMessage::Delay(delay));
+    }
+
+    pub fn recv_init_round(&mut self) -> Result<Option<RoundInitial>, SessionError> {
         let mut local_delay = None;
-        loop {
-            let Some((_, delay)) = self.dequeue_local() else {
-                break;
-            };
-            if let Some(delay) = delay {
+        while let Some(local) = self.local.pop_front() {
+            if let SessionMessage::Delay(delay) = local {
                 debug_assert!(self.host);
                 local_delay = Some(delay);
+                continue;
             }
             trace!("local input skipped");
         }
@@ -82,7 +348,7 @@ impl DelayedInputs {
             }
             let (_, delay) = self.dequeue_remote()?;
             if let Some(delay) = delay {
-                debug_assert!(self.host);
+                debug_assert!(!self.host);
                 remote_delay = Some(delay);
             }
             trace!("remote input skipped");
@@ -91,6 +357,7 @@ impl DelayedInputs {
         if let Some(delay) = delay {
             self.update_delay(delay);
         }
+        self.desync_detector.next_round();
         Ok(round_initial)
     }
 
@@ -98,22 +365,28 @@ impl DelayedInputs {
         &mut self,
         input: u16,
         delay: Option<u8>,
-    ) -> Result<(u16, u16), RecvError> {
+    ) -> Result<(u16, u16), SessionError> {
+        self.send_ping_if_needed();
+        self.receive_remote_messages();
         let delay_gap = self.delay_gap();
         if delay_gap <= 0 {
             if let Some(delay) = delay {
-                let _ = self.remote_sender.send(SessionMessage::Delay(delay));
+                self.send_ordered(SessionMessage::Delay(delay));
                 self.local.push_back(SessionMessage::Delay(delay));
             }
-            let _ = self.remote_sender.send(SessionMessage::Input(input));
+            self.send_ordered(SessionMessage::Input(input));
             self.local.push_back(SessionMessage::Input(input));
         }
         if delay_gap < 0 {
             trace!("delay gap updated: {}", self.delay_gap());
             return Ok((0, 0));
         }
-        let (local, local_delay) = self.dequeue_local().unwrap();
+        let (local, local_delay) = self.dequeue_local()?.unwrap();
+        let queue_depth = self.remote.len();
+        let dequeue_started_at = Instant::now();
         let (remote, remote_delay) = self.dequeue_remote()?;
+        self.network_stats
+            .add_frame_sample(dequeue_started_at.elapsed(), queue_depth);
         let (p1, p2, delay) = if self.host {
             (local, remote, local_delay)
         } else {
@@ -131,35 +404,60 @@ impl DelayedInputs {
         debug!("delay gap={}", self.delay_gap());
     }
 
-    fn dequeue_local(&mut self) -> Option<(u16, Option<u8>)> {
+    fn dequeue_local(&mut self) -> Result<Option<(u16, Option<u8>)>, SessionError> {
         let mut delay = None;
         loop {
-            let local = self.local.pop_front()?;
+            let Some(local) = self.local.pop_front() else {
+                return Ok(None);
+            };
             debug_assert!(matches!(local, SessionMessage::Input(_)) || self.host);
             match local {
-                SessionMessage::InitMatch(_) => panic!("unexpected message: {:?}", local),
+                SessionMessage::InitMatch(_)
+                | SessionMessage::InitRound(_)
+                | SessionMessage::Ping(_)
+                | SessionMessage::Pong(_)
+                | SessionMessage::Checksum(_)
+                | SessionMessage::ResumeToken(_)
+                | SessionMessage::Received(_)
+                | SessionMessage::Resume(_)
+                | SessionMessage::Chat(_)
+                | SessionMessage::Negotiation(_) => {
+                    return Err(SessionError::unexpected_message(&local))
+                }
                 SessionMessage::Delay(d) => {
                     debug_assert!(self.host);
                     delay = Some(d);
                     continue;
                 }
-                SessionMessage::Input(input) => return Some((input, delay)),
-                SessionMessage::InitRound(_) => panic!(),
+                SessionMessage::Input(input) => return Ok(Some((input, delay))),
             }
         }
     }
 
-    fn dequeue_remote(&mut self) -> Result<(u16, Option<u8>), RecvError> {
+    fn dequeue_remote(&mut self) -> Result<(u16, Option<u8>), SessionError> {
         if self.remote_round_initial.is_some() {
             return Ok((0, None));
         }
         let mut delay = None;
         loop {
-            let remote = self.remote_receiver.recv()?;
+            let remote = self.recv_remote()?;
             match remote {
-                SessionMessage::InitMatch(_) => panic!("unexpected message: {:?}", remote),
+                SessionMessage::InitMatch(_)
+                | SessionMessage::Ping(_)
+                | SessionMessage::Pong(_)
+                | SessionMessage::Checksum(_)
+                | SessionMessage::ResumeToken(_)
+                | SessionMessage::Received(_)
+                | SessionMessage::Resume(_)
+                | SessionMessage::Chat(_)
+                | SessionMessage::Negotiation(_) => {
+                    return Err(SessionError::unexpected_message(&remote))
+                }
+                // ディレイはホストのみ変更できる
+                SessionMessage::Delay(_) if self.host => {
+                    return Err(SessionError::unexpected_message(&remote))
+                }
                 SessionMessage::Delay(d) => {
-                    debug_assert!(!self.host);
                     delay = Some(d);
                     continue;
                 }
@@ -167,7 +465,7 @@ impl DelayedInputs {
                 SessionMessage::InitRound(round_initial) => {
                     debug_assert!(self.remote_round_initial.is_none());
                     self.remote_round_initial = Some(round_initial);
-                    return Ok((0, None));
+                    return Ok((0, delay));
                 }
             }
         }
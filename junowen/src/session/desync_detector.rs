@@ -0,0 +1,74 @@
+use std::collections::BTreeMap;
+
+use getset::CopyGetters;
+use tracing::warn;
+
+use super::session_message::FrameChecksum;
+
+/// 相手のチェックサムが届かないまま保持しておく数
+const MAX_PENDING_CHECKSUMS: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Desync {
+    pub round: u32,
+    pub frame: u32,
+}
+
+/// 同じフレームのゲームの状態のチェックサムを両者で比べる
+#[derive(CopyGetters, Default)]
+pub struct DesyncDetector {
+    /// init_round した回数
+    #[getset(get_copy = "pub")]
+    round: u32,
+    local: BTreeMap<(u32, u32), u64>,
+    remote: BTreeMap<(u32, u32), u64>,
+    /// 最初に見つかった不一致
+    #[getset(get_copy = "pub")]
+    desync: Option<Desync>,
+}
+
+impl DesyncDetector {
+    pub fn next_round(&mut self) {
+        self.round += 1;
+    }
+
+    pub fn add_local(&mut self, frame: u32, checksum: u64) -> FrameChecksum {
+        let msg = FrameChecksum {
+            round: self.round,
+            frame,
+            checksum,
+        };
+        Self::insert(&mut self.local, &msg);
+        self.compare(self.round, frame);
+        msg
+    }
+
+    pub fn add_remote(&mut self, msg: &FrameChecksum) {
+        Self::insert(&mut self.remote, msg);
+        self.compare(msg.round, msg.frame);
+    }
+
+    fn insert(checksums: &mut BTreeMap<(u32, u32), u64>, msg: &FrameChecksum) {
+        checksums.insert((msg.round, msg.frame), msg.checksum);
+        if checksums.len() > MAX_PENDING_CHECKSUMS {
+            checksums.pop_first();
+        }
+    }
+
+    fn compare(&mut self, round: u32, frame: u32) {
+        let key = (round, frame);
+        let (Some(&local), Some(&remote)) = (self.local.get(&key), self.remote.get(&key)) else {
+            return;
+        };
+        self.local.remove(&key);
+        self.remote.remove(&key);
+        if local == remote || self.desync.is_some() {
+            return;
+        }
+        warn!(
+            "desync detected: round={}, frame={}, local={:016x}, remote={:016x}",
+            round, frame, local, remote
+        );
+        self.desync = Some(Desync { round, frame });
+    }
+}
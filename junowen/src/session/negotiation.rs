@@ -0,0 +1,154 @@
+use getset::CopyGetters;
+use junowen_lib::structs::settings::GameSettings;
+use tracing::{info, warn};
+
+use super::session_message::NegotiationMessage;
+
+/// 試合の合間に、次の試合の設定と再戦するかを相手と決める。
+/// 両者が同時に提案したときはホストの提案を優先する
+#[derive(CopyGetters, Default)]
+pub struct Negotiation {
+    /// 答えを待っている提案 (自分の提案か, 設定)
+    proposal: Option<(bool, GameSettings)>,
+    /// 受け入れられた最後の提案。次の試合から使う
+    agreed: Option<GameSettings>,
+    /// 自分の提案への最後の答え
+    #[getset(get_copy = "pub")]
+    answer: Option<bool>,
+    #[getset(get_copy = "pub")]
+    local_rematch: Option<bool>,
+    #[getset(get_copy = "pub")]
+    remote_rematch: Option<bool>,
+}
+
+impl Negotiation {
+    pub fn proposal(&self) -> Option<(bool, &GameSettings)> {
+        self.proposal.as_ref().map(|(local, x)| (*local, x))
+    }
+
+    pub fn agreed(&self) -> Option<&GameSettings> {
+        self.agreed.as_ref()
+    }
+
+    /// 提案の答えを待っているか、再戦すると答えた後は提案できない
+    pub fn can_propose(&self) -> bool {
+        self.proposal.is_none() && self.local_rematch.is_none()
+    }
+
+    /// 設定が変わったら、それまでの再戦の答えは無効になる。再戦しないという答えは取り消さない
+    fn reset_rematch(&mut self) {
+        if self.local_rematch == Some(true) {
+            self.local_rematch = None;
+        }
+        if self.remote_rematch == Some(true) {
+            self.remote_rematch = None;
+        }
+    }
+
+    pub fn propose(&mut self, game_settings: GameSettings) -> Option<NegotiationMessage> {
+        if !self.can_propose() {
+            return None;
+        }
+        self.proposal = Some((true, game_settings.clone()));
+        self.answer = None;
+        self.reset_rematch();
+        Some(NegotiationMessage::Propose(game_settings))
+    }
+
+    pub fn answer_proposal(&mut self, accept: bool) -> Option<NegotiationMessage> {
+        if !matches!(self.proposal, Some((false, _))) {
+            return None;
+        }
+        let (_, game_settings) = self.proposal.take().unwrap();
+        if accept {
+            self.agreed = Some(game_settings);
+        }
+        self.reset_rematch();
+        Some(NegotiationMessage::Answer(accept))
+    }
+
+    /// 再戦しないと答えたらそれで終わり。再戦するなら相手の提案に答えてからにする
+    pub fn answer_rematch(&mut self, rematch: bool) -> Option<NegotiationMessage> {
+        if rematch && (self.proposal.is_some() || self.local_rematch.is_some()) {
+            return None;
+        }
+        self.local_rematch = Some(rematch);
+        Some(NegotiationMessage::Rematch(rematch))
+    }
+
+    pub fn on_remote(&mut self, msg: NegotiationMessage, host: bool) {
+        match msg {
+            NegotiationMessage::Propose(game_settings) => {
+                if host && matches!(self.proposal, Some((true, _))) {
+                    info!("remote proposal ignored");
+                    return;
+                }
+                self.proposal = Some((false, game_settings));
+                self.answer = None;
+                self.reset_rematch();
+            }
+            NegotiationMessage::Answer(accept) => {
+                if !matches!(self.proposal, Some((true, _))) {
+                    warn!("unexpected answer: {}", accept);
+                    return;
+                }
+                let (_, game_settings) = self.proposal.take().unwrap();
+                if accept {
+                    self.agreed = Some(game_settings);
+                }
+                self.answer = Some(accept);
+                self.reset_rematch();
+            }
+            NegotiationMessage::Rematch(rematch) => {
+                self.remote_rematch = Some(rematch);
+            }
+        }
+    }
+
+    /// どちらかが再戦しないと答えたら Some(false)、両者が再戦すると答えたら Some(true)
+    pub fn result(&self) -> Option<bool> {
+        if self.local_rematch == Some(false) || self.remote_rematch == Some(false) {
+            return Some(false);
+        }
+        (self.local_rematch == Some(true)
+            && self.remote_rematch == Some(true)
+            && self.proposal.is_none())
+        .then_some(true)
+    }
+
+    /// 次の試合の合間に備えて初期状態に戻し、合意した設定を返す
+    pub fn finish(&mut self) -> Option<GameSettings> {
+        std::mem::take(self).agreed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use junowen_lib::structs::settings::GameSettings;
+
+    use crate::session::session_message::NegotiationMessage;
+
+    use super::Negotiation;
+
+    #[test]
+    fn quit_is_kept_when_remote_proposes_afterwards() {
+        let mut negotiation = Negotiation::default();
+        assert!(negotiation.answer_rematch(false).is_some());
+        let proposal = NegotiationMessage::Propose(GameSettings::default());
+        negotiation.on_remote(proposal, true);
+        assert_eq!(negotiation.local_rematch(), Some(false));
+        assert_eq!(negotiation.result(), Some(false));
+    }
+
+    #[test]
+    fn rematch_is_reset_when_remote_proposes_afterwards() {
+        let mut negotiation = Negotiation::default();
+        assert!(negotiation.answer_rematch(true).is_some());
+        negotiation.on_remote(NegotiationMessage::Rematch(true), true);
+        let proposal = NegotiationMessage::Propose(GameSettings::default());
+        negotiation.on_remote(proposal, true);
+        assert_eq!(negotiation.local_rematch(), None);
+        assert_eq!(negotiation.remote_rematch(), None);
+        assert_eq!(negotiation.result(), None);
+    }
+}
@@ -0,0 +1,74 @@
+use std::{collections::VecDeque, time::Duration};
+
+use getset::CopyGetters;
+
+const MAX_RTT_SAMPLES: usize = 16;
+const FRAMES_PER_SECOND: usize = 60;
+const FRAME_MS: f64 = 1000.0 / FRAMES_PER_SECOND as f64;
+const MAX_DELAY: u8 = 9;
+
+#[derive(CopyGetters, Default)]
+pub struct NetworkStats {
+    rtt_samples: VecDeque<Duration>,
+    /// 直近 1 秒間の、フレームごとに相手の入力を待った時間
+    stall_samples: VecDeque<Duration>,
+    /// 受信済みで処理していない相手のメッセージの数
+    #[getset(get_copy = "pub")]
+    queue_depth: usize,
+}
+
+impl NetworkStats {
+    pub fn add_frame_sample(&mut self, stall: Duration, queue_depth: usize) {
+        if self.stall_samples.len() >= FRAMES_PER_SECOND {
+            self.stall_samples.pop_front();
+        }
+        self.stall_samples.push_back(stall);
+        self.queue_depth = queue_depth;
+    }
+
+    /// 直前のフレームで相手の入力を待った時間
+    pub fn last_stall(&self) -> Duration {
+        self.stall_samples.back().copied().unwrap_or_default()
+    }
+
+    /// 直近 1 秒間に相手の入力を待った時間の合計
+    pub fn stall_per_second(&self) -> Duration {
+        self.stall_samples.iter().sum()
+    }
+
+    pub fn add_rtt_sample(&mut self, rtt: Duration) {
+        if self.rtt_samples.len() >= MAX_RTT_SAMPLES {
+            self.rtt_samples.pop_front();
+        }
+        self.rtt_samples.push_back(rtt);
+    }
+
+    pub fn rtt(&self) -> Option<Duration> {
+        if self.rtt_samples.is_empty() {
+            return None;
+        }
+        Some(self.rtt_samples.iter().sum::<Duration>() / self.rtt_samples.len() as u32)
+    }
+
+    /// 連続する RTT の差の平均
+    pub fn jitter(&self) -> Option<Duration> {
+        if self.rtt_samples.len() < 2 {
+            return None;
+        }
+        let sum: Duration = self
+            .rtt_samples
+            .iter()
+            .zip(self.rtt_samples.iter().skip(1))
+            .map(|(a, b)| a.abs_diff(*b))
+            .sum();
+        Some(sum / (self.rtt_samples.len() - 1) as u32)
+    }
+
+    /// 片道の遅延に揺らぎの分の余裕を足して、相手の入力が間に合うフレーム数
+    pub fn recommended_delay(&self) -> Option<u8> {
+        let rtt = self.rtt()?.as_secs_f64() * 1000.0;
+        let jitter = self.jitter().unwrap_or_default().as_secs_f64() * 1000.0;
+        let frames = ((rtt / 2.0 + jitter * 2.0) / FRAME_MS).ceil();
+        Some((frames as u8).clamp(1, MAX_DELAY))
+    }
+}
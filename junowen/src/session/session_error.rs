@@ -0,0 +1,30 @@
+use std::{fmt::Debug, sync::mpsc::RecvError};
+
+use thiserror::Error;
+
+/// 相手から不正なメッセージが届いたり、切断されたりしてセッションを続けられない
+#[derive(Debug, Error)]
+pub enum SessionError {
+    #[error("unexpected message: {0}")]
+    UnexpectedMessage(String),
+    #[error("failed to decode message: {0}")]
+    DecodeFailed(String),
+    #[error("disconnected")]
+    Disconnected,
+    #[error("spectator host left")]
+    SpectatorHostLeft,
+    #[error("rematch declined")]
+    RematchDeclined,
+}
+
+impl SessionError {
+    pub fn unexpected_message(msg: &impl Debug) -> Self {
+        Self::UnexpectedMessage(format!("{:?}", msg))
+    }
+}
+
+impl From<RecvError> for SessionError {
+    fn from(_: RecvError) -> Self {
+        Self::Disconnected
+    }
+}
@@ -1,12 +1,15 @@
 use junowen_lib::structs::settings::GameSettings;
 use serde::{Deserialize, Serialize};
 
+/// チャットの文の最大の文字数
+const MAX_CHAT_TEXT_LEN: usize = 40;
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct MatchInitial {
     pub game_settings: GameSettings,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct RoundInitial {
     pub seed1: u32,
     pub seed2: u32,
@@ -14,11 +17,95 @@ pub struct RoundInitial {
     pub seed4: u32,
 }
 
-/** input 以外はホストのみ発行できる */
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct FrameChecksum {
+    pub round: u32,
+    pub frame: u32,
+    pub checksum: u64,
+}
+
+/// 定型文
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum Emote {
+    Gg,
+    OneMore,
+    Lag,
+}
+
+impl Emote {
+    pub fn text(&self) -> &'static str {
+        match self {
+            Self::Gg => "gg",
+            Self::OneMore => "one more?",
+            Self::Lag => "lag?",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum ChatMessage {
+    Emote(Emote),
+    Text(String),
+}
+
+impl ChatMessage {
+    /// ゲームのフォントで表示できる 1 行の短い文にする。空になったら None
+    pub fn sanitized(self) -> Option<Self> {
+        let Self::Text(text) = self else {
+            return Some(self);
+        };
+        let text: String = text
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .chars()
+            .filter(|c| c.is_ascii_graphic() || *c == ' ')
+            .take(MAX_CHAT_TEXT_LEN)
+            .collect();
+        let text = text.trim();
+        (!text.is_empty()).then(|| Self::Text(text.to_owned()))
+    }
+
+    pub fn text(&self) -> &str {
+        match self {
+            Self::Emote(emote) => emote.text(),
+            Self::Text(text) => text,
+        }
+    }
+}
+
+/// 試合の合間に次の試合の設定と再戦するかを決める
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum NegotiationMessage {
+    /// 次の試合の設定を提案する
+    Propose(GameSettings),
+    /// 相手の提案を受け入れるか
+    Answer(bool),
+    /// 再戦するか
+    Rematch(bool),
+}
+
+/** input, ping, pong, checksum, received, resume, chat, negotiation 以外はホストのみ発行できる */
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub enum SessionMessage {
     InitMatch((String, Option<MatchInitial>)),
     InitRound(Option<RoundInitial>),
     Delay(u8),
     Input(u16),
+    /// 送信側のセッション開始からのミリ秒
+    Ping(u32),
+    /// 受け取った Ping の値をそのまま返す
+    Pong(u32),
+    /// 入力の列とは別に送るので、届く順番は問わない
+    Checksum(FrameChecksum),
+    /// 切断されたときに繋ぎ直す部屋の合言葉
+    ResumeToken(String),
+    /// 受け取った順序付きのメッセージの数。これより前は送り直さなくて良い
+    Received(u32),
+    /// 繋ぎ直した直後に送る、受け取った順序付きのメッセージの数
+    Resume(u32),
+    /// 入力の列とは別に送るので、届く順番は問わない。繋ぎ直しても送り直さない
+    Chat(ChatMessage),
+    /// 入力の列とは別に送るが、繋ぎ直したら送り直す
+    Negotiation(NegotiationMessage),
 }
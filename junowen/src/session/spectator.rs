@@ -1,4 +1,4 @@
-use std::sync::mpsc::RecvError;
+use std::{collections::VecDeque, mem};
 
 use anyhow::Result;
 use derive_new::new;
@@ -8,9 +8,14 @@ use junowen_lib::{
     structs::settings::GameSettings,
 };
 use serde::{Deserialize, Serialize};
-use tracing::{error, info};
+use tracing::info;
 
-use super::{session_message::RoundInitial, to_channel};
+use super::{
+    chat::{ChatEntry, ChatLog},
+    session_error::SessionError,
+    session_message::{ChatMessage, RoundInitial},
+    to_channel,
+};
 
 #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 pub enum Screen {
@@ -35,7 +40,7 @@ pub struct InitialState {
     p2_card: u8,
 }
 
-#[derive(new, Clone, Debug, Deserialize, Getters, Serialize)]
+#[derive(new, Clone, CopyGetters, Debug, Deserialize, Getters, Serialize)]
 pub struct SpectatorInitial {
     #[get = "pub"]
     p1_name: String,
@@ -45,6 +50,25 @@ pub struct SpectatorInitial {
     game_settings: GameSettings,
     #[get = "pub"]
     initial_state: InitialState,
+    /// 対戦者から観戦者に届くまでに遅らせているフレーム数。中継した観戦者の分も含む
+    #[get_copy = "pub"]
+    delay_frames: u32,
+}
+
+/// 途中から観戦する為に溜めておくメッセージ
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum BufferedMessage {
+    InitRound(RoundInitial),
+    Inputs(u16, u16),
+}
+
+impl From<BufferedMessage> for SpectatorSessionMessage {
+    fn from(msg: BufferedMessage) -> Self {
+        match msg {
+            BufferedMessage::InitRound(round_initial) => Self::InitRound(round_initial),
+            BufferedMessage::Inputs(p1, p2) => Self::Inputs(p1, p2),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -52,14 +76,26 @@ pub enum SpectatorSessionMessage {
     InitSpectator(SpectatorInitial),
     InitRound(RoundInitial),
     Inputs(u16, u16),
+    /// 送り元の対戦者や中継している観戦者が抜けた
+    Leave,
+    /// 観戦を始める前のメッセージをまとめたもの
+    CatchUp(Vec<BufferedMessage>),
+    /// 対戦者のチャット (p1 が送ったか, 内容)
+    Chat(bool, ChatMessage),
 }
 
 #[derive(CopyGetters, Getters, Setters)]
 pub struct SpectatorSession {
     _conn: PeerConnection,
-    hook_incoming_rx: std::sync::mpsc::Receiver<SpectatorSessionMessage>,
+    hook_incoming_rx: std::sync::mpsc::Receiver<Result<SpectatorSessionMessage, SessionError>>,
     spectator_initial: Option<SpectatorInitial>,
     round_initial: Option<RoundInitial>,
+    /// 受け取ったが処理していない CatchUp の中身
+    catch_up: VecDeque<BufferedMessage>,
+    #[getset(get = "pub")]
+    chat_log: ChatLog,
+    /// 更に別の観戦者に中継していないチャット
+    new_chats: Vec<ChatEntry>,
 }
 
 impl SpectatorSession {
@@ -71,55 +107,86 @@ impl SpectatorSession {
             hook_incoming_rx,
             spectator_initial: None,
             round_initial: None,
+            catch_up: VecDeque::new(),
+            chat_log: ChatLog::default(),
+            new_chats: Vec::new(),
         }
     }
 
+    /// 途中から観戦を始めて、まだ追いついていない
+    pub fn catching_up(&self) -> bool {
+        !self.catch_up.is_empty()
+    }
+
+    fn recv(&mut self) -> Result<SpectatorSessionMessage, SessionError> {
+        loop {
+            if let Some(msg) = self.catch_up.pop_front() {
+                return Ok(msg.into());
+            }
+            match self.hook_incoming_rx.recv()?? {
+                SpectatorSessionMessage::CatchUp(messages) => self.catch_up.extend(messages),
+                SpectatorSessionMessage::Chat(p1, chat) => {
+                    if let Some(chat) = chat.sanitized() {
+                        let entry = ChatEntry::new(p1, chat);
+                        self.chat_log.push(entry.clone());
+                        self.new_chats.push(entry);
+                    }
+                }
+                msg => return Ok(msg),
+            }
+        }
+    }
+
+    pub fn take_new_chats(&mut self) -> Vec<ChatEntry> {
+        mem::take(&mut self.new_chats)
+    }
+
     pub fn spectator_initial(&self) -> Option<&SpectatorInitial> {
         self.spectator_initial.as_ref()
     }
 
-    pub fn recv_init_spectator(&mut self) -> Result<(), RecvError> {
-        let init = match self.hook_incoming_rx.recv()? {
+    pub fn recv_init_spectator(&mut self) -> Result<(), SessionError> {
+        let init = match self.recv()? {
             SpectatorSessionMessage::InitSpectator(init) => init,
-            msg => {
-                error!("unexpected message: {:?}", msg);
-                return Err(RecvError);
-            }
+            SpectatorSessionMessage::Leave => return Err(SessionError::SpectatorHostLeft),
+            msg => return Err(SessionError::unexpected_message(&msg)),
         };
         self.spectator_initial = Some(init);
         Ok(())
     }
 
-    pub fn dequeue_init_round(&mut self) -> Result<RoundInitial, RecvError> {
+    pub fn dequeue_init_round(&mut self) -> Result<RoundInitial, SessionError> {
         if let Some(round_initial) = self.round_initial.take() {
             return Ok(round_initial);
         }
         loop {
-            match self.hook_incoming_rx.recv()? {
-                SpectatorSessionMessage::InitSpectator(init) => {
-                    error!("unexpected init spectator message: {:?}", init);
-                    return Err(RecvError);
+            match self.recv()? {
+                msg @ (SpectatorSessionMessage::InitSpectator(_)
+                | SpectatorSessionMessage::CatchUp(_)
+                | SpectatorSessionMessage::Chat(..)) => {
+                    return Err(SessionError::unexpected_message(&msg))
                 }
                 SpectatorSessionMessage::InitRound(round_initial) => return Ok(round_initial),
                 SpectatorSessionMessage::Inputs(..) => continue,
+                SpectatorSessionMessage::Leave => return Err(SessionError::SpectatorHostLeft),
             }
         }
     }
 
-    pub fn dequeue_inputs(&mut self) -> Result<(u16, u16), RecvError> {
+    pub fn dequeue_inputs(&mut self) -> Result<(u16, u16), SessionError> {
         if self.round_initial.is_some() {
             return Ok((0, 0));
         }
-        match self.hook_incoming_rx.recv()? {
-            SpectatorSessionMessage::InitSpectator(init) => {
-                error!("unexpected init spectator message: {:?}", init);
-                Err(RecvError)
-            }
+        match self.recv()? {
+            msg @ (SpectatorSessionMessage::InitSpectator(_)
+            | SpectatorSessionMessage::CatchUp(_)
+            | SpectatorSessionMessage::Chat(..)) => Err(SessionError::unexpected_message(&msg)),
             SpectatorSessionMessage::InitRound(round_initial) => {
                 self.round_initial = Some(round_initial);
                 Ok((0, 0))
             }
             SpectatorSessionMessage::Inputs(p1, p2) => Ok((p1, p2)),
+            SpectatorSessionMessage::Leave => Err(SessionError::SpectatorHostLeft),
         }
     }
 }
@@ -4,8 +4,9 @@ use junowen_lib::connection::{DataChannel, PeerConnection};
 use tracing::info;
 
 use super::{
-    spectator::{SpectatorInitial, SpectatorSessionMessage},
-    to_channel, session_message::RoundInitial,
+    session_message::{ChatMessage, RoundInitial},
+    spectator::{BufferedMessage, SpectatorInitial, SpectatorSessionMessage},
+    to_channel,
 };
 
 #[derive(CopyGetters, Getters, Setters)]
@@ -36,6 +37,18 @@ impl SpectatorHostSession {
             .send(SpectatorSessionMessage::InitRound(init))?)
     }
 
+    pub fn send_catch_up(&self, messages: Vec<BufferedMessage>) -> Result<()> {
+        Ok(self
+            .hook_outgoing_tx
+            .send(SpectatorSessionMessage::CatchUp(messages))?)
+    }
+
+    pub fn send_chat(&self, p1: bool, chat: ChatMessage) -> Result<()> {
+        Ok(self
+            .hook_outgoing_tx
+            .send(SpectatorSessionMessage::Chat(p1, chat))?)
+    }
+
     pub fn send_inputs(&self, p1_input: u16, p2_input: u16) -> Result<()> {
         Ok(self
             .hook_outgoing_tx
@@ -45,6 +58,8 @@ impl SpectatorHostSession {
 
 impl Drop for SpectatorHostSession {
     fn drop(&mut self) {
+        // 届かなくても観戦者側は切断として扱うので、結果は見ない
+        let _ = self.hook_outgoing_tx.send(SpectatorSessionMessage::Leave);
         info!("spectator session host closed");
     }
 }
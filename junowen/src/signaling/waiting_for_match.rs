@@ -1,3 +1,6 @@
+mod public_rooms;
+mod quick_match_socket;
+mod reconnection;
 mod reserved_room_opponent_socket;
 mod reserved_room_spectator_host_socket;
 mod reserved_room_spectator_socket;
@@ -11,6 +14,8 @@ use tokio::sync::mpsc;
 
 use crate::session::{battle::BattleSession, spectator::SpectatorSession};
 
+pub use public_rooms::FetchingPublicRooms;
+pub use reconnection::reconnect;
 pub use waiting_for_spectator::{WaitingForPureP2pSpectator, WaitingForSpectator};
 pub use waiting_in_room::{
     WaitingForOpponentInReservedRoom, WaitingForOpponentInSharedRoom,
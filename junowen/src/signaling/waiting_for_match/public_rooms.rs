@@ -0,0 +1,45 @@
+use anyhow::{anyhow, bail, Result};
+use junowen_lib::signaling_server::public_room::{GetPublicRoomsResponse, PublicRoom};
+use reqwest::StatusCode;
+use tokio::sync::oneshot::{self, error::TryRecvError};
+use tracing::info;
+
+use crate::TOKIO_RUNTIME;
+
+use super::socket::{client, origin};
+
+async fn get_public_rooms() -> Result<Vec<PublicRoom>> {
+    let url = format!("{}/public-rooms", origin());
+    info!("GET {}", url);
+    let res = client().get(&url).send().await?;
+    // 一覧は定期的に取り直すので、送り直さずに諦める
+    if res.status() == StatusCode::TOO_MANY_REQUESTS {
+        bail!("too many requests");
+    }
+    let res = GetPublicRoomsResponse::parse(res.status(), res.text().await.ok().as_deref())?;
+    let GetPublicRoomsResponse::Ok(body) = res;
+    Ok(body.into_rooms())
+}
+
+/// 公開ルーム一覧の取得
+pub struct FetchingPublicRooms {
+    rx: oneshot::Receiver<Result<Vec<PublicRoom>>>,
+}
+
+impl FetchingPublicRooms {
+    pub fn start() -> Self {
+        let (tx, rx) = oneshot::channel();
+        TOKIO_RUNTIME.spawn(async move {
+            let _ = tx.send(get_public_rooms().await);
+        });
+        Self { rx }
+    }
+
+    pub fn try_recv(&mut self) -> Option<Result<Vec<PublicRoom>>> {
+        match self.rx.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Closed) => Some(Err(anyhow!("canceled"))),
+        }
+    }
+}
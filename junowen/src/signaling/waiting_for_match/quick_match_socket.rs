@@ -0,0 +1,128 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use junowen_lib::{
+    connection::signaling::{
+        socket::{OfferResponse, SignalingSocket},
+        CompressedSdp,
+    },
+    signaling_server::{
+        custom::{PostSharedRoomKeepRequestBody, PostSharedRoomKeepResponse},
+        quick_match::{PostQuickMatchRequestBody, PostQuickMatchResponse, QuickMatchFilter},
+        room::{PostRoomJoinRequestBody, PostRoomJoinResponse},
+    },
+};
+use tokio::sync::watch;
+use tracing::info;
+
+use super::{
+    encode_room_name,
+    socket::{client, retry_after, send, sleep_or_abort_and_delete_room},
+};
+
+/// 待ち行列で決まったルームで、以降は共有ルームと同じ手順で応答を交換する
+pub struct SignalingServerQuickMatchSocket {
+    client: reqwest::Client,
+    origin: String,
+    filter: QuickMatchFilter,
+    room_url: Option<String>,
+    abort_rx: watch::Receiver<bool>,
+}
+
+impl SignalingServerQuickMatchSocket {
+    pub fn new(origin: String, filter: QuickMatchFilter, abort_rx: watch::Receiver<bool>) -> Self {
+        Self {
+            client: client(),
+            origin,
+            filter,
+            room_url: None,
+            abort_rx,
+        }
+    }
+
+    async fn sleep_or_abort_and_delete_room(
+        &mut self,
+        retry_after: u32,
+        url: &str,
+        key: &str,
+    ) -> Result<()> {
+        sleep_or_abort_and_delete_room(retry_after, &mut self.abort_rx, &self.client, url, key)
+            .await
+    }
+}
+
+#[async_trait]
+impl SignalingSocket for SignalingServerQuickMatchSocket {
+    fn timeout() -> Duration {
+        Duration::from_secs(10)
+    }
+
+    async fn offer(&mut self, desc: CompressedSdp) -> Result<OfferResponse> {
+        let url = format!("{}/quick-match", self.origin);
+        let json = PostQuickMatchRequestBody::new(desc, self.filter.clone());
+        info!("POST {}", url);
+        let res = send(self.client.post(&url).json(&json), &mut self.abort_rx).await?;
+        let status = res.status();
+        let body_retry_after = retry_after(&res);
+        let body = res.text().await.ok();
+        let res = PostQuickMatchResponse::parse(status, body_retry_after, body.as_deref())?;
+        info!("{:?}", res);
+        let (room_url, key) = match res {
+            PostQuickMatchResponse::Matched(body) => {
+                let (room_name, offer) = body.into_inner();
+                let room_name = encode_room_name(&room_name);
+                self.room_url = Some(format!("{}/custom/{}", self.origin, room_name));
+                return Ok(OfferResponse::Offer(offer));
+            }
+            PostQuickMatchResponse::Waiting { retry_after, body } => {
+                let (room_name, key) = body.into_inner();
+                let room_name = encode_room_name(&room_name);
+                let room_url = format!("{}/custom/{}", self.origin, room_name);
+                self.sleep_or_abort_and_delete_room(retry_after, &room_url, &key)
+                    .await?;
+                (room_url, key)
+            }
+        };
+
+        let url = format!("{}/keep", room_url);
+        let body = PostSharedRoomKeepRequestBody::new(key.clone());
+        loop {
+            info!("POST {}", url);
+            let res = send(self.client.post(&url).json(&body), &mut self.abort_rx).await?;
+            let status = res.status();
+            let retry_after = retry_after(&res);
+            let body = res.text().await.ok();
+            let res = PostSharedRoomKeepResponse::parse(status, retry_after, body.as_deref())?;
+            info!("{:?}", res);
+            match res {
+                PostSharedRoomKeepResponse::BadRequest => {
+                    bail!("bad request")
+                }
+                PostSharedRoomKeepResponse::Ok(body) => {
+                    return Ok(OfferResponse::Answer(body.into_answer()));
+                }
+                PostSharedRoomKeepResponse::NoContent { retry_after } => {
+                    self.sleep_or_abort_and_delete_room(retry_after, &room_url, &key)
+                        .await?;
+                }
+            }
+        }
+    }
+
+    async fn answer(&mut self, desc: CompressedSdp) -> Result<()> {
+        let room_url = self
+            .room_url
+            .as_ref()
+            .ok_or_else(|| anyhow!("not matched"))?;
+        let url = format!("{}/join", room_url);
+        let json = PostRoomJoinRequestBody::new(desc);
+        let res = send(self.client.post(url).json(&json), &mut self.abort_rx).await?;
+        let res = PostRoomJoinResponse::parse(res.status())?;
+        match res {
+            PostRoomJoinResponse::Ok => Ok(()),
+            PostRoomJoinResponse::Conflict => bail!("opponent is already matched"),
+            PostRoomJoinResponse::Forbidden => bail!("forbidden"),
+        }
+    }
+}
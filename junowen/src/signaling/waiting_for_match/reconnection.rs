@@ -0,0 +1,36 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use junowen_lib::connection::{signaling::socket::SignalingSocket, DataChannel, PeerConnection};
+use tokio::{
+    sync::watch,
+    time::{sleep, timeout},
+};
+use tracing::info;
+
+use super::{shared_room_opponent_socket::SignalingServerSharedRoomOpponentSocket, socket::origin};
+
+const RECONNECTION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 切断された対戦相手と、セッション開始時に共有した合言葉の部屋で繋ぎ直す。
+/// シグナリングサーバーを経由したセッションでのみ使える
+pub async fn reconnect(resume_token: &str) -> Result<(PeerConnection, DataChannel)> {
+    let room_name = format!("resume-{}", resume_token);
+    let (_abort_tx, abort_rx) = watch::channel(false);
+    let mut socket =
+        SignalingServerSharedRoomOpponentSocket::new(origin().into(), &room_name, false, abort_rx);
+    let task = async {
+        loop {
+            match socket.receive_signaling().await {
+                Ok((conn, dc, _host)) => return (conn, dc),
+                Err(err) => {
+                    info!("Reconnection failed: {}", err);
+                    sleep(Duration::from_secs(1)).await;
+                }
+            }
+        }
+    };
+    timeout(RECONNECTION_TIMEOUT, task)
+        .await
+        .map_err(|_| anyhow!("reconnection timed out"))
+}
@@ -10,9 +10,10 @@ use junowen_lib::{
     signaling_server::{
         reserved_room::{
             PostReservedRoomKeepRequestBody, PostReservedRoomKeepResponse,
-            PostReservedRoomKeepResponseOkBody, PutReservedRoomResponse,
+            PostReservedRoomKeepResponseOkBody, PutReservedRoomRequestBody,
+            PutReservedRoomResponse,
         },
-        room::{PostRoomJoinRequestBody, PostRoomJoinResponse, PutRoomRequestBody},
+        room::{PostRoomJoinRequestBody, PostRoomJoinResponse},
     },
 };
 use tokio::sync::watch;
@@ -20,22 +21,35 @@ use tracing::info;
 
 use super::{
     encode_room_name,
-    socket::{retry_after, sleep_or_abort_and_delete_room},
+    socket::{client, retry_after, send, sleep_or_abort_and_delete_room, with_room_password},
 };
 
 pub struct SignalingServerReservedRoomOpponentSocket {
     client: reqwest::Client,
     resource_url: String,
+    public: bool,
+    player_password: Option<String>,
+    spectator_password: Option<String>,
     key: Option<String>,
     abort_rx: watch::Receiver<bool>,
 }
 
 impl SignalingServerReservedRoomOpponentSocket {
-    pub fn new(origin: String, room_name: &str, abort_rx: watch::Receiver<bool>) -> Self {
+    pub fn new(
+        origin: String,
+        room_name: &str,
+        public: bool,
+        player_password: Option<String>,
+        spectator_password: Option<String>,
+        abort_rx: watch::Receiver<bool>,
+    ) -> Self {
         let encoded_room_name = encode_room_name(room_name);
         Self {
-            client: reqwest::Client::new(),
+            client: client(),
             resource_url: format!("{}/reserved-room/{}", origin, encoded_room_name),
+            public,
+            player_password,
+            spectator_password,
             key: None,
             abort_rx,
         }
@@ -60,9 +74,14 @@ impl SignalingSocket for SignalingServerReservedRoomOpponentSocket {
 
     async fn offer(&mut self, desc: CompressedSdp) -> Result<OfferResponse> {
         let url = &self.resource_url;
-        let json = PutRoomRequestBody::new(desc);
+        let json = PutReservedRoomRequestBody::new(
+            desc,
+            self.public,
+            self.player_password.clone(),
+            self.spectator_password.clone(),
+        );
         info!("PUT {}", url);
-        let res = self.client.put(url).json(&json).send().await?;
+        let res = send(self.client.put(url).json(&json), &mut self.abort_rx).await?;
         info!("{:?}", res);
         let res =
             PutReservedRoomResponse::parse(res.status(), retry_after(&res), &res.text().await?)?;
@@ -73,6 +92,12 @@ impl SignalingSocket for SignalingServerReservedRoomOpponentSocket {
                 };
                 return Ok(OfferResponse::Offer(offer));
             }
+            PutReservedRoomResponse::Incompatible { body, .. } => {
+                bail!(body.into_error());
+            }
+            PutReservedRoomResponse::Forbidden { .. } => {
+                bail!("wrong password");
+            }
             PutReservedRoomResponse::CreatedWithAnswer { body, .. } => {
                 return Ok(OfferResponse::Answer(body.into_answer()));
             }
@@ -86,10 +111,10 @@ impl SignalingSocket for SignalingServerReservedRoomOpponentSocket {
         self.key = Some(key.clone());
 
         let url = format!("{}/keep", self.resource_url);
-        let body = PostReservedRoomKeepRequestBody::new(key.clone(), None);
+        let body = PostReservedRoomKeepRequestBody::new(key.clone(), None, None);
         loop {
             info!("POST {}", url);
-            let res = self.client.post(&url).json(&body).send().await?;
+            let res = send(self.client.post(&url).json(&body), &mut self.abort_rx).await?;
             info!("{:?}", res);
             let status = res.status();
             let retry_after = retry_after(&res);
@@ -117,11 +142,14 @@ impl SignalingSocket for SignalingServerReservedRoomOpponentSocket {
     async fn answer(&mut self, desc: CompressedSdp) -> Result<()> {
         let url = format!("{}/join", self.resource_url);
         let json = PostRoomJoinRequestBody::new(desc);
-        let res = self.client.post(url).json(&json).send().await?;
+        let req = self.client.post(url).json(&json);
+        let req = with_room_password(req, self.player_password.as_deref());
+        let res = send(req, &mut self.abort_rx).await?;
         let res = PostRoomJoinResponse::parse(res.status())?;
         match res {
             PostRoomJoinResponse::Ok => Ok(()),
             PostRoomJoinResponse::Conflict => bail!("room is full"),
+            PostRoomJoinResponse::Forbidden => bail!("wrong password"),
         }
     }
 }
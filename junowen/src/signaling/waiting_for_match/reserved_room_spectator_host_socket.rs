@@ -18,12 +18,16 @@ use tracing::info;
 
 use crate::signaling::waiting_for_match::socket::retry_after;
 
-use super::{socket::sleep_or_abort_and_delete_room, encode_room_name};
+use super::{
+    encode_room_name,
+    socket::{client, send, sleep_or_abort_and_delete_room},
+};
 
 pub struct SignalingServerReservedRoomSpectatorHostSocket {
     client: reqwest::Client,
     resource_url: String,
     key: String,
+    spectator_id: String,
     abort_rx: watch::Receiver<bool>,
 }
 
@@ -32,13 +36,15 @@ impl SignalingServerReservedRoomSpectatorHostSocket {
         origin: String,
         room_name: &str,
         key: String,
+        spectator_id: String,
         abort_rx: watch::Receiver<bool>,
     ) -> Self {
         let encoded_room_name = encode_room_name(room_name);
         Self {
-            client: reqwest::Client::new(),
+            client: client(),
             resource_url: format!("{}/reserved-room/{}", origin, encoded_room_name),
             key,
+            spectator_id,
             abort_rx,
         }
     }
@@ -65,9 +71,13 @@ impl SignalingSocket for SignalingServerReservedRoomSpectatorHostSocket {
         let url = format!("{}/keep", self.resource_url);
         let mut desc = Some(desc);
         loop {
-            let body = PostReservedRoomKeepRequestBody::new(key.clone(), desc.take());
+            let body = PostReservedRoomKeepRequestBody::new(
+                key.clone(),
+                desc.take(),
+                Some(self.spectator_id.clone()),
+            );
             info!("POST {}", url);
-            let res = self.client.post(&url).json(&body).send().await?;
+            let res = send(self.client.post(&url).json(&body), &mut self.abort_rx).await?;
             let status = res.status();
             let retry_after = retry_after(&res);
             let body = res.text().await.ok();
@@ -18,7 +18,7 @@ use tracing::info;
 
 use super::{
     encode_room_name,
-    socket::{retry_after, sleep_or_abort},
+    socket::{client, retry_after, send, sleep_or_abort, with_room_password},
 };
 
 #[derive(Error, Debug)]
@@ -27,20 +27,34 @@ pub enum SignalingServerReservedRoomSpectatorSocketError {
     RoomNotFound,
     #[error("match is not started")]
     MatchIsNotStarted,
+    #[error("wrong password")]
+    WrongPassword,
+    #[error("spectator slot is already taken")]
+    SlotTaken,
 }
 
 pub struct SignalingServerReservedRoomSpectatorSocket {
     client: reqwest::Client,
     resource_url: String,
+    password: Option<String>,
+    /// 応答する観戦枠の ID
+    spectator_id: Option<String>,
     abort_rx: watch::Receiver<bool>,
 }
 
 impl SignalingServerReservedRoomSpectatorSocket {
-    pub fn new(origin: String, room_name: &str, abort_rx: watch::Receiver<bool>) -> Self {
+    pub fn new(
+        origin: String,
+        room_name: &str,
+        password: Option<String>,
+        abort_rx: watch::Receiver<bool>,
+    ) -> Self {
         let encoded_room_name = encode_room_name(room_name);
         Self {
-            client: reqwest::Client::new(),
+            client: client(),
             resource_url: format!("{}/reserved-room/{}", origin, encoded_room_name),
+            password,
+            spectator_id: None,
             abort_rx,
         }
     }
@@ -55,7 +69,9 @@ impl SignalingSocket for SignalingServerReservedRoomSpectatorSocket {
     async fn offer(&mut self, _desc: CompressedSdp) -> Result<OfferResponse> {
         loop {
             info!("GET {}", self.resource_url);
-            let res = self.client.get(&self.resource_url).send().await?;
+            let req = self.client.get(&self.resource_url);
+            let req = with_room_password(req, self.password.as_deref());
+            let res = send(req, &mut self.abort_rx).await?;
             info!("{:?}", res);
             let retry_after = retry_after(&res)
                 .ok_or_else(|| Error::msg("retry-after header not found in response"))?;
@@ -65,11 +81,20 @@ impl SignalingSocket for SignalingServerReservedRoomSpectatorSocket {
                 GetReservedRoomResponse::NotFound => {
                     bail!(SignalingServerReservedRoomSpectatorSocketError::RoomNotFound);
                 }
+                GetReservedRoomResponse::Incompatible(body) => {
+                    bail!(body.into_error());
+                }
+                GetReservedRoomResponse::Forbidden => {
+                    bail!(SignalingServerReservedRoomSpectatorSocketError::WrongPassword);
+                }
                 GetReservedRoomResponse::Ok(body) => {
                     if body.opponent_offer().is_some() {
                         bail!(SignalingServerReservedRoomSpectatorSocketError::MatchIsNotStarted);
                     }
-                    if let Some(spectator_offer) = body.into_spectator_offer() {
+                    if let Some((spectator_id, spectator_offer)) =
+                        body.into_spectator_offers().into_iter().next()
+                    {
+                        self.spectator_id = spectator_id;
                         return Ok(OfferResponse::Offer(spectator_offer));
                     };
                     sleep_or_abort(retry_after, &mut self.abort_rx).await?;
@@ -81,17 +106,26 @@ impl SignalingSocket for SignalingServerReservedRoomSpectatorSocket {
 
     async fn answer(&mut self, desc: CompressedSdp) -> Result<()> {
         let url = format!("{}/spectate", self.resource_url);
-        let json = PostReservedRoomSpectateRequestBody::new(desc);
+        let json = PostReservedRoomSpectateRequestBody::new(desc, self.spectator_id.clone());
         loop {
-            let res = self.client.post(&url).json(&json).send().await?;
+            let req = self.client.post(&url).json(&json);
+            let req = with_room_password(req, self.password.as_deref());
+            let res = send(req, &mut self.abort_rx).await?;
             let retry_after = retry_after(&res)
                 .ok_or_else(|| Error::msg("retry-after header not found in response"))?;
             let res = PostReservedRoomSpectateResponse::parse(res.status())?;
             match res {
                 PostReservedRoomSpectateResponse::Ok => return Ok(()),
                 PostReservedRoomSpectateResponse::Conflict => {
+                    // 他の観戦者が先に入った観戦枠はもう空かないので、オファーから取り直す
+                    if self.spectator_id.is_some() {
+                        bail!(SignalingServerReservedRoomSpectatorSocketError::SlotTaken);
+                    }
                     sleep_or_abort(retry_after, &mut self.abort_rx).await?;
                 }
+                PostReservedRoomSpectateResponse::Forbidden => {
+                    bail!(SignalingServerReservedRoomSpectatorSocketError::WrongPassword);
+                }
             }
         }
     }
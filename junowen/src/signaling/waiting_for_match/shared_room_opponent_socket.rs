@@ -19,21 +19,28 @@ use tracing::info;
 
 use super::{
     encode_room_name,
-    socket::{retry_after, sleep_or_abort_and_delete_room},
+    socket::{client, retry_after, send, sleep_or_abort_and_delete_room},
 };
 
 pub struct SignalingServerSharedRoomOpponentSocket {
     client: reqwest::Client,
     resource_url: String,
+    public: bool,
     abort_rx: watch::Receiver<bool>,
 }
 
 impl SignalingServerSharedRoomOpponentSocket {
-    pub fn new(origin: String, room_name: &str, abort_rx: watch::Receiver<bool>) -> Self {
+    pub fn new(
+        origin: String,
+        room_name: &str,
+        public: bool,
+        abort_rx: watch::Receiver<bool>,
+    ) -> Self {
         let encoded_room_name = encode_room_name(room_name);
         Self {
-            client: reqwest::Client::new(),
+            client: client(),
             resource_url: format!("{}/custom/{}", origin, encoded_room_name),
+            public,
             abort_rx,
         }
     }
@@ -53,9 +60,9 @@ impl SignalingSocket for SignalingServerSharedRoomOpponentSocket {
 
     async fn offer(&mut self, desc: CompressedSdp) -> Result<OfferResponse> {
         let url = &self.resource_url;
-        let json = PutRoomRequestBody::new(desc);
+        let json = PutRoomRequestBody::new(desc, self.public);
         info!("PUT {}", url);
-        let res = self.client.put(url).json(&json).send().await?;
+        let res = send(self.client.put(url).json(&json), &mut self.abort_rx).await?;
         let res =
             PutSharedRoomResponse::parse(res.status(), retry_after(&res), &res.text().await?)?;
         info!("{:?}", res);
@@ -63,6 +70,12 @@ impl SignalingSocket for SignalingServerSharedRoomOpponentSocket {
             PutSharedRoomResponse::Conflict { body, .. } => {
                 return Ok(OfferResponse::Offer(body.into_offer()))
             }
+            PutSharedRoomResponse::Incompatible { body, .. } => {
+                bail!(body.into_error());
+            }
+            PutSharedRoomResponse::Forbidden { .. } => {
+                bail!("forbidden");
+            }
             PutSharedRoomResponse::CreatedWithAnswer { body, .. } => {
                 return Ok(OfferResponse::Answer(body.into_answer()));
             }
@@ -78,7 +91,7 @@ impl SignalingSocket for SignalingServerSharedRoomOpponentSocket {
         let body = PostSharedRoomKeepRequestBody::new(key.clone());
         loop {
             info!("POST {}", url);
-            let res = self.client.post(&url).json(&body).send().await?;
+            let res = send(self.client.post(&url).json(&body), &mut self.abort_rx).await?;
             let status = res.status();
             let retry_after = retry_after(&res);
             let body = res.text().await.ok();
@@ -102,11 +115,12 @@ impl SignalingSocket for SignalingServerSharedRoomOpponentSocket {
     async fn answer(&mut self, desc: CompressedSdp) -> Result<()> {
         let url = format!("{}/join", self.resource_url);
         let json = PostRoomJoinRequestBody::new(desc);
-        let res = self.client.post(url).json(&json).send().await?;
+        let res = send(self.client.post(url).json(&json), &mut self.abort_rx).await?;
         let res = PostRoomJoinResponse::parse(res.status())?;
         match res {
             PostRoomJoinResponse::Ok => Ok(()),
             PostRoomJoinResponse::Conflict => bail!("room is full"),
+            PostRoomJoinResponse::Forbidden => bail!("forbidden"),
         }
     }
 }
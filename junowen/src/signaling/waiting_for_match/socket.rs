@@ -2,11 +2,36 @@ use std::time::Duration;
 
 use anyhow::{bail, Result};
 
-use junowen_lib::signaling_server::room::DeleteRoomRequestBody;
-use reqwest::{header::RETRY_AFTER, Response};
+use junowen_lib::signaling_server::{
+    reserved_room::ROOM_PASSWORD_HEADER, room::DeleteRoomRequestBody, version::ClientVersion,
+};
+use reqwest::{header::RETRY_AFTER, RequestBuilder, Response, StatusCode};
 use tokio::{sync::watch, time::sleep};
 use tracing::info;
 
+pub fn origin() -> &'static str {
+    if cfg!(debug_assertions) {
+        "https://qayvs4nki2nl72kf4tn5h5yati0maxpe.lambda-url.ap-northeast-1.on.aws"
+    } else {
+        "https://wxvo3rgklveqwyig4b3q5qupbq0mgvik.lambda-url.ap-northeast-1.on.aws"
+    }
+}
+
+/// サーバーが互換性の無い相手を弾けるように、バージョンをヘッダーで申告する
+pub fn client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .default_headers(ClientVersion::current().to_headers())
+        .build()
+        .unwrap()
+}
+
+pub fn with_room_password(req: RequestBuilder, password: Option<&str>) -> RequestBuilder {
+    let Some(password) = password else {
+        return req;
+    };
+    req.header(ROOM_PASSWORD_HEADER, password)
+}
+
 pub fn retry_after(res: &Response) -> Option<u32> {
     res.headers()
         .get(RETRY_AFTER)
@@ -24,6 +49,21 @@ pub async fn sleep_or_abort(retry_after: u32, abort_rx: &mut watch::Receiver<boo
     bail!("abort");
 }
 
+/// サーバーに制限されたら Retry-After の間待ってから送り直す
+pub async fn send(req: RequestBuilder, abort_rx: &mut watch::Receiver<bool>) -> Result<Response> {
+    loop {
+        let res = req.try_clone().unwrap().send().await?;
+        if res.status() != StatusCode::TOO_MANY_REQUESTS {
+            return Ok(res);
+        }
+        let Some(retry_after) = retry_after(&res) else {
+            bail!("too many requests");
+        };
+        info!("Too many requests, retry after {} sec", retry_after);
+        sleep_or_abort(retry_after, abort_rx).await?;
+    }
+}
+
 pub async fn sleep_or_abort_and_delete_room(
     retry_after: u32,
     abort_rx: &mut watch::Receiver<bool>,
@@ -7,7 +7,7 @@ use junowen_lib::{
         parse_signaling_code, socket::async_read_write_socket::SignalingServerMessage,
         SignalingCodeType,
     },
-    structs::app::{MainMenu, ScreenId},
+    signaling_server::reserved_room::MAX_SPECTATOR_SLOTS,
     Th19,
 };
 use tokio::sync::mpsc::{self, error::TryRecvError};
@@ -38,67 +38,36 @@ fn try_start_signaling(th19: &Th19) -> Option<WaitingForPureP2pSpectator> {
     Some(WaitingForPureP2pSpectator::SignalingCodeRecved {
         signaling,
         session_rx,
-        ready: false,
         pushed: true,
     })
 }
 
 pub enum WaitingForPureP2pSpectator {
     Standby {
-        ready: bool,
         pushed: bool,
     },
     SignalingCodeRecved {
         signaling: Signaling,
         session_rx: mpsc::Receiver<SpectatorHostSession>,
-        ready: bool,
         pushed: bool,
     },
     SignalingCodeSent {
         _signaling: Signaling,
         session_rx: mpsc::Receiver<SpectatorHostSession>,
-        ready: bool,
         pushed: bool,
     },
 }
 
 impl WaitingForPureP2pSpectator {
     pub fn standby() -> Self {
-        Self::Standby {
-            ready: false,
-            pushed: false,
-        }
+        Self::Standby { pushed: false }
     }
 
     fn dummy() -> Self {
-        Self::Standby {
-            ready: false,
-            pushed: false,
-        }
+        Self::Standby { pushed: false }
     }
 
-    fn set_ready(&mut self, value: bool) {
-        match self {
-            Self::Standby { ready, .. }
-            | Self::SignalingCodeRecved { ready, .. }
-            | Self::SignalingCodeSent { ready, .. } => *ready = value,
-        }
-    }
-
-    fn update_inner(
-        &mut self,
-        current_pushed: bool,
-        main_menu: Option<&MainMenu>,
-        th19: &Th19,
-    ) -> Result<()> {
-        let selection = th19.selection();
-        self.set_ready(
-            main_menu.is_some()
-                && main_menu.unwrap().screen_id() == ScreenId::DifficultySelect
-                && selection.p1().card == 0
-                && selection.p2().card == 0,
-        );
-
+    fn update_inner(&mut self, current_pushed: bool, th19: &Th19) -> Result<()> {
         match self {
             Self::Standby { pushed, .. } => {
                 let prev_pushed = *pushed;
@@ -132,7 +101,6 @@ impl WaitingForPureP2pSpectator {
                 let Self::SignalingCodeRecved {
                     signaling,
                     session_rx,
-                    ready,
                     pushed,
                 } = mem::replace(self, Self::dummy())
                 else {
@@ -141,7 +109,6 @@ impl WaitingForPureP2pSpectator {
                 *self = Self::SignalingCodeSent {
                     _signaling: signaling,
                     session_rx,
-                    ready,
                     pushed,
                 };
                 Ok(())
@@ -159,32 +126,33 @@ impl WaitingForPureP2pSpectator {
         }
     }
 
-    pub fn update(&mut self, pushed: bool, menu: Option<&MainMenu>, th19: &Th19) {
-        if let Err(err) = self.update_inner(pushed, menu, th19) {
+    pub fn update(&mut self, pushed: bool, th19: &Th19) {
+        if let Err(err) = self.update_inner(pushed, th19) {
             info!("spectator host error: {:?}", err);
-            *self = Self::Standby {
-                ready: false,
-                pushed,
-            };
+            *self = Self::Standby { pushed };
         }
     }
 }
 
 pub enum WaitingForSpectator {
     PureP2p(WaitingForPureP2pSpectator),
-    ReservedRoom(WaitingForSpectatorInReservedRoom),
+    /// 観戦枠ごとに並行して待ち受ける
+    ReservedRoom(Vec<WaitingForSpectatorInReservedRoom>),
 }
 
 impl WaitingForSpectator {
-    pub fn try_recv_session(
-        &mut self,
-        pushed: bool,
-        menu: Option<&MainMenu>,
-        th19: &Th19,
-    ) -> Option<SpectatorHostSession> {
+    pub fn reserved_room(room_name: &str, key: String) -> Self {
+        Self::ReservedRoom(
+            (0..MAX_SPECTATOR_SLOTS)
+                .map(|_| WaitingForSpectatorInReservedRoom::new(room_name.to_owned(), key.clone()))
+                .collect(),
+        )
+    }
+
+    pub fn try_recv_session(&mut self, pushed: bool, th19: &Th19) -> Option<SpectatorHostSession> {
         match self {
             Self::PureP2p(waiting) => {
-                waiting.update(pushed, menu, th19);
+                waiting.update(pushed, th19);
                 match waiting {
                     WaitingForPureP2pSpectator::Standby { .. }
                     | WaitingForPureP2pSpectator::SignalingCodeRecved { .. } => None,
@@ -203,13 +171,11 @@ impl WaitingForSpectator {
                     }
                 }
             }
-            Self::ReservedRoom(waiting) => match waiting.try_session_and_waiting_for_spectator() {
-                Ok((session, waiting)) => {
-                    *self = waiting;
-                    Some(session)
-                }
-                Err(_) => None,
-            },
+            Self::ReservedRoom(slots) => slots.iter_mut().find_map(|slot| {
+                let (session, next_slot) = slot.try_session_and_next_slot().ok()?;
+                *slot = next_slot;
+                Some(session)
+            }),
         }
     }
 }
@@ -2,7 +2,10 @@ use std::time::{Duration, Instant};
 
 use anyhow::Error;
 use getset::Getters;
-use junowen_lib::connection::{signaling::socket::SignalingSocket, DataChannel, PeerConnection};
+use junowen_lib::{
+    connection::{signaling::socket::SignalingSocket, DataChannel, PeerConnection},
+    signaling_server::quick_match::QuickMatchFilter,
+};
 use tokio::{
     sync::{
         mpsc::{self},
@@ -13,12 +16,14 @@ use tokio::{
     time::sleep,
 };
 use tracing::{debug, debug_span, info, Instrument};
+use uuid::Uuid;
 
 use crate::{
     session::{
         battle::BattleSession, spectator::SpectatorSession, spectator_host::SpectatorHostSession,
     },
     signaling::waiting_for_match::{
+        quick_match_socket::SignalingServerQuickMatchSocket,
         reserved_room_opponent_socket::SignalingServerReservedRoomOpponentSocket,
         reserved_room_spectator_host_socket::SignalingServerReservedRoomSpectatorHostSocket,
         shared_room_opponent_socket::SignalingServerSharedRoomOpponentSocket,
@@ -28,7 +33,8 @@ use crate::{
 };
 
 use super::{
-    reserved_room_spectator_socket::SignalingServerReservedRoomSpectatorSocket, WaitingForSpectator,
+    reserved_room_spectator_socket::SignalingServerReservedRoomSpectatorSocket, socket::origin,
+    WaitingForSpectator,
 };
 
 pub struct RoomKey(String);
@@ -73,12 +79,7 @@ where
         let handle = {
             let room_name = room_name.clone();
             TOKIO_RUNTIME.spawn(async move {
-                let origin = if cfg!(debug_assertions) {
-                    "https://qayvs4nki2nl72kf4tn5h5yati0maxpe.lambda-url.ap-northeast-1.on.aws"
-                } else {
-                    "https://wxvo3rgklveqwyig4b3q5qupbq0mgvik.lambda-url.ap-northeast-1.on.aws"
-                };
-                let mut socket = create_socket(origin.into(), &room_name, abort_rx.clone());
+                let mut socket = create_socket(origin().into(), &room_name, abort_rx.clone());
                 let (conn, dc, host) = loop {
                     match socket.receive_signaling().await {
                         Ok(ok) => break ok,
@@ -112,22 +113,49 @@ where
 }
 
 impl WaitingForOpponentInSharedRoom {
-    pub fn new(room_name: String) -> Self {
+    pub fn new(room_name: String, public: bool) -> Self {
         Self::internal_new(
-            SignalingServerSharedRoomOpponentSocket::new,
-            |pc, dc, host, _socket| BattleSession::new(pc, dc, host),
+            move |origin, room_name, abort_rx| {
+                SignalingServerSharedRoomOpponentSocket::new(origin, room_name, public, abort_rx)
+            },
+            |pc, dc, host, _socket| BattleSession::new_resumable(pc, dc, host),
             room_name,
         )
     }
+
+    /// ルーム名はサーバーが決めるので、表示用の名前を渡す
+    pub fn new_quick_match(display_name: String, filter: QuickMatchFilter) -> Self {
+        Self::internal_new(
+            move |origin, _room_name, abort_rx| {
+                SignalingServerQuickMatchSocket::new(origin, filter, abort_rx)
+            },
+            |pc, dc, host, _socket| BattleSession::new_resumable(pc, dc, host),
+            display_name,
+        )
+    }
 }
 
 impl WaitingForOpponentInReservedRoom {
-    pub fn new(room_name: String) -> Self {
+    pub fn new(
+        room_name: String,
+        public: bool,
+        player_password: Option<String>,
+        spectator_password: Option<String>,
+    ) -> Self {
         Self::internal_new(
-            SignalingServerReservedRoomOpponentSocket::new,
+            move |origin, room_name, abort_rx| {
+                SignalingServerReservedRoomOpponentSocket::new(
+                    origin,
+                    room_name,
+                    public,
+                    player_password,
+                    spectator_password,
+                    abort_rx,
+                )
+            },
             |conn, dc, host, socket| {
                 (
-                    BattleSession::new(conn, dc, host),
+                    BattleSession::new_resumable(conn, dc, host),
                     socket.into_key().map(RoomKey),
                 )
             },
@@ -142,8 +170,7 @@ impl WaitingForOpponentInReservedRoom {
             return Err(self);
         };
         let waiting = if let Some(key) = key {
-            let waiting = WaitingForSpectatorInReservedRoom::new(self.room_name.clone(), key.0);
-            WaitingForSpectator::ReservedRoom(waiting)
+            WaitingForSpectator::reserved_room(&self.room_name, key.0)
         } else {
             WaitingForSpectator::PureP2p(WaitingForPureP2pSpectator::standby())
         };
@@ -152,11 +179,17 @@ impl WaitingForOpponentInReservedRoom {
 }
 
 impl WaitingForSpectatorInReservedRoom {
+    /// 観戦枠を 1 つ待ち受ける
     pub fn new(room_name: String, key: String) -> Self {
+        let spectator_id = Uuid::new_v4().to_string();
         Self::internal_new(
             |origin, room_name, abort_rx| {
                 SignalingServerReservedRoomSpectatorHostSocket::new(
-                    origin, room_name, key, abort_rx,
+                    origin,
+                    room_name,
+                    key,
+                    spectator_id,
+                    abort_rx,
                 )
             },
             |conn, dc, _host, socket| {
@@ -169,21 +202,23 @@ impl WaitingForSpectatorInReservedRoom {
         )
     }
 
-    pub fn try_session_and_waiting_for_spectator(
+    /// 観戦者が繋がったら、代わりに待ち受ける新しい観戦枠と一緒に返す
+    pub fn try_session_and_next_slot(
         &mut self,
-    ) -> Result<(SpectatorHostSession, WaitingForSpectator), TryRecvError> {
+    ) -> Result<(SpectatorHostSession, WaitingForSpectatorInReservedRoom), TryRecvError> {
         let (session, key) = self.session_rx.try_recv()?;
         let waiting = WaitingForSpectatorInReservedRoom::new(self.room_name.clone(), key.0);
-        let waiting = WaitingForSpectator::ReservedRoom(waiting);
         Ok((session, waiting))
     }
 }
 
 impl WaitingForSpectatorHostInReservedRoom {
-    pub fn new(room_name: String) -> Self {
+    pub fn new(room_name: String, password: Option<String>) -> Self {
         Self::internal_new(
             |origin, room_name, abort_rx| {
-                SignalingServerReservedRoomSpectatorSocket::new(origin, room_name, abort_rx)
+                SignalingServerReservedRoomSpectatorSocket::new(
+                    origin, room_name, password, abort_rx,
+                )
             },
             |pc, dc, host, _socket| {
                 assert!(!host);
@@ -2,23 +2,34 @@ mod battle_session_state;
 mod junowen_state;
 mod prepare;
 mod render_parts;
+mod spectator_host;
 mod spectator_session_state;
 
-use std::{ffi::c_void, fmt::Display};
+use std::{
+    ffi::c_void,
+    time::{Duration, Instant},
+};
 
 use getset::{Getters, MutGetters};
 use junowen_lib::{
     structs::{others::RenderingText, selection::Selection},
     Fn011560, Fn0b7d40, Fn0d5ae0, Fn10f720, Th19,
 };
-use tracing::debug;
+use tracing::warn;
 
-use self::junowen_state::JunowenState;
+use self::{
+    junowen_state::JunowenState, render_parts::render_session_aborted,
+    spectator_host::SpectatorHostOptions,
+};
 use crate::{
     file::{Features, SettingsRepo},
     in_game_lobby::{Lobby, TitleMenuModifier},
+    session::SessionError,
 };
 
+/// セッションが中断された理由を表示しておく時間
+const ABORTED_REASON_DURATION: Duration = Duration::from_secs(10);
+
 #[derive(Getters, MutGetters)]
 pub struct State {
     features: Vec<Features>,
@@ -27,31 +38,49 @@ pub struct State {
     title_menu_modifier: TitleMenuModifier,
     lobby: Lobby,
     junowen_state: JunowenState,
+    aborted: Option<(SessionError, Instant)>,
+    /// リプレイを記録しないときは None
+    replay_dir: Option<String>,
+    spectator_host_options: SpectatorHostOptions,
 }
 
 impl State {
-    pub async fn new(settings_repo: SettingsRepo, th19: Th19) -> Self {
+    pub async fn new(settings_repo: SettingsRepo, th19: Th19, replay_dir: String) -> Self {
+        let features = settings_repo.features().await;
+        let replay_dir = features
+            .contains(&Features::RecordReplays)
+            .then_some(replay_dir);
+        let spectator_host_options = SpectatorHostOptions {
+            delay_frames: settings_repo.spectator_delay_frames().await,
+            share_chat: features.contains(&Features::ShareChatWithSpectators),
+        };
         Self {
-            features: settings_repo.features().await,
+            features,
             th19,
             title_menu_modifier: TitleMenuModifier::new(),
             lobby: Lobby::new(settings_repo),
             junowen_state: JunowenState::Standby,
+            aborted: None,
+            replay_dir,
+            spectator_host_options,
         }
     }
 
-    fn abort_session(&mut self, err: impl Display) {
-        debug!("session aborted: {}", err);
+    fn abort_session(&mut self, err: SessionError) {
+        warn!("session aborted: {}", err);
         self.junowen_state.abort_session(&mut self.th19);
         self.lobby.reset_depth();
+        self.aborted = Some((err, Instant::now()));
     }
 
     pub fn on_input_players(&mut self) {
         let has_session = self.junowen_state.has_session();
-        match self
-            .junowen_state
-            .on_input_players(&mut self.th19, self.lobby.waiting_for_match_mut())
-        {
+        match self.junowen_state.on_input_players(
+            &mut self.th19,
+            self.lobby.waiting_for_match_mut(),
+            self.replay_dir.as_deref(),
+            self.spectator_host_options,
+        ) {
             Ok(_) => {
                 if has_session && self.junowen_state.has_session() {
                     self.lobby.reset_depth();
@@ -101,6 +130,11 @@ impl State {
             &self.lobby,
             text_renderer,
         );
+        if let Some((err, aborted_at)) = &self.aborted {
+            if !self.junowen_state.has_session() && aborted_at.elapsed() < ABORTED_REASON_DURATION {
+                render_session_aborted(&self.th19, text_renderer, err);
+            }
+        }
     }
 
     pub fn on_round_over(&mut self) {
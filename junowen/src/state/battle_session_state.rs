@@ -1,10 +1,11 @@
 mod battle_game;
 mod battle_select;
 mod in_session;
-mod spectator_host;
+mod rematch_prompt;
+mod replay_recorder;
 mod utils;
 
-use std::{ffi::c_void, mem, sync::mpsc::RecvError};
+use std::{ffi::c_void, mem};
 
 use anyhow::Result;
 use junowen_lib::{
@@ -14,15 +15,22 @@ use junowen_lib::{
 };
 
 use crate::{
-    file::Features, session::battle::BattleSession,
+    file::Features,
+    session::{battle::BattleSession, SessionError},
     signaling::waiting_for_match::WaitingForSpectator,
 };
 
 use self::in_session::RenderingStatus;
 
-use super::prepare::Prepare;
+use super::{
+    prepare::Prepare,
+    spectator_host::{SpectatorHostOptions, SpectatorHostState},
+};
 
-use {battle_game::BattleGame, battle_select::BattleSelect, spectator_host::SpectatorHostState};
+use {
+    battle_game::BattleGame, battle_select::BattleSelect, rematch_prompt::RematchPrompt,
+    replay_recorder::ReplayRecorder,
+};
 
 pub enum BattleSessionState {
     Null,
@@ -40,8 +48,13 @@ pub enum BattleSessionState {
 }
 
 impl BattleSessionState {
-    pub fn prepare(session: BattleSession, waiting: WaitingForSpectator) -> Self {
-        Self::Prepare(Prepare::new((session, SpectatorHostState::new(waiting))))
+    pub fn prepare(
+        session: BattleSession,
+        waiting: WaitingForSpectator,
+        spectator_host_options: SpectatorHostOptions,
+    ) -> Self {
+        let spectator_host_state = SpectatorHostState::new(waiting, spectator_host_options);
+        Self::Prepare(Prepare::new((session, spectator_host_state)))
     }
 
     pub fn game_settings(&self) -> Option<&GameSettings> {
@@ -61,18 +74,28 @@ impl BattleSessionState {
 
     pub fn change_to_select(&mut self) {
         let old = mem::replace(self, Self::Null);
-        let (session, spectator_host_state) = match old {
+        let (session, spectator_host_state, rematch_prompt) = match old {
             Self::Null => unreachable!(),
-            Self::Prepare(prepare) => prepare.inner_session(),
+            Self::Prepare(prepare) => {
+                let (session, spectator_host_state) = prepare.inner_session();
+                (session, spectator_host_state, None)
+            }
             Self::Select { .. } => unreachable!(),
             Self::GameLoading { .. } => unreachable!(),
             Self::Game { .. } => unreachable!(),
             Self::BackToSelect {
                 session,
                 spectator_host_state,
-            } => (session, spectator_host_state),
+            } => {
+                let rematch_prompt = RematchPrompt::new(&session);
+                (session, spectator_host_state, Some(rematch_prompt))
+            }
         };
-        *self = Self::Select(BattleSelect::new(session, spectator_host_state));
+        *self = Self::Select(BattleSelect::new(
+            session,
+            spectator_host_state,
+            rematch_prompt,
+        ));
     }
     pub fn change_to_game_loading(&mut self) {
         let old = mem::replace(self, Self::Null);
@@ -85,7 +108,7 @@ impl BattleSessionState {
             spectator_host_state,
         }
     }
-    pub fn change_to_game(&mut self) {
+    pub fn change_to_game(&mut self, th19: &Th19, replay_dir: Option<&str>) {
         let old = mem::replace(self, Self::Null);
         let Self::GameLoading {
             session,
@@ -94,7 +117,12 @@ impl BattleSessionState {
         else {
             unreachable!()
         };
-        *self = Self::Game(BattleGame::new(session, spectator_host_state));
+        let replay_recorder = replay_dir.map(|dir| ReplayRecorder::new(dir, th19, &session));
+        *self = Self::Game(BattleGame::new(
+            session,
+            spectator_host_state,
+            replay_recorder,
+        ));
     }
     pub fn change_to_back_to_select(&mut self) {
         let old = mem::replace(self, Self::Null);
@@ -108,7 +136,11 @@ impl BattleSessionState {
         }
     }
 
-    pub fn update_state(&mut self, th19: &Th19) -> Option<Option<&'static MainMenu>> {
+    pub fn update_state(
+        &mut self,
+        th19: &Th19,
+        replay_dir: Option<&str>,
+    ) -> Option<Option<&'static MainMenu>> {
         match self {
             Self::Null => unreachable!(),
             Self::Prepare(prepare) => {
@@ -138,7 +170,7 @@ impl BattleSessionState {
                 if !round_frame.is_first_frame() {
                     return Some(None);
                 }
-                self.change_to_game();
+                self.change_to_game(th19, replay_dir);
                 Some(None)
             }
             Self::Game { .. } => {
@@ -165,7 +197,7 @@ impl BattleSessionState {
         &mut self,
         menu: Option<&MainMenu>,
         th19: &mut Th19,
-    ) -> Result<(), RecvError> {
+    ) -> Result<(), SessionError> {
         match self {
             Self::Null => unreachable!(),
             Self::Prepare(prepare) => prepare.update_th19_on_input_players(th19),
@@ -177,7 +209,7 @@ impl BattleSessionState {
         Ok(())
     }
 
-    pub fn on_input_menu(&mut self, th19: &mut Th19) -> Result<(), RecvError> {
+    pub fn on_input_menu(&mut self, th19: &mut Th19) -> Result<(), SessionError> {
         match self {
             Self::Null => unreachable!(),
             Self::Prepare(prepare) => prepare.update_th19_on_input_menu(th19),
@@ -235,18 +267,34 @@ impl BattleSessionState {
             };
             select.session().match_initial().map(|x| &x.game_settings)
         };
+        let rematch_menu = match self {
+            Self::Select(select) => select
+                .rematch_prompt()
+                .as_ref()
+                .map(|x| x.menu(select.session())),
+            _ => None,
+        };
         let status = RenderingStatus {
             host: session.host(),
             delay: session.delay(),
+            auto_delay: session.auto_delay(),
+            recommended_delay: session.recommended_delay(),
             p1_name,
             p2_name,
             game_settings,
+            network_stats: features
+                .contains(&Features::ShowNetworkStats)
+                .then(|| session.network_stats()),
+            desync: session.desync(),
+            resumed: session.recently_resumed(),
+            chat_log: session.chat_log(),
+            rematch_menu,
             spectator_host_state,
         };
         in_session::on_render_texts(th19, text_renderer, status);
     }
 
-    pub fn on_round_over(&mut self, th19: &mut Th19) -> Result<(), RecvError> {
+    pub fn on_round_over(&mut self, th19: &mut Th19) -> Result<(), SessionError> {
         let Self::Game(game) = self else {
             return Ok(());
         };
@@ -1,13 +1,23 @@
-use std::sync::mpsc::RecvError;
-
 use anyhow::Result;
 use derive_new::new;
 use getset::{Getters, MutGetters};
 use junowen_lib::{structs::input_devices::InputValue, Th19};
 
-use crate::{helper::inputed_number, session::battle::BattleSession};
+use crate::{
+    helper::{inputed_number, pushed_f1},
+    session::{battle::BattleSession, SessionError},
+    state::spectator_host::SpectatorHostState,
+};
+
+use super::{
+    replay_recorder::ReplayRecorder,
+    utils::{
+        exchange_chats, fold_consumed_inputs, game_state_checksum, init_round, INITIAL_INPUT_DIGEST,
+    },
+};
 
-use super::{spectator_host::SpectatorHostState, utils::init_round};
+/// ゲームの状態を相手と比べる間隔
+const CHECKSUM_INTERVAL_FRAMES: u32 = 60;
 
 #[derive(new, Getters, MutGetters)]
 pub struct BattleGame {
@@ -15,6 +25,12 @@ pub struct BattleGame {
     session: BattleSession,
     #[getset(get = "pub")]
     spectator_host_state: SpectatorHostState,
+    replay_recorder: Option<ReplayRecorder>,
+    #[new(default)]
+    chat_key: Option<usize>,
+    /// ラウンド内で相手と交換した両者の入力の累積値
+    #[new(value = "INITIAL_INPUT_DIGEST")]
+    input_digest: u64,
 }
 
 impl BattleGame {
@@ -22,9 +38,11 @@ impl BattleGame {
         (self.session, self.spectator_host_state)
     }
 
-    pub fn update_th19(&mut self, th19: &mut Th19) -> Result<(), RecvError> {
+    pub fn update_th19(&mut self, th19: &mut Th19) -> Result<(), SessionError> {
         // -1フレーム目、0フレーム目は複数回呼ばれ、回数が不定なのでスキップする
-        if th19.round_frame().unwrap().frame < 1 {
+        let frame = th19.round_frame().unwrap().frame;
+        if frame < 1 {
+            self.input_digest = INITIAL_INPUT_DIGEST;
             let input_devices = th19.input_devices_mut();
             input_devices
                 .p1_input_mut()
@@ -34,6 +52,10 @@ impl BattleGame {
                 .set_current(InputValue::empty());
             return Ok(());
         }
+        if frame.is_multiple_of(CHECKSUM_INTERVAL_FRAMES) {
+            self.session
+                .send_checksum(frame, game_state_checksum(th19, self.input_digest));
+        }
         let input_devices = th19.input_devices_mut();
         let delay = if self.session.host() {
             inputed_number(input_devices)
@@ -49,14 +71,29 @@ impl BattleGame {
         input_devices
             .p2_input_mut()
             .set_current((p2 as u32).try_into().unwrap());
+        self.input_digest = fold_consumed_inputs(self.input_digest, p1, p2);
 
+        exchange_chats(
+            th19,
+            &mut self.session,
+            &mut self.spectator_host_state,
+            &mut self.chat_key,
+        );
+        let current_pushed = pushed_f1(th19.input_devices());
         self.spectator_host_state
-            .update(false, None, th19, &self.session, p1, p2);
+            .update(current_pushed, None, th19, &self.session, p1, p2);
+        if let Some(replay_recorder) = &mut self.replay_recorder {
+            replay_recorder.push_frame(&self.session, p1, p2);
+        }
 
         Ok(())
     }
 
-    pub fn on_round_over(&mut self, th19: &mut Th19) -> Result<(), RecvError> {
-        init_round(th19, &mut self.session, &mut self.spectator_host_state)
+    pub fn on_round_over(&mut self, th19: &mut Th19) -> Result<(), SessionError> {
+        init_round(th19, &mut self.session, &mut self.spectator_host_state)?;
+        if let Some(replay_recorder) = &mut self.replay_recorder {
+            replay_recorder.start_round(&self.session);
+        }
+        Ok(())
     }
 }
@@ -1,5 +1,3 @@
-use std::sync::mpsc::RecvError;
-
 use anyhow::Result;
 use derive_new::new;
 use getset::{Getters, MutGetters};
@@ -12,12 +10,16 @@ use tracing::trace;
 
 use crate::{
     helper::{inputed_number, pushed_f1},
-    session::{battle::BattleSession, MatchInitial},
+    session::{battle::BattleSession, MatchInitial, SessionError},
+    state::spectator_host::SpectatorHostState,
 };
 
-use super::{spectator_host::SpectatorHostState, utils::init_round};
+use super::{
+    rematch_prompt::RematchPrompt,
+    utils::{exchange_chats, init_round},
+};
 
-fn init_match(th19: &mut Th19, battle_session: &mut BattleSession) -> Result<(), RecvError> {
+fn init_match(th19: &mut Th19, battle_session: &mut BattleSession) -> Result<(), SessionError> {
     trace!("init_match");
     th19.set_no_wait(false);
     reset_cursors(th19);
@@ -46,8 +48,13 @@ pub struct BattleSelect {
     session: BattleSession,
     #[getset(get = "pub")]
     spectator_host_state: SpectatorHostState,
+    /// 試合が終わって戻ってきたときは、再戦するかを決めるまで操作させない
+    #[getset(get = "pub")]
+    rematch_prompt: Option<RematchPrompt>,
     #[new(value = "true")]
     first_time: bool,
+    #[new(default)]
+    chat_key: Option<usize>,
 }
 
 impl BattleSelect {
@@ -59,7 +66,21 @@ impl BattleSelect {
         &mut self,
         main_menu: &MainMenu,
         th19: &mut Th19,
-    ) -> Result<(), RecvError> {
+    ) -> Result<(), SessionError> {
+        if let Some(rematch_prompt) = &mut self.rematch_prompt {
+            let rematch = rematch_prompt.update(th19, &mut self.session)?;
+            exchange_chats(
+                th19,
+                &mut self.session,
+                &mut self.spectator_host_state,
+                &mut self.chat_key,
+            );
+            if !rematch {
+                return Ok(());
+            }
+            self.rematch_prompt = None;
+        }
+
         if self.first_time {
             self.first_time = false;
             if self.session.match_initial().is_none() {
@@ -88,13 +109,19 @@ impl BattleSelect {
             .p2_input_mut()
             .set_current((p2 as u32).try_into().unwrap());
 
+        exchange_chats(
+            th19,
+            &mut self.session,
+            &mut self.spectator_host_state,
+            &mut self.chat_key,
+        );
         self.spectator_host_state
             .update(false, Some(main_menu), th19, &self.session, p1, p2);
 
         Ok(())
     }
 
-    pub fn update_th19_on_input_menu(&mut self, th19: &mut Th19) -> Result<(), RecvError> {
+    pub fn update_th19_on_input_menu(&mut self, th19: &mut Th19) -> Result<(), SessionError> {
         let main_menu = th19.app().main_loop_tasks().find_main_menu().unwrap();
         if main_menu.screen_id() != ScreenId::DifficultySelect {
             return Ok(());
@@ -113,6 +140,13 @@ impl BattleSelect {
         let input = if p1 != 0 { p1 } else { p2 };
         menu_input.set_current((input as u32).try_into().unwrap());
 
+        exchange_chats(
+            th19,
+            &mut self.session,
+            &mut self.spectator_host_state,
+            &mut self.chat_key,
+        );
+
         let current_pushed = pushed_f1(input_devices);
         self.spectator_host_state.update(
             current_pushed,
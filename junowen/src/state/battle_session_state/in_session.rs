@@ -1,20 +1,34 @@
-use std::{borrow::Cow, ffi::c_void};
+use std::ffi::c_void;
 
 use junowen_lib::{structs::settings::GameSettings, Th19};
 
 use crate::{
-    signaling::waiting_for_match::{WaitingForPureP2pSpectator, WaitingForSpectator},
-    state::render_parts::{render_footer, render_game_settings, render_names},
+    session::{ChatLog, Desync, NetworkStats},
+    state::{
+        render_parts::{
+            render_chat, render_desync_warning, render_footer, render_game_settings, render_names,
+            render_network_stats, render_rematch_menu, render_resumed_notice,
+            spectator_host_message,
+        },
+        spectator_host::SpectatorHostState,
+    },
 };
 
-use super::spectator_host::SpectatorHostState;
+use super::rematch_prompt::RematchMenu;
 
 pub struct RenderingStatus<'a> {
     pub host: bool,
     pub delay: u8,
+    pub auto_delay: bool,
+    pub recommended_delay: Option<u8>,
     pub p1_name: &'a str,
     pub p2_name: &'a str,
     pub game_settings: Option<&'a GameSettings>,
+    pub network_stats: Option<&'a NetworkStats>,
+    pub desync: Option<Desync>,
+    pub resumed: bool,
+    pub chat_log: &'a ChatLog,
+    pub rematch_menu: Option<RematchMenu>,
     pub spectator_host_state: Option<&'a SpectatorHostState>,
 }
 
@@ -23,47 +37,45 @@ pub fn on_render_texts(th19: &Th19, text_renderer: *const c_void, status: Render
     if let Some(game_settings) = status.game_settings {
         render_game_settings(th19, text_renderer, game_settings);
     }
+    if let Some(network_stats) = status.network_stats {
+        render_network_stats(th19, text_renderer, network_stats);
+    }
+    if let Some(desync) = status.desync {
+        render_desync_warning(th19, text_renderer, desync);
+    }
+    if status.resumed {
+        render_resumed_notice(th19, text_renderer);
+    }
+    if let Some(rematch_menu) = &status.rematch_menu {
+        render_rematch_menu(
+            th19,
+            text_renderer,
+            &rematch_menu.message,
+            &rematch_menu.lines,
+        );
+    }
+    render_chat(
+        th19,
+        text_renderer,
+        status.chat_log,
+        status.p1_name,
+        status.p2_name,
+    );
 
-    let (msg2_rear, msg2_front) = if let Some(spectator_host_state) = status.spectator_host_state {
-        if spectator_host_state.count_spectators() > 0 {
-            (
-                "               ",
-                Cow::Owned(format!(
-                    "Spectator(s): {}",
-                    spectator_host_state.count_spectators()
-                )),
-            )
-        } else {
-            match spectator_host_state.waiting() {
-                WaitingForSpectator::PureP2p(waiting) => match waiting {
-                    WaitingForPureP2pSpectator::Standby { ready: false, .. }
-                    | WaitingForPureP2pSpectator::SignalingCodeRecved { ready: false, .. }
-                    | WaitingForPureP2pSpectator::SignalingCodeSent { ready: false, .. } => {
-                        ("", "".into())
-                    }
-                    WaitingForPureP2pSpectator::Standby { .. } => (
-                        "       __                                    ",
-                        "(Press F1 to accept spectator from clipboard)".into(),
-                    ),
-                    WaitingForPureP2pSpectator::SignalingCodeRecved { .. } => (
-                        "                              ",
-                        "(Generating signaling code...)".into(),
-                    ),
-                    WaitingForPureP2pSpectator::SignalingCodeSent { .. } => (
-                        "                                                      ",
-                        "(Your signaling code has been copied to the clipboard)".into(),
-                    ),
-                },
-                WaitingForSpectator::ReservedRoom(_) => ("", "".into()),
-            }
-        }
-    } else {
-        ("", "".into())
-    };
+    let (msg2_rear, msg2_front) = status
+        .spectator_host_state
+        .map(spectator_host_message)
+        .unwrap_or_default();
 
     let delay_underline = if status.host { "_" } else { " " };
-    let msg_front/* _ */= format!("Delay: {} {}", status.delay, msg2_front);
-    let msg_rear/* __ */= format!("       {} {}", delay_underline, msg2_rear);
+    let delay_note = match (status.host, status.auto_delay, status.recommended_delay) {
+        (true, true, _) => " (Auto)".to_owned(),
+        (true, false, Some(recommended)) => format!(" (Recommended: {})", recommended),
+        _ => "".to_owned(),
+    };
+    let delay_note_rear = " ".repeat(delay_note.len());
+    let msg_front/* _ */= format!("Delay: {}{} {}", status.delay, delay_note, msg2_front);
+    let msg_rear/* __ */= format!("       {}{} {}", delay_underline, delay_note_rear, msg2_rear);
 
     render_footer(th19, text_renderer, &msg_front, &msg_rear);
 }
@@ -0,0 +1,286 @@
+use junowen_lib::{
+    structs::{
+        input_devices::{InputFlags, InputValue},
+        settings::GameSettings,
+    },
+    Th19,
+};
+
+use crate::{
+    session::{battle::BattleSession, Negotiation, SessionError},
+    state::render_parts::MenuLine,
+};
+
+/// ライフの設定値の最大。表示は +1 する
+const MAX_LIFE: u8 = 4;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Item {
+    TimeLimit,
+    Round,
+    AbilityCard,
+    P1Life,
+    P1Barrier,
+    P2Life,
+    P2Barrier,
+    Propose,
+    Rematch,
+    Accept,
+    Decline,
+    Quit,
+}
+
+const SETTINGS: [Item; 7] = [
+    Item::TimeLimit,
+    Item::Round,
+    Item::AbilityCard,
+    Item::P1Life,
+    Item::P1Barrier,
+    Item::P2Life,
+    Item::P2Barrier,
+];
+
+fn pulse(current: InputValue, prev: InputValue, flag: InputFlags) -> bool {
+    current.0 & flag != None && prev.0 & flag == None
+}
+
+fn cycle(value: u8, count: u8, forward: bool) -> u8 {
+    if forward {
+        (value + 1) % count
+    } else {
+        (value + count - 1) % count
+    }
+}
+
+fn change_setting(game_settings: &mut GameSettings, item: Item, forward: bool) {
+    match item {
+        Item::TimeLimit => {
+            let value = cycle(game_settings.time_limit() as u8, 7, forward);
+            game_settings.set_time_limit(value.try_into().unwrap_or_default());
+        }
+        Item::Round => {
+            let value = cycle(game_settings.round() as u8, 3, forward);
+            game_settings.set_round(value.try_into().unwrap_or_default());
+        }
+        Item::AbilityCard => {
+            let value = cycle(game_settings.ability_card() as u8, 4, forward);
+            game_settings.set_ability_card(value.try_into().unwrap_or_default());
+        }
+        Item::P1Life => {
+            let value = cycle(game_settings.p1_life() as u8, MAX_LIFE + 1, forward);
+            game_settings.set_p1_life(value as u32);
+        }
+        Item::P1Barrier => {
+            let value = cycle(game_settings.p1_barrier() as u8, 4, forward);
+            game_settings.set_p1_barrier(value.try_into().unwrap_or_default());
+        }
+        Item::P2Life => {
+            let value = cycle(game_settings.p2_life() as u8, MAX_LIFE + 1, forward);
+            game_settings.set_p2_life(value as u32);
+        }
+        Item::P2Barrier => {
+            let value = cycle(game_settings.p2_barrier() as u8, 4, forward);
+            game_settings.set_p2_barrier(value.try_into().unwrap_or_default());
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn same_settings(a: &GameSettings, b: &GameSettings) -> bool {
+    (a.common(), a.p1(), a.p2()) == (b.common(), b.p1(), b.p2())
+}
+
+fn item_text(item: Item, game_settings: &GameSettings, editable: bool) -> String {
+    let (label, value) = match item {
+        Item::TimeLimit => ("Time Limit", game_settings.time_limit().to_string()),
+        Item::Round => ("Round", game_settings.round().to_string()),
+        Item::AbilityCard => (
+            "Ability Card",
+            format!("{:?}", game_settings.ability_card()),
+        ),
+        Item::P1Life => ("P1 Life", (game_settings.p1_life() + 1).to_string()),
+        Item::P1Barrier => ("P1 Barrier", game_settings.p1_barrier().to_string()),
+        Item::P2Life => ("P2 Life", (game_settings.p2_life() + 1).to_string()),
+        Item::P2Barrier => ("P2 Barrier", game_settings.p2_barrier().to_string()),
+        Item::Propose => return "Propose".to_owned(),
+        Item::Rematch => return "Rematch".to_owned(),
+        Item::Accept => return "Accept".to_owned(),
+        Item::Decline => return "Decline".to_owned(),
+        Item::Quit => return "Quit".to_owned(),
+    };
+    if editable {
+        format!("{}: < {} >", label, value)
+    } else {
+        format!("{}: {}", label, value)
+    }
+}
+
+/// 提案を待っている間や再戦すると答えた後は設定を変えられない
+fn actions(negotiation: &Negotiation) -> &'static [Item] {
+    match negotiation.proposal() {
+        Some((false, _)) => &[Item::Accept, Item::Decline, Item::Quit],
+        Some((true, _)) => &[Item::Quit],
+        None if negotiation.local_rematch().is_some() => &[Item::Quit],
+        None => &[Item::Propose, Item::Rematch, Item::Quit],
+    }
+}
+
+fn selectable_items(negotiation: &Negotiation) -> Vec<Item> {
+    let actions = actions(negotiation);
+    if !negotiation.can_propose() {
+        return actions.to_vec();
+    }
+    SETTINGS.iter().chain(actions).copied().collect()
+}
+
+pub struct RematchMenu {
+    pub message: String,
+    pub lines: Vec<MenuLine>,
+}
+
+/// 試合が終わったら、次の試合の設定を提案し合ってから再戦するかを両者に確かめる
+pub struct RematchPrompt {
+    /// 提案するために変えている設定
+    editing: GameSettings,
+    items: Vec<Item>,
+    cursor: usize,
+    prev_input: InputValue,
+    proposal_pending: bool,
+}
+
+impl RematchPrompt {
+    pub fn new(session: &BattleSession) -> Self {
+        Self {
+            editing: session.next_game_settings().unwrap().clone(),
+            items: selectable_items(session.negotiation()),
+            cursor: 0,
+            prev_input: InputValue::full(),
+            proposal_pending: false,
+        }
+    }
+
+    /// 両者が再戦すると答えたら true を返す。入力はすべてこのメニューが使う
+    pub fn update(
+        &mut self,
+        th19: &mut Th19,
+        session: &mut BattleSession,
+    ) -> Result<bool, SessionError> {
+        let input_devices = th19.input_devices_mut();
+        let current = input_devices.p1_input().current();
+        let prev = std::mem::replace(&mut self.prev_input, current);
+        input_devices
+            .p1_input_mut()
+            .set_current(InputValue::empty());
+        input_devices
+            .p2_input_mut()
+            .set_current(InputValue::empty());
+
+        if session.poll_rematch()? {
+            return Ok(true);
+        }
+        let negotiation = session.negotiation();
+        // 提案に答えが出たら、合意した設定から変え直す
+        if self.proposal_pending && negotiation.proposal().is_none() {
+            self.editing = session.next_game_settings().unwrap().clone();
+        }
+        self.proposal_pending = negotiation.proposal().is_some();
+        let items = selectable_items(negotiation);
+        if items != self.items {
+            self.items = items;
+            self.cursor = 0;
+        }
+
+        let len = self.items.len();
+        let item = self.items[self.cursor];
+        if pulse(current, prev, InputFlags::UP) {
+            self.cursor = (self.cursor + len - 1) % len;
+            th19.play_sound(th19.sound_manager(), 0x0a, 0);
+        } else if pulse(current, prev, InputFlags::DOWN) {
+            self.cursor = (self.cursor + 1) % len;
+            th19.play_sound(th19.sound_manager(), 0x0a, 0);
+        } else if SETTINGS.contains(&item)
+            && (pulse(current, prev, InputFlags::LEFT) || pulse(current, prev, InputFlags::RIGHT))
+        {
+            let forward = pulse(current, prev, InputFlags::RIGHT);
+            change_setting(&mut self.editing, item, forward);
+            th19.play_sound(th19.sound_manager(), 0x0a, 0);
+        } else if pulse(current, prev, InputFlags::SHOT) || pulse(current, prev, InputFlags::ENTER)
+        {
+            self.decide(th19, session, item);
+        } else if (pulse(current, prev, InputFlags::CHARGE)
+            || pulse(current, prev, InputFlags::BOMB)
+            || pulse(current, prev, InputFlags::PAUSE))
+            && self.cursor != len - 1
+        {
+            // Quit に合わせる
+            self.cursor = len - 1;
+            th19.play_sound(th19.sound_manager(), 0x09, 0);
+        }
+        Ok(false)
+    }
+
+    fn decide(&mut self, th19: &Th19, session: &mut BattleSession, item: Item) {
+        let next_game_settings = session.next_game_settings().unwrap().clone();
+        match item {
+            Item::Propose => {
+                if same_settings(&self.editing, &next_game_settings) {
+                    th19.play_sound(th19.sound_manager(), 0x10, 0);
+                    return;
+                }
+                session.propose_settings(self.editing.clone());
+            }
+            Item::Rematch => {
+                self.editing = next_game_settings;
+                session.answer_rematch(true);
+            }
+            Item::Accept => session.answer_proposal(true),
+            Item::Decline => session.answer_proposal(false),
+            Item::Quit => {
+                session.answer_rematch(false);
+                th19.play_sound(th19.sound_manager(), 0x09, 0);
+                return;
+            }
+            _ => return,
+        }
+        th19.play_sound(th19.sound_manager(), 0x07, 0);
+    }
+
+    pub fn menu(&self, session: &BattleSession) -> RematchMenu {
+        let negotiation = session.negotiation();
+        let remote = session.remote_player_name();
+        let message = match negotiation.proposal() {
+            Some((false, _)) => format!("{} proposes new settings", remote),
+            Some((true, _)) => format!("Waiting for {} to answer...", remote),
+            None if negotiation.local_rematch() == Some(true) => {
+                format!("Waiting for {}...", remote)
+            }
+            None => match (negotiation.answer(), negotiation.remote_rematch()) {
+                (_, Some(true)) => format!("{} wants a rematch", remote),
+                (Some(true), _) => "Your proposal was accepted".to_owned(),
+                (Some(false), _) => "Your proposal was declined".to_owned(),
+                _ => "".to_owned(),
+            },
+        };
+
+        let editable = negotiation.can_propose();
+        let (game_settings, next_game_settings) = match negotiation.proposal() {
+            Some((_, proposed)) => (proposed, proposed),
+            None if editable => (&self.editing, session.next_game_settings().unwrap()),
+            None => {
+                let next_game_settings = session.next_game_settings().unwrap();
+                (next_game_settings, next_game_settings)
+            }
+        };
+        let selected = self.items[self.cursor];
+        let lines = SETTINGS
+            .iter()
+            .chain(actions(negotiation))
+            .map(|&item| MenuLine {
+                text: item_text(item, game_settings, editable),
+                enabled: item != Item::Propose || !same_settings(game_settings, next_game_settings),
+                selected: item == selected,
+            })
+            .collect();
+        RematchMenu { message, lines }
+    }
+}
@@ -0,0 +1,129 @@
+use std::{
+    fs::{self, File},
+    io::BufWriter,
+    path::PathBuf,
+    time::Duration,
+};
+
+use anyhow::Result;
+use junowen_lib::{
+    replay::{Replay, ReplayGameSettings, ReplayMetadata, ReplayRound, RoundNetplay, RoundSeeds},
+    Th19,
+};
+use time::OffsetDateTime;
+use tracing::{error, info};
+
+use crate::session::battle::BattleSession;
+
+/// これより長く相手の入力を待ったフレームを記録する (1 フレーム)
+const STALL_THRESHOLD: Duration = Duration::from_micros(16_667);
+
+/// 対戦の入力を記録して、終わったらファイルに書き出す
+pub struct ReplayRecorder {
+    path: PathBuf,
+    replay: Replay,
+}
+
+impl ReplayRecorder {
+    pub fn new(replay_dir: &str, th19: &Th19, session: &BattleSession) -> Self {
+        let local_player_name = th19.vs_mode().player_name().to_owned();
+        let remote_player_name = session.remote_player_name().clone();
+        let (p1_name, p2_name) = if session.host() {
+            (local_player_name, remote_player_name)
+        } else {
+            (remote_player_name, local_player_name)
+        };
+        let now = OffsetDateTime::now_utc();
+        let file_name = format!(
+            "{:04}{:02}{:02}-{:02}{:02}{:02}.jnrep",
+            now.year(),
+            now.month() as u8,
+            now.day(),
+            now.hour(),
+            now.minute(),
+            now.second()
+        );
+        let selection = th19.selection();
+        let game_settings = th19.game_settings_in_game().unwrap();
+        let mut recorder = Self {
+            path: PathBuf::from(replay_dir).join(file_name),
+            replay: Replay {
+                metadata: ReplayMetadata {
+                    p1_name,
+                    p2_name,
+                    started_at: Some(now.unix_timestamp()),
+                    difficulty: selection.difficulty as u8,
+                    player_matchup: selection.player_matchup as u8,
+                    p1_character: selection.p1().character as u8,
+                    p1_card: selection.p1().card as u8,
+                    p2_character: selection.p2().character as u8,
+                    p2_card: selection.p2().card as u8,
+                    game_settings: ReplayGameSettings {
+                        common: game_settings.common(),
+                        p1: game_settings.p1(),
+                        p2: game_settings.p2(),
+                    },
+                },
+                rounds: vec![],
+            },
+        };
+        recorder.start_round(session);
+        recorder
+    }
+
+    pub fn start_round(&mut self, session: &BattleSession) {
+        let Some(round_initial) = session.round_initial() else {
+            return;
+        };
+        self.replay.rounds.push(ReplayRound {
+            seeds: RoundSeeds {
+                seed1: round_initial.seed1,
+                seed2: round_initial.seed2,
+                seed3: round_initial.seed3,
+                seed4: round_initial.seed4,
+            },
+            inputs: vec![],
+            netplay: Some(RoundNetplay::default()),
+        });
+    }
+
+    pub fn push_frame(&mut self, session: &BattleSession, p1: u16, p2: u16) {
+        let Some(round) = self.replay.rounds.last_mut() else {
+            return;
+        };
+        let frame = round.inputs.len() as u32;
+        round.inputs.push((p1, p2));
+        let Some(netplay) = &mut round.netplay else {
+            return;
+        };
+        let delay = session.delay();
+        if netplay.delays.last().map(|&(_, x)| x) != Some(delay) {
+            netplay.delays.push((frame, delay));
+        }
+        let stall = session.network_stats().last_stall();
+        if stall > STALL_THRESHOLD {
+            netplay.stalls.push((frame, stall.as_millis() as u32));
+        }
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut writer = BufWriter::new(File::create(&self.path)?);
+        self.replay.write_to(&mut writer)?;
+        Ok(())
+    }
+}
+
+impl Drop for ReplayRecorder {
+    fn drop(&mut self) {
+        if self.replay.rounds.iter().all(|x| x.inputs.is_empty()) {
+            return;
+        }
+        match self.save() {
+            Ok(()) => info!("replay saved: {}", self.path.display()),
+            Err(err) => error!("failed to save replay: {}", err),
+        }
+    }
+}
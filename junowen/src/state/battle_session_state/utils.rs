@@ -1,17 +1,54 @@
-use std::sync::mpsc::RecvError;
-
 use anyhow::Result;
+use clipboard_win::get_clipboard_string;
 use junowen_lib::Th19;
 
-use crate::session::{battle::BattleSession, RoundInitial};
+use crate::{
+    helper::pushed_chat_key,
+    session::{battle::BattleSession, ChatMessage, Emote, RoundInitial, SessionError},
+    state::spectator_host::SpectatorHostState,
+};
+
+fn fnv1a(hash: u64, bytes: impl IntoIterator<Item = u8>) -> u64 {
+    bytes.into_iter().fold(hash, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+const FNV1A_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
 
-use super::spectator_host::SpectatorHostState;
+/// ラウンド開始時の入力の累積値
+pub const INITIAL_INPUT_DIGEST: u64 = FNV1A_OFFSET_BASIS;
+
+/// 相手と交換してゲームに渡す両者の入力を累積値に加える。
+/// 手元のデバイスの生の入力は両者で異なるので使わない
+pub fn fold_consumed_inputs(input_digest: u64, p1: u16, p2: u16) -> u64 {
+    fnv1a(input_digest, [p1, p2].iter().flat_map(|x| x.to_le_bytes()))
+}
+
+/// 乱数のシード、フレーム数、それまでの入力の累積値から作る、両者で一致するはずの値。
+/// 乱数に影響が出る前に入力がずれても検知できる。
+/// 自機の位置やライフは junowen-lib がプレイヤーの構造体を読めないので含めない
+pub fn game_state_checksum(th19: &Th19, input_digest: u64) -> u64 {
+    let frame = th19.round_frame().unwrap().frame;
+    let values = [
+        th19.rand_seed1().unwrap(),
+        th19.rand_seed2().unwrap(),
+        th19.rand_seed3().unwrap(),
+        th19.rand_seed4().unwrap(),
+        frame,
+    ];
+    let hash = fnv1a(
+        FNV1A_OFFSET_BASIS,
+        values.iter().flat_map(|x| x.to_le_bytes()),
+    );
+    fnv1a(hash, input_digest.to_le_bytes())
+}
 
 pub fn init_round(
     th19: &mut Th19,
     battle_session: &mut BattleSession,
     spectator_host_state: &mut SpectatorHostState,
-) -> Result<(), RecvError> {
+) -> Result<(), SessionError> {
     if battle_session.host() {
         let opt = battle_session.init_round(Some(RoundInitial {
             seed1: th19.rand_seed1().unwrap(),
@@ -30,3 +67,28 @@ pub fn init_round(
     spectator_host_state.send_init_round_if_connected(th19);
     Ok(())
 }
+
+/// F5-F7 で定型文、F8 でクリップボードの文を送る
+fn chat_message(key: usize) -> Option<ChatMessage> {
+    match key {
+        0 => Some(ChatMessage::Emote(Emote::Gg)),
+        1 => Some(ChatMessage::Emote(Emote::OneMore)),
+        2 => Some(ChatMessage::Emote(Emote::Lag)),
+        _ => get_clipboard_string().ok().map(ChatMessage::Text),
+    }
+}
+
+/// チャットのキーが押されたら送り、届いたものと合わせて観戦者にも伝える。
+/// 押しっぱなしで繰り返し送らないように、前のフレームで押していたキーを prev_key に持っておく
+pub fn exchange_chats(
+    th19: &Th19,
+    battle_session: &mut BattleSession,
+    spectator_host_state: &mut SpectatorHostState,
+    prev_key: &mut Option<usize>,
+) {
+    let key = pushed_chat_key(th19.input_devices());
+    let chat = key.filter(|_| key != *prev_key).and_then(chat_message);
+    *prev_key = key;
+    let chats = battle_session.exchange_chats(chat);
+    spectator_host_state.send_chats(chats);
+}
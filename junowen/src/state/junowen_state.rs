@@ -1,7 +1,7 @@
 mod on_rewrite_controller_assignments;
 mod standby;
 
-use std::{ffi::c_void, sync::mpsc::RecvError};
+use std::ffi::c_void;
 
 use anyhow::Result;
 use junowen_lib::{
@@ -14,12 +14,13 @@ use tracing::trace;
 use crate::{
     file::Features,
     in_game_lobby::{Lobby, TitleMenuModifier},
-    session::{battle::BattleSession, spectator::SpectatorSession},
+    session::{battle::BattleSession, spectator::SpectatorSession, SessionError},
     signaling::waiting_for_match::{WaitingForMatch, WaitingForSpectator},
 };
 
 use super::{
-    battle_session_state::BattleSessionState, spectator_session_state::SpectatorSessionState,
+    battle_session_state::BattleSessionState, spectator_host::SpectatorHostOptions,
+    spectator_session_state::SpectatorSessionState,
 };
 
 use self::on_rewrite_controller_assignments::on_rewrite_controller_assignments;
@@ -47,8 +48,13 @@ impl JunowenState {
         &mut self,
         battle_session: BattleSession,
         waiting: WaitingForSpectator,
+        spectator_host_options: SpectatorHostOptions,
     ) {
-        *self = Self::BattleSession(BattleSessionState::prepare(battle_session, waiting));
+        *self = Self::BattleSession(BattleSessionState::prepare(
+            battle_session,
+            waiting,
+            spectator_host_options,
+        ));
     }
 
     fn end_session(&mut self) {
@@ -60,14 +66,23 @@ impl JunowenState {
         th19.set_no_wait(false);
     }
 
-    pub fn start_spectator_session(&mut self, session: SpectatorSession) {
-        *self = Self::SpectatorSession(SpectatorSessionState::prepare(session));
+    pub fn start_spectator_session(
+        &mut self,
+        session: SpectatorSession,
+        spectator_host_options: SpectatorHostOptions,
+    ) {
+        *self = Self::SpectatorSession(SpectatorSessionState::prepare(
+            session,
+            spectator_host_options,
+        ));
     }
 
     fn update_state(
         &mut self,
         th19: &Th19,
         waiting_for_match: &mut Option<WaitingForMatch>,
+        replay_dir: Option<&str>,
+        spectator_host_options: SpectatorHostOptions,
     ) -> (bool, Option<&'static MainMenu>) {
         match self {
             Self::Standby => {
@@ -84,7 +99,7 @@ impl JunowenState {
                         match waiting.try_into_session_and_waiting_for_spectator() {
                             Ok((session, waiting)) => {
                                 trace!("session received");
-                                self.start_battle_session(session, waiting);
+                                self.start_battle_session(session, waiting, spectator_host_options);
                                 (true, None)
                             }
                             Err(waiting) => {
@@ -96,7 +111,7 @@ impl JunowenState {
                     WaitingForMatch::SpectatorHost(waiting) => match waiting.try_into_session() {
                         Ok(session) => {
                             trace!("session received");
-                            self.start_spectator_session(session);
+                            self.start_spectator_session(session, spectator_host_options);
                             (true, None)
                         }
                         Err(waiting) => {
@@ -107,7 +122,7 @@ impl JunowenState {
                 }
             }
             Self::BattleSession(session_state) => {
-                let Some(menu_opt) = session_state.update_state(th19) else {
+                let Some(menu_opt) = session_state.update_state(th19, replay_dir) else {
                     self.end_session();
                     return (true, None);
                 };
@@ -128,7 +143,7 @@ impl JunowenState {
         changed: bool,
         menu: Option<&MainMenu>,
         th19: &mut Th19,
-    ) -> Result<(), RecvError> {
+    ) -> Result<(), SessionError> {
         match self {
             Self::Standby => {
                 if changed {
@@ -149,8 +164,11 @@ impl JunowenState {
         &mut self,
         th19: &mut Th19,
         waiting_for_match: &mut Option<WaitingForMatch>,
-    ) -> Result<(), RecvError> {
-        let (changed, menu_opt) = self.update_state(th19, waiting_for_match);
+        replay_dir: Option<&str>,
+        spectator_host_options: SpectatorHostOptions,
+    ) -> Result<(), SessionError> {
+        let (changed, menu_opt) =
+            self.update_state(th19, waiting_for_match, replay_dir, spectator_host_options);
         self.update_th19_on_input_players(changed, menu_opt, th19)
     }
 
@@ -159,7 +177,7 @@ impl JunowenState {
         th19: &mut Th19,
         title_menu_modifier: &mut TitleMenuModifier,
         lobby: &mut Lobby,
-    ) -> Result<(), RecvError> {
+    ) -> Result<(), SessionError> {
         match self {
             Self::Standby => {
                 standby::update_th19_on_input_menu(th19, title_menu_modifier, lobby);
@@ -223,7 +241,7 @@ impl JunowenState {
         }
     }
 
-    pub fn on_round_over(&mut self, th19: &mut Th19) -> Result<(), RecvError> {
+    pub fn on_round_over(&mut self, th19: &mut Th19) -> Result<(), SessionError> {
         match self {
             Self::Standby => Ok(()),
             Self::BattleSession(session_state) => session_state.on_round_over(th19),
@@ -1,10 +1,17 @@
-use std::ffi::c_void;
+use std::{borrow::Cow, ffi::c_void};
 
 use junowen_lib::{
     structs::{others::RenderingText, settings::GameSettings},
     Th19,
 };
 
+use crate::{
+    session::{ChatLog, Desync, NetworkStats, SessionError},
+    signaling::waiting_for_match::{WaitingForPureP2pSpectator, WaitingForSpectator},
+};
+
+use super::spectator_host::SpectatorHostState;
+
 pub fn render_names(th19: &Th19, text_renderer: *const c_void, p1_name: &str, p2_name: &str) {
     let mut text = RenderingText::default();
     text.set_text(p1_name.as_bytes());
@@ -77,6 +84,189 @@ pub fn render_game_settings(
     render_game_players_settings(th19, text_renderer, game_settings);
 }
 
+pub fn render_network_stats(
+    th19: &Th19,
+    text_renderer: *const c_void,
+    network_stats: &NetworkStats,
+) {
+    let to_ms = |x: Option<std::time::Duration>| {
+        x.map(|x| format!("{}ms", x.as_millis()))
+            .unwrap_or_else(|| "-".to_owned())
+    };
+    let msg = format!(
+        "RTT: {}  Jitter: {}  Stall: {}ms/s  Queue: {}",
+        to_ms(network_stats.rtt()),
+        to_ms(network_stats.jitter()),
+        network_stats.stall_per_second().as_millis(),
+        network_stats.queue_depth(),
+    );
+    let mut text = RenderingText::default();
+    text.set_text(msg.as_bytes());
+    text.set_x(640, th19.window_inner());
+    text.set_y(4, th19.window_inner());
+    text.color = 0xffffffff;
+    text.font_type = 1;
+    text.horizontal_align = 0;
+    th19.render_text(text_renderer, &text);
+}
+
+pub fn render_desync_warning(th19: &Th19, text_renderer: *const c_void, desync: Desync) {
+    let msg = format!(
+        "Desync detected at round {} frame {}",
+        desync.round, desync.frame
+    );
+    let mut text = RenderingText::default();
+    text.set_text(msg.as_bytes());
+    text.set_x(640, th19.window_inner());
+    text.set_y(4 + 32, th19.window_inner());
+    text.color = 0xffff4040;
+    text.horizontal_align = 0;
+    th19.render_text(text_renderer, &text);
+}
+
+pub fn render_resumed_notice(th19: &Th19, text_renderer: *const c_void) {
+    let mut text = RenderingText::default();
+    text.set_text(b"Reconnected to the opponent");
+    text.set_x(640, th19.window_inner());
+    text.set_y(4 + 32 * 2, th19.window_inner());
+    text.color = 0xff40ff40;
+    text.horizontal_align = 0;
+    th19.render_text(text_renderer, &text);
+}
+
+/// 最近のチャットを送った側の色で画面上部の中央に出す
+pub fn render_chat(
+    th19: &Th19,
+    text_renderer: *const c_void,
+    chat_log: &ChatLog,
+    p1_name: &str,
+    p2_name: &str,
+) {
+    let mut text = RenderingText::default();
+    text.font_type = 1;
+    text.horizontal_align = 0;
+    text.set_x(640, th19.window_inner());
+    for (i, entry) in chat_log.recent().enumerate() {
+        let (name, color) = if entry.p1() {
+            (p1_name, 0xffff8080)
+        } else {
+            (p2_name, 0xff8080ff)
+        };
+        text.set_text(format!("{}: {}", name, entry.message().text()).as_bytes());
+        text.set_y(4 + 64 + i as u32 * 24, th19.window_inner());
+        text.color = color;
+        th19.render_text(text_renderer, &text);
+    }
+}
+
+pub struct MenuLine {
+    pub text: String,
+    pub enabled: bool,
+    pub selected: bool,
+}
+
+/// 試合の合間に出す、再戦するかと次の試合の設定を決めるメニュー
+pub fn render_rematch_menu(
+    th19: &Th19,
+    text_renderer: *const c_void,
+    message: &str,
+    lines: &[MenuLine],
+) {
+    let mut text = RenderingText::default();
+    text.set_x(640, th19.window_inner());
+    text.horizontal_align = 0;
+
+    text.set_text(b"Rematch?");
+    text.set_y(176, th19.window_inner());
+    text.color = 0xff000000;
+    text.font_type = 9;
+    text.drop_shadow = true;
+    th19.render_text(text_renderer, &text);
+    text.color = 0xffffffff;
+    text.font_type = 7;
+    th19.render_text(text_renderer, &text);
+
+    text.set_text(message.as_bytes());
+    text.set_y(232, th19.window_inner());
+    text.color = 0xffffffa0;
+    text.font_type = 0;
+    text.drop_shadow = false;
+    th19.render_text(text_renderer, &text);
+
+    for (i, line) in lines.iter().enumerate() {
+        let (shadow, color) = match (line.enabled, line.selected) {
+            (false, _) => (0x40ffffff, 0xff808080),
+            (true, true) => (0xff000000, 0xffffff80),
+            (true, false) => (0xff404040, 0xff808060),
+        };
+        text.set_text(line.text.as_bytes());
+        text.set_y(296 + i as u32 * 40, th19.window_inner());
+        text.color = shadow;
+        text.font_type = 9;
+        th19.render_text(text_renderer, &text);
+        text.color = color;
+        text.font_type = 7;
+        th19.render_text(text_renderer, &text);
+    }
+}
+
+pub fn render_session_aborted(th19: &Th19, text_renderer: *const c_void, err: &SessionError) {
+    let mut text = RenderingText::default();
+    text.set_text(format!("Session aborted: {}", err).as_bytes());
+    text.set_x(640, th19.window_inner());
+    text.set_y(4, th19.window_inner());
+    text.color = 0xffff4040;
+    text.horizontal_align = 0;
+    th19.render_text(text_renderer, &text);
+}
+
+/// フッターに出す観戦者の受け付け状況 (下線, 本文)。観戦を遅らせているときはその時間も出す
+pub fn spectator_host_message(
+    spectator_host_state: &SpectatorHostState,
+) -> (&'static str, Cow<'static, str>) {
+    let (underline, msg) = spectator_host_waiting_message(spectator_host_state);
+    let delay_frames = spectator_host_state.delay_frames();
+    if delay_frames == 0 {
+        return (underline, msg);
+    }
+    let delay = format!("(Spectator delay: {:.1}s)", delay_frames as f64 / 60.0);
+    if msg.is_empty() {
+        return (underline, delay.into());
+    }
+    (underline, format!("{} {}", msg, delay).into())
+}
+
+fn spectator_host_waiting_message(
+    spectator_host_state: &SpectatorHostState,
+) -> (&'static str, Cow<'static, str>) {
+    if spectator_host_state.count_spectators() > 0 {
+        return (
+            "               ",
+            Cow::Owned(format!(
+                "Spectator(s): {}",
+                spectator_host_state.count_spectators()
+            )),
+        );
+    }
+    match spectator_host_state.waiting() {
+        WaitingForSpectator::PureP2p(waiting) => match waiting {
+            WaitingForPureP2pSpectator::Standby { .. } => (
+                "       __                                    ",
+                "(Press F1 to accept spectator from clipboard)".into(),
+            ),
+            WaitingForPureP2pSpectator::SignalingCodeRecved { .. } => (
+                "                              ",
+                "(Generating signaling code...)".into(),
+            ),
+            WaitingForPureP2pSpectator::SignalingCodeSent { .. } => (
+                "                                                      ",
+                "(Your signaling code has been copied to the clipboard)".into(),
+            ),
+        },
+        WaitingForSpectator::ReservedRoom(_) => ("", "".into()),
+    }
+}
+
 pub fn render_footer(th19: &Th19, text_renderer: *const c_void, msg_front: &str, msg_rear: &str) {
     let version = env!("CARGO_PKG_VERSION");
     let version_blank = (0..version.len()).map(|_| " ").collect::<String>();
@@ -0,0 +1,307 @@
+use std::collections::VecDeque;
+
+use anyhow::{bail, Result};
+use getset::Getters;
+use junowen_lib::{
+    structs::app::{MainMenu, ScreenId},
+    structs::{selection::Selection, settings::GameSettings},
+    Th19,
+};
+use tracing::info;
+
+use crate::{
+    session::{
+        battle::BattleSession,
+        spectator::{self, BufferedMessage, InitialState, SpectatorInitial, SpectatorSession},
+        spectator_host::SpectatorHostSession,
+        ChatEntry, ChatMessage, RoundInitial,
+    },
+    signaling::waiting_for_match::WaitingForSpectator,
+};
+
+/// 追いつくための入力を 1 メッセージにまとめる数
+const CATCH_UP_CHUNK_SIZE: usize = 4096;
+/// 途中から観戦を始めた観戦者のために持っておくメッセージの上限。60fps で 30 分ほど
+const MAX_BACKLOG_MESSAGES: usize = 60 * 60 * 30;
+
+/// 観戦者に伝える対戦者の名前と試合設定を持つもの
+pub trait SpectatorSource {
+    /// (p1 の名前, p2 の名前, 試合設定)。まだ決まっていなければ None
+    fn match_info(&self, th19: &Th19) -> Option<(String, String, GameSettings)>;
+    /// 手元に届くまでに既に遅らされているフレーム数
+    fn delay_frames(&self) -> u32;
+}
+
+impl SpectatorSource for BattleSession {
+    fn match_info(&self, th19: &Th19) -> Option<(String, String, GameSettings)> {
+        let game_settings = self.match_initial().as_ref()?.game_settings.clone();
+        let local_player_name = th19.vs_mode().player_name().to_owned();
+        let remote_player_name = self.remote_player_name().clone();
+        Some(if self.host() {
+            (local_player_name, remote_player_name, game_settings)
+        } else {
+            (remote_player_name, local_player_name, game_settings)
+        })
+    }
+
+    fn delay_frames(&self) -> u32 {
+        0
+    }
+}
+
+/// 観戦者が更に別の観戦者に中継する
+impl SpectatorSource for SpectatorSession {
+    fn match_info(&self, _th19: &Th19) -> Option<(String, String, GameSettings)> {
+        let init = self.spectator_initial()?;
+        Some((
+            init.p1_name().clone(),
+            init.p2_name().clone(),
+            init.game_settings().clone(),
+        ))
+    }
+
+    fn delay_frames(&self) -> u32 {
+        self.spectator_initial()
+            .map(|init| init.delay_frames())
+            .unwrap_or_default()
+    }
+}
+
+fn current_round_initial(th19: &Th19) -> RoundInitial {
+    RoundInitial {
+        seed1: th19.rand_seed1().unwrap(),
+        seed2: th19.rand_seed2().unwrap(),
+        seed3: th19.rand_seed3().unwrap(),
+        seed4: th19.rand_seed4().unwrap(),
+    }
+}
+
+fn create_spectator_initial(
+    current_screen: ScreenId,
+    selection: &Selection,
+    source: &impl SpectatorSource,
+    th19: &Th19,
+    delay_frames: u32,
+) -> Option<SpectatorInitial> {
+    let (p1_name, p2_name, game_settings) = source.match_info(th19)?;
+    Some(SpectatorInitial::new(
+        p1_name,
+        p2_name,
+        game_settings,
+        InitialState::new(
+            match current_screen {
+                ScreenId::DifficultySelect => spectator::Screen::DifficultySelect,
+                ScreenId::CharacterSelect => spectator::Screen::CharacterSelect,
+                _ => unreachable!(),
+            },
+            selection.difficulty as u8,
+            selection.p1().character as u8,
+            selection.p1().card as u8,
+            selection.p2().character as u8,
+            selection.p2().card as u8,
+        ),
+        source.delay_frames() + delay_frames,
+    ))
+}
+
+#[derive(Clone, Copy)]
+pub struct SpectatorHostOptions {
+    /// 大会などで、配信を見て相手の動きを知られないように観戦を遅らせるフレーム数
+    pub delay_frames: u32,
+    /// 対戦者のチャットを観戦者にも送る
+    pub share_chat: bool,
+}
+
+/// 途中から観戦を始めた観戦者に送るもの。対戦の状態は難易度選択画面からしか再現できないので、
+/// 最後に難易度選択画面にいた時点の状態とそれ以降に送ったメッセージをすべて持っておく。
+/// 再戦では難易度選択画面に戻らないので、上限を超えたら捨てて、次に戻るまで途中からの観戦を断る
+struct Backlog {
+    spectator_initial: SpectatorInitial,
+    round_initial: RoundInitial,
+    messages: Vec<BufferedMessage>,
+}
+
+/// 遅延させて観戦者に届けるもの
+enum Broadcast {
+    Snapshot(SpectatorInitial, RoundInitial),
+    Message(BufferedMessage),
+    Chat(bool, ChatMessage),
+}
+
+#[derive(Getters)]
+pub struct SpectatorHostState {
+    #[get = "pub"]
+    waiting: WaitingForSpectator,
+    sessions: Vec<SpectatorHostSession>,
+    backlog: Option<Backlog>,
+    options: SpectatorHostOptions,
+    /// update が呼ばれた回数
+    frame: u32,
+    /// (積んだ時の frame, 内容)
+    delayed: VecDeque<(u32, Broadcast)>,
+}
+
+impl SpectatorHostState {
+    pub fn new(waiting: WaitingForSpectator, options: SpectatorHostOptions) -> Self {
+        Self {
+            waiting,
+            sessions: Vec::new(),
+            backlog: None,
+            options,
+            frame: 0,
+            delayed: VecDeque::new(),
+        }
+    }
+
+    pub fn count_spectators(&self) -> usize {
+        self.sessions.len()
+    }
+
+    pub fn delay_frames(&self) -> u32 {
+        self.options.delay_frames
+    }
+
+    /// 設定で許可されていれば、対戦者のチャットを観戦と同じだけ遅らせて送る
+    pub fn send_chats(&mut self, chats: Vec<ChatEntry>) {
+        if !self.options.share_chat || chats.is_empty() {
+            return;
+        }
+        for chat in chats {
+            let broadcast = Broadcast::Chat(chat.p1(), chat.message().clone());
+            self.delayed.push_back((self.frame, broadcast));
+        }
+        self.release(false);
+    }
+
+    pub fn send_init_round_if_connected(&mut self, th19: &Th19) {
+        let msg = BufferedMessage::InitRound(current_round_initial(th19));
+        self.delayed
+            .push_back((self.frame, Broadcast::Message(msg)));
+        self.release(false);
+    }
+
+    fn take_snapshot(
+        &mut self,
+        source: &impl SpectatorSource,
+        main_menu: Option<&MainMenu>,
+        th19: &Th19,
+    ) {
+        let Some(main_menu) = main_menu else {
+            return;
+        };
+        let vs_mode = th19.vs_mode();
+        if main_menu.screen_id() != ScreenId::DifficultySelect
+            || vs_mode.p1_card() != 0
+            || vs_mode.p2_card() != 0
+        {
+            return;
+        }
+        let Some(spectator_initial) = create_spectator_initial(
+            main_menu.screen_id(),
+            th19.selection(),
+            source,
+            th19,
+            self.options.delay_frames,
+        ) else {
+            return;
+        };
+        let snapshot = Broadcast::Snapshot(spectator_initial, current_round_initial(th19));
+        self.delayed.push_back((self.frame, snapshot));
+    }
+
+    /// 遅延させる時間が過ぎたものを観戦者に送る。all なら時間に関わらずすべて送る
+    fn release(&mut self, all: bool) {
+        while let Some((frame, _)) = self.delayed.front() {
+            if !all && frame + self.options.delay_frames > self.frame {
+                break;
+            }
+            let (_, broadcast) = self.delayed.pop_front().unwrap();
+            match broadcast {
+                Broadcast::Snapshot(spectator_initial, round_initial) => {
+                    self.backlog = Some(Backlog {
+                        spectator_initial,
+                        round_initial,
+                        messages: Vec::new(),
+                    });
+                }
+                Broadcast::Message(msg) => self.broadcast(msg),
+                Broadcast::Chat(p1, chat) => self.sessions.retain(|session| {
+                    if let Err(err) = session.send_chat(p1, chat.clone()) {
+                        info!("spectator host error: {:?}", err);
+                        return false;
+                    }
+                    true
+                }),
+            }
+        }
+    }
+
+    fn broadcast(&mut self, msg: BufferedMessage) {
+        if let Some(backlog) = &mut self.backlog {
+            if backlog.messages.len() < MAX_BACKLOG_MESSAGES {
+                backlog.messages.push(msg.clone());
+            } else {
+                info!("spectator backlog is full, refuse spectators until difficulty select");
+                self.backlog = None;
+            }
+        }
+        self.sessions.retain(|session| {
+            let result = match &msg {
+                BufferedMessage::InitRound(round_initial) => {
+                    session.send_init_round(round_initial.clone())
+                }
+                BufferedMessage::Inputs(p1_input, p2_input) => {
+                    session.send_inputs(*p1_input, *p2_input)
+                }
+            };
+            if let Err(err) = result {
+                info!("spectator host error: {:?}", err);
+                return false;
+            }
+            true
+        });
+    }
+
+    fn init_session(&self, session: &SpectatorHostSession) -> Result<()> {
+        let Some(backlog) = &self.backlog else {
+            bail!("no state to catch up from.");
+        };
+        session.send_init_spectator(backlog.spectator_initial.clone())?;
+        session.send_init_round(backlog.round_initial.clone())?;
+        for chunk in backlog.messages.chunks(CATCH_UP_CHUNK_SIZE) {
+            session.send_catch_up(chunk.to_vec())?;
+        }
+        Ok(())
+    }
+
+    pub fn update(
+        &mut self,
+        pushed: bool,
+        main_menu: Option<&MainMenu>,
+        th19: &Th19,
+        source: &impl SpectatorSource,
+        p1_input: u16,
+        p2_input: u16,
+    ) {
+        self.take_snapshot(source, main_menu, th19);
+        let msg = BufferedMessage::Inputs(p1_input, p2_input);
+        self.delayed
+            .push_back((self.frame, Broadcast::Message(msg)));
+        self.release(false);
+        if let Some(session) = self.waiting.try_recv_session(pushed, th19) {
+            if let Err(err) = self.init_session(&session) {
+                info!("initialize spectator failed: {:?}", err);
+            } else {
+                self.sessions.push(session);
+            }
+        }
+        self.frame += 1;
+    }
+}
+
+impl Drop for SpectatorHostState {
+    /// 対戦が終わったら、遅らせていた分も観戦者に送っておく
+    fn drop(&mut self) {
+        self.release(true);
+    }
+}
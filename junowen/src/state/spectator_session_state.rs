@@ -2,7 +2,7 @@ mod in_session;
 mod spectator_game;
 mod spectator_select;
 
-use std::{ffi::c_void, mem, sync::mpsc::RecvError};
+use std::{ffi::c_void, mem};
 
 use anyhow::Result;
 use junowen_lib::{
@@ -14,67 +14,110 @@ use junowen_lib::{
     Th19,
 };
 
-use crate::session::spectator::SpectatorSession;
+use crate::{
+    session::{spectator::SpectatorSession, SessionError},
+    signaling::waiting_for_match::{WaitingForPureP2pSpectator, WaitingForSpectator},
+};
 
-use super::prepare::Prepare;
+use super::{
+    prepare::Prepare,
+    spectator_host::{SpectatorHostOptions, SpectatorHostState},
+};
 
 use {spectator_game::SpectatorGame, spectator_select::SpectatorSelect};
 
+/// 観戦しながら、更に別の観戦者に中継できる
 pub enum SpectatorSessionState {
     Null,
-    Prepare(Prepare<SpectatorSession>),
+    Prepare(Prepare<(SpectatorSession, SpectatorHostState)>),
     Select(SpectatorSelect),
-    GameLoading { session: SpectatorSession },
+    GameLoading {
+        session: SpectatorSession,
+        spectator_host_state: SpectatorHostState,
+    },
     Game(SpectatorGame),
-    BackToSelect { session: SpectatorSession },
+    BackToSelect {
+        session: SpectatorSession,
+        spectator_host_state: SpectatorHostState,
+    },
 }
 
 impl SpectatorSessionState {
-    pub fn prepare(session: SpectatorSession) -> Self {
-        Self::Prepare(Prepare::new(session))
+    pub fn prepare(
+        session: SpectatorSession,
+        spectator_host_options: SpectatorHostOptions,
+    ) -> Self {
+        let waiting = WaitingForSpectator::PureP2p(WaitingForPureP2pSpectator::standby());
+        let spectator_host_state = SpectatorHostState::new(waiting, spectator_host_options);
+        Self::Prepare(Prepare::new((session, spectator_host_state)))
     }
 
-    pub fn game_settings(&self) -> Option<&GameSettings> {
-        let init = match self {
+    fn session_and_spectator_host_state(&self) -> (&SpectatorSession, &SpectatorHostState) {
+        match self {
             Self::Null => unreachable!(),
-            Self::GameLoading { session } | Self::BackToSelect { session } => {
-                session.spectator_initial()?
+            Self::GameLoading {
+                session,
+                spectator_host_state,
             }
-            Self::Prepare(i) => i.session().spectator_initial()?,
-            Self::Select(i) => i.session().spectator_initial()?,
-            Self::Game(i) => i.session().spectator_initial()?,
-        };
-        Some(init.game_settings())
+            | Self::BackToSelect {
+                session,
+                spectator_host_state,
+            } => (session, spectator_host_state),
+            Self::Prepare(i) => {
+                let (session, spectator_host_state) = i.session();
+                (session, spectator_host_state)
+            }
+            Self::Select(i) => (i.session(), i.spectator_host_state()),
+            Self::Game(i) => (i.session(), i.spectator_host_state()),
+        }
+    }
+
+    pub fn game_settings(&self) -> Option<&GameSettings> {
+        let (session, _) = self.session_and_spectator_host_state();
+        Some(session.spectator_initial()?.game_settings())
     }
 
-    pub fn inner_spectator_session(self) -> SpectatorSession {
+    fn inner_state(self) -> (SpectatorSession, SpectatorHostState) {
         match self {
             Self::Null => unreachable!(),
-            Self::GameLoading { session } | Self::BackToSelect { session } => session,
+            Self::GameLoading {
+                session,
+                spectator_host_state,
+            }
+            | Self::BackToSelect {
+                session,
+                spectator_host_state,
+            } => (session, spectator_host_state),
             Self::Prepare(inner) => inner.inner_session(),
-            Self::Select(inner) => inner.inner_session(),
-            Self::Game(inner) => inner.inner_session(),
+            Self::Select(inner) => inner.inner_state(),
+            Self::Game(inner) => inner.inner_state(),
         }
     }
 
     pub fn change_to_select(&mut self) {
         let old = mem::replace(self, Self::Null);
-        *self = Self::Select(SpectatorSelect::new(old.inner_spectator_session()));
+        let (session, spectator_host_state) = old.inner_state();
+        *self = Self::Select(SpectatorSelect::new(session, spectator_host_state));
     }
     pub fn change_to_game_loading(&mut self) {
         let old = mem::replace(self, Self::Null);
+        let (session, spectator_host_state) = old.inner_state();
         *self = Self::GameLoading {
-            session: old.inner_spectator_session(),
+            session,
+            spectator_host_state,
         }
     }
     pub fn change_to_game(&mut self) {
         let old = mem::replace(self, Self::Null);
-        *self = Self::Game(SpectatorGame::new(old.inner_spectator_session()));
+        let (session, spectator_host_state) = old.inner_state();
+        *self = Self::Game(SpectatorGame::new(session, spectator_host_state));
     }
     pub fn change_to_back_to_select(&mut self) {
         let old = mem::replace(self, Self::Null);
+        let (session, spectator_host_state) = old.inner_state();
         *self = Self::BackToSelect {
-            session: old.inner_spectator_session(),
+            session,
+            spectator_host_state,
         }
     }
 
@@ -144,14 +187,15 @@ impl SpectatorSessionState {
         &mut self,
         menu: Option<&MainMenu>,
         th19: &mut Th19,
-    ) -> Result<(), RecvError> {
+    ) -> Result<(), SessionError> {
         match self {
             Self::Null => unreachable!(),
             Self::Prepare(prepare) => prepare.update_th19_on_input_players(th19),
             Self::Select(select) => select.update_th19_on_input_players(menu.unwrap(), th19)?,
-            Self::GameLoading { .. } => {
-                if th19.no_wait() {
-                    th19.set_no_wait(false);
+            Self::GameLoading { session, .. } => {
+                let no_wait = session.catching_up();
+                if th19.no_wait() != no_wait {
+                    th19.set_no_wait(no_wait);
                 }
             }
             Self::Game(game) => game.update_th19(th19)?,
@@ -160,7 +204,7 @@ impl SpectatorSessionState {
         Ok(())
     }
 
-    pub fn on_input_menu(&mut self, th19: &mut Th19) -> Result<bool, RecvError> {
+    pub fn on_input_menu(&mut self, th19: &mut Th19) -> Result<bool, SessionError> {
         match self {
             Self::Null => unreachable!(),
             Self::Prepare(prepare) => prepare.update_th19_on_input_menu(th19),
@@ -185,15 +229,7 @@ impl SpectatorSessionState {
     }
 
     pub fn on_render_texts(&self, th19: &Th19, text_renderer: *const c_void) {
-        let session = {
-            match self {
-                Self::Null => unreachable!(),
-                Self::GameLoading { session } | Self::BackToSelect { session } => session,
-                Self::Prepare(inner) => inner.session(),
-                Self::Select(inner) => inner.session(),
-                Self::Game(inner) => inner.session(),
-            }
-        };
+        let (session, spectator_host_state) = self.session_and_spectator_host_state();
         let Some(initial) = session.spectator_initial() else {
             return;
         };
@@ -202,10 +238,13 @@ impl SpectatorSessionState {
             text_renderer,
             initial.p1_name(),
             initial.p2_name(),
+            initial.delay_frames(),
+            spectator_host_state,
+            session.chat_log(),
         );
     }
 
-    pub fn on_round_over(&mut self, th19: &mut Th19) -> Result<(), RecvError> {
+    pub fn on_round_over(&mut self, th19: &mut Th19) -> Result<(), SessionError> {
         let Self::Game(game) = self else {
             return Ok(());
         };
@@ -2,14 +2,32 @@ use std::ffi::c_void;
 
 use junowen_lib::Th19;
 
-use crate::state::render_parts::{render_footer, render_names};
+use crate::{
+    session::ChatLog,
+    state::{
+        render_parts::{render_chat, render_footer, render_names, spectator_host_message},
+        spectator_host::SpectatorHostState,
+    },
+};
 
 pub fn on_render_texts_spectator(
     th19: &Th19,
     text_renderer: *const c_void,
     p1_name: &str,
     p2_name: &str,
+    delay_frames: u32,
+    spectator_host_state: &SpectatorHostState,
+    chat_log: &ChatLog,
 ) {
     render_names(th19, text_renderer, p1_name, p2_name);
-    render_footer(th19, text_renderer, "(Spectating)", "");
+    render_chat(th19, text_renderer, chat_log, p1_name, p2_name);
+    let spectating = if delay_frames == 0 {
+        "(Spectating)".to_owned()
+    } else {
+        format!("(Spectating, delay: {:.1}s)", delay_frames as f64 / 60.0)
+    };
+    let (msg2_rear, msg2_front) = spectator_host_message(spectator_host_state);
+    let msg_front = format!("{} {}", spectating, msg2_front);
+    let msg_rear = format!("{} {}", " ".repeat(spectating.len()), msg2_rear);
+    render_footer(th19, text_renderer, &msg_front, &msg_rear);
 }
@@ -1,24 +1,28 @@
-use std::sync::mpsc::RecvError;
-
 use anyhow::Result;
 use derive_new::new;
 use getset::{Getters, MutGetters};
 use junowen_lib::{structs::input_devices::InputValue, Th19};
 
-use crate::session::spectator::SpectatorSession;
+use crate::{
+    helper::pushed_f1,
+    session::{spectator::SpectatorSession, SessionError},
+    state::spectator_host::SpectatorHostState,
+};
 
 #[derive(new, Getters, MutGetters)]
 pub struct SpectatorGame {
     #[getset(get = "pub", get_mut = "pub")]
     session: SpectatorSession,
+    #[getset(get = "pub")]
+    spectator_host_state: SpectatorHostState,
 }
 
 impl SpectatorGame {
-    pub fn inner_session(self) -> SpectatorSession {
-        self.session
+    pub fn inner_state(self) -> (SpectatorSession, SpectatorHostState) {
+        (self.session, self.spectator_host_state)
     }
 
-    pub fn update_th19(&mut self, th19: &mut Th19) -> Result<(), RecvError> {
+    pub fn update_th19(&mut self, th19: &mut Th19) -> Result<(), SessionError> {
         // -1フレーム目、0フレーム目は複数回呼ばれ、回数が不定なのでスキップする
         if th19.round_frame().unwrap().frame < 1 {
             let input_devices = th19.input_devices_mut();
@@ -38,15 +42,27 @@ impl SpectatorGame {
         input_devices
             .p2_input_mut()
             .set_current((p2 as u32).try_into().unwrap());
+        // 途中から観戦を始めたときは追いつくまで早送りする
+        let no_wait = self.session.catching_up();
+        if th19.no_wait() != no_wait {
+            th19.set_no_wait(no_wait);
+        }
+
+        let chats = self.session.take_new_chats();
+        self.spectator_host_state.send_chats(chats);
+        let current_pushed = pushed_f1(th19.input_devices());
+        self.spectator_host_state
+            .update(current_pushed, None, th19, &self.session, p1, p2);
         Ok(())
     }
 
-    pub fn on_round_over(&mut self, th19: &mut Th19) -> Result<(), RecvError> {
+    pub fn on_round_over(&mut self, th19: &mut Th19) -> Result<(), SessionError> {
         let init = self.session.dequeue_init_round()?;
         th19.set_rand_seed1(init.seed1).unwrap();
         th19.set_rand_seed2(init.seed2).unwrap();
         th19.set_rand_seed3(init.seed3).unwrap();
         th19.set_rand_seed4(init.seed4).unwrap();
+        self.spectator_host_state.send_init_round_if_connected(th19);
         Ok(())
     }
 }
@@ -1,5 +1,3 @@
-use std::sync::mpsc::RecvError;
-
 use anyhow::Result;
 use derive_new::new;
 use getset::{Getters, MutGetters};
@@ -13,26 +11,35 @@ use junowen_lib::{
 };
 use tracing::trace;
 
-use crate::session::spectator::{self, SpectatorSession};
+use crate::{
+    helper::pushed_f1,
+    session::{
+        spectator::{self, SpectatorSession},
+        SessionError,
+    },
+    state::spectator_host::SpectatorHostState,
+};
 
 #[derive(new, Getters, MutGetters)]
 pub struct SpectatorSelect {
     #[getset(get = "pub", get_mut = "pub")]
     session: SpectatorSession,
+    #[getset(get = "pub")]
+    spectator_host_state: SpectatorHostState,
     #[new(value = "0")]
     initializing_state: u8,
 }
 
 impl SpectatorSelect {
-    pub fn inner_session(self) -> SpectatorSession {
-        self.session
+    pub fn inner_state(self) -> (SpectatorSession, SpectatorHostState) {
+        (self.session, self.spectator_host_state)
     }
 
     pub fn update_th19_on_input_players(
         &mut self,
         main_menu: &MainMenu,
         th19: &mut Th19,
-    ) -> Result<(), RecvError> {
+    ) -> Result<(), SessionError> {
         if self.initializing_state == 0 {
             if self.session.spectator_initial().is_none() {
                 self.initializing_state = 1;
@@ -46,6 +53,7 @@ impl SpectatorSelect {
             th19.set_rand_seed2(round_initial.seed2).unwrap();
             th19.set_rand_seed3(round_initial.seed3).unwrap();
             th19.set_rand_seed4(round_initial.seed4).unwrap();
+            self.spectator_host_state.send_init_round_if_connected(th19);
         }
         if main_menu.screen_id() == ScreenId::DifficultySelect {
             return Ok(());
@@ -73,6 +81,11 @@ impl SpectatorSelect {
             .p2_input_mut()
             .set_current((p2 as u32).try_into().unwrap());
 
+        let chats = self.session.take_new_chats();
+        self.spectator_host_state.send_chats(chats);
+        self.spectator_host_state
+            .update(false, Some(main_menu), th19, &self.session, p1, p2);
+
         Ok(())
     }
 
@@ -80,7 +93,7 @@ impl SpectatorSelect {
         &mut self,
         main_menu: &mut MainMenu,
         th19: &mut Th19,
-    ) -> Result<(), RecvError> {
+    ) -> Result<(), SessionError> {
         if main_menu.screen_id() != ScreenId::DifficultySelect {
             return Ok(());
         }
@@ -99,7 +112,7 @@ impl SpectatorSelect {
                     let selection = th19.selection_mut();
                     selection.p1_mut().character = initial_state.p1_character() as u32;
                     selection.p2_mut().character = initial_state.p2_character() as u32;
-                    th19.set_no_wait(false);
+                    th19.set_no_wait(self.session.catching_up());
                     self.initializing_state = 2;
                 }
                 spectator::Screen::CharacterSelect => unimplemented!(),
@@ -111,6 +124,22 @@ impl SpectatorSelect {
         let input = if p1 != 0 { p1 } else { p2 };
         th19.menu_input_mut()
             .set_current((input as u32).try_into().unwrap());
+        let no_wait = self.session.catching_up();
+        if th19.no_wait() != no_wait {
+            th19.set_no_wait(no_wait);
+        }
+
+        let current_pushed = pushed_f1(th19.input_devices());
+        let chats = self.session.take_new_chats();
+        self.spectator_host_state.send_chats(chats);
+        self.spectator_host_state.update(
+            current_pushed,
+            Some(main_menu),
+            th19,
+            &self.session,
+            p1,
+            p2,
+        );
         Ok(())
     }
 }